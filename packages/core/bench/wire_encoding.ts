@@ -0,0 +1,46 @@
+// Client-side cost of encoding CreateWidget frames, JSON-in-msgpack (v1)
+// versus native nested maps (v2). Run with `bun packages/core/bench/wire_encoding.ts`.
+// The Rust-side decode cost is measured by `bench_create_widget_decoding` in
+// src/ipc/server.rs.
+import { encode } from "@msgpack/msgpack";
+import { toWire, type JsToRustMessage } from "../src/bun_bridge.ts";
+
+const WIDGETS = 20_000;
+
+const style = {
+    padding: "8, 16",
+    background: "#2d2d2d",
+    color: "#ffffff",
+    fontSize: 14,
+    cornerRadius: 6,
+    borderWidth: 1,
+    borderColor: "#444444",
+    gap: 8,
+    direction: "row",
+};
+const params = { minValue: 0, maxValue: 100, value: 40 };
+
+const messages: JsToRustMessage[] = Array.from({ length: WIDGETS }, (_, i) => ({
+    type: "createWidget",
+    id: `w${i}`,
+    kind: "slider",
+    parent_id: null,
+    text: null,
+    style: { ...style },
+    params: { ...params },
+    data: null,
+    classes: null,
+}));
+
+for (const [label, native] of [["json (v1)", false], ["native (v2)", true]] as const) {
+    let bytes = 0;
+    const start = performance.now();
+    for (const message of messages) {
+        bytes += encode(toWire(message, native)).byteLength;
+    }
+    const elapsed = performance.now() - start;
+    console.log(
+        `${label}: ${WIDGETS} widgets in ${elapsed.toFixed(1)}ms ` +
+            `(${((elapsed * 1000) / WIDGETS).toFixed(2)} µs/widget, ${(bytes / WIDGETS).toFixed(0)} B/frame)`,
+    );
+}
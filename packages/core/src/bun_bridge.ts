@@ -6,10 +6,52 @@ import path from "node:path";
 import crypto from "node:crypto";
 import { spawn, type ChildProcess } from "node:child_process";
 import { decode, encode } from "@msgpack/msgpack";
+import type { CursorStyle, WindowOptions, WindowState } from "./types.ts";
+
+// `host:port` of an already running Vellum started with `VELLUM_TRANSPORT=tcp`,
+// or the socket path `vellum run` hands to the client it starts. The bridge
+// then connects to it instead of spawning the binary, presenting the token
+// from `VELLUM_SESSION_TOKEN` (the runtime prints where it wrote it).
+const ATTACH_ADDRESS = process.env.VELLUM_ATTACH;
+// Handed to the runtime on spawn; only a client presenting it in `hello` is served
+const SESSION_TOKEN = ATTACH_ADDRESS
+    ? (process.env.VELLUM_SESSION_TOKEN ?? "")
+    : crypto.randomBytes(32).toString("hex");
+
+type SocketLocation = { dir: string; path: string; ownsDir: boolean };
+
+// The socket lives in a directory only this user can enter (0700): the
+// runtime dir when there is one, otherwise a fresh one we remove on exit.
+function createSocketLocation(): SocketLocation {
+    const runtimeDir = process.env.XDG_RUNTIME_DIR;
+    const dir = process.platform === "win32"
+        ? os.tmpdir()
+        : runtimeDir
+            ? path.join(runtimeDir, "vellum")
+            : fs.mkdtempSync(path.join(os.tmpdir(), "vellum-"));
+    if (process.platform !== "win32") {
+        fs.mkdirSync(dir, { recursive: true, mode: 0o700 });
+    }
+    return {
+        dir,
+        path: path.join(dir, `vellum-${crypto.randomBytes(6).toString("hex")}.sock`),
+        ownsDir: process.platform !== "win32" && !runtimeDir,
+    };
+}
 
-const SOCKET_PATH = process.platform === "win32"
-    ? `${os.tmpdir()}\\Vellum_${crypto.randomUUID()}.sock`
-    : `/tmp/Vellum_${crypto.randomUUID()}.sock`;
+type AttachAddress = { path: string } | { host: string; port: number };
+
+function parseAttachAddress(address: string): AttachAddress {
+    if (address.startsWith("/") || address.includes("\\")) {
+        return { path: address };
+    }
+    const separator = address.lastIndexOf(":");
+    const port = Number(address.slice(separator + 1));
+    if (separator <= 0 || !Number.isInteger(port)) {
+        throw new Error(`VELLUM_ATTACH must be host:port or a socket path, got "${address}"`);
+    }
+    return { host: address.slice(0, separator).replace(/^\[|\]$/g, ""), port };
+}
 
 function findVellumBinary(): string {
     const isWin = process.platform === "win32";
@@ -42,8 +84,25 @@ export type BridgeEvent = {
     source?: string;
     message?: string;
     fatal?: boolean;
+    /** Stable error code of a `runtimeError`, e.g. `"widget_not_found"`. */
+    code?: string;
+    /** Command that caused a `runtimeError`, if any. */
+    command?: string;
+    cancelled?: boolean;
+    width?: number;
+    height?: number;
+    ratio?: number;
+    /** Window state of a `windowStateChanged` event. */
+    state?: WindowState;
+    /** Files of a `fileHoverEnter` or `fileDropped` event. */
+    paths?: string[];
+    x?: number;
+    y?: number;
 };
 
+/** A style object as written by the user; encoded per the negotiated features. */
+type WireStyle = object;
+
 export type JsToRustMessage =
     | { type: "setTitle"; title: string }
     | {
@@ -52,41 +111,238 @@ export type JsToRustMessage =
         kind: string;
         parent_id: string | null;
         text: string | null;
-        style_json: string | null;
-        widget_params_json: string | null;
+        style: WireStyle | null;
+        params: object | null;
         data: Uint8Array | null;
+        classes: string[] | null;
     }
+    | { type: "defineStyleClass"; name: string; style: WireStyle }
+    | { type: "setWidgetClasses"; id: string; classes: string[] }
     | { type: "removeWidget"; id: string }
     | { type: "setWidgetText"; id: string; text: string }
     | { type: "setWidgetVisible"; id: string; visible: boolean }
     | { type: "setWidgetValue"; id: string; value: number }
     | { type: "setWidgetChecked"; id: string; checked: boolean }
-    | { type: "setWidgetStyle"; id: string; style_json: string }
+    | { type: "setWidgetStyle"; id: string; style: WireStyle }
     | { type: "setStyleProperty"; id: string; property: string; value: string }
+    | {
+          type: "animate";
+          id: string;
+          keyframes_json: string;
+          duration_ms: number;
+          easing: string | null;
+          iterations: number | null;
+          direction: string | null;
+      }
+    | { type: "cancelAnimation"; id: string }
+    | { type: "setTheme"; theme_json: string }
+    | { type: "setThemeVariant"; variant: string | null }
+    | { type: "setStyleVariables"; variables: Record<string, string | null> }
     | { type: "resizeWindow"; width: number; height: number }
     | { type: "closeWindow" }
+    | { type: "setWindowMinimized"; minimized: boolean }
+    | { type: "setWindowMaximized"; maximized: boolean }
+    | { type: "setWindowFullscreen"; fullscreen: boolean }
+    | { type: "setWindowPosition"; x: number; y: number }
+    | { type: "setWindowMinSize"; width: number | null; height: number | null }
+    | { type: "setWindowMaxSize"; width: number | null; height: number | null }
+    | { type: "setWindowAlwaysOnTop"; always_on_top: boolean }
+    | { type: "setWindowDecorations"; decorations: boolean }
+    | { type: "setWindowIcon"; data: Uint8Array | null }
+    | { type: "requestWindowAttention"; critical: boolean }
+    | { type: "getWindowState" }
+    | { type: "setCursor"; cursor: CursorStyle | null }
     | { type: "exitApp" }
-    | { type: "setImageData"; id: string; data: Uint8Array }
+    | { type: "setImageData"; id: string; data: Uint8Array | null }
     | { type: "playVideo"; id: string }
     | { type: "pauseVideo"; id: string }
-    | { type: "seekVideo"; id: string; time_secs: number };
+    | { type: "seekVideo"; id: string; time_secs: number }
+    | { type: "getWidgetRect"; id: string }
+    | { type: "getWindowSize" }
+    | { type: "hitTest"; x: number; y: number }
+    | { type: "observeResize"; id: string }
+    | { type: "unobserveResize"; id: string }
+    | { type: "observeIntersection"; id: string; root_id: string | null; threshold: number }
+    | { type: "unobserveIntersection"; id: string };
+
+/**
+ * Any message may carry a `request_id`, which Rust answers with a `response` frame.
+ * `buffer_id` replaces `data` when the payload went through a shared buffer.
+ */
+type JsToRustFrame = JsToRustMessage & { request_id?: number; buffer_id?: string };
+
+/** Wire protocol version spoken by this client; see `src/ipc/handshake.rs`. */
+export const PROTOCOL_VERSION = 2;
+
+/** Optional protocol features this client uses. */
+const CLIENT_CAPABILITIES = [
+    "requests",
+    "typed_errors",
+    "observers",
+    "geometry_queries",
+    "native_styles",
+    "window_control",
+    "cursor",
+    ...(process.platform === "linux" ? ["shared_buffers"] : []),
+];
+
+const VAR_REFERENCE = /^\s*var\(.+\)\s*$/;
+
+/**
+ * Style as a nested msgpack map for `native_styles`. Whole-value `var(...)`
+ * references move to `varRefs`, since Rust decodes the rest into typed fields.
+ */
+function toNativeStyle(style: WireStyle): WireStyle {
+    const native: Record<string, unknown> = {};
+    let varRefs: Record<string, string> | null = null;
+    for (const [property, value] of Object.entries(style)) {
+        if (value === undefined) {
+            continue;
+        }
+        if (property !== "varRefs" && typeof value === "string" && VAR_REFERENCE.test(value)) {
+            varRefs ??= {};
+            varRefs[property] = value.trim();
+        } else {
+            native[property] = value;
+        }
+    }
+    if (varRefs) {
+        const existing = (style as { varRefs?: Record<string, string> }).varRefs;
+        native.varRefs = { ...existing, ...varRefs };
+    }
+    return native;
+}
+
+/**
+ * Encode styles and widget params natively, or as the JSON strings older
+ * servers (without `native_styles`) expect.
+ */
+export function toWire(frame: JsToRustFrame, nativeStyles: boolean): object {
+    switch (frame.type) {
+        case "createWidget": {
+            const { style, params, ...rest } = frame;
+            return nativeStyles
+                ? { ...rest, style: style && toNativeStyle(style), params }
+                : {
+                      ...rest,
+                      style_json: style ? JSON.stringify(style) : null,
+                      widget_params_json: params ? JSON.stringify(params) : null,
+                  };
+        }
+        case "defineStyleClass":
+        case "setWidgetStyle": {
+            const { style, ...rest } = frame;
+            return nativeStyles
+                ? { ...rest, style: toNativeStyle(style) }
+                : { ...rest, style_json: JSON.stringify(style) };
+        }
+        default:
+            return frame;
+    }
+}
+
+/** Payloads at least this large go through a shared buffer when the server supports it. */
+const SHARED_BUFFER_THRESHOLD = 256 * 1024;
+const SHARED_BUFFER_DIR = "/dev/shm";
+
+/**
+ * Write a large payload to a `/dev/shm` file for the server to read, instead
+ * of copying it through the socket. Returns null if it should be sent inline.
+ */
+function toSharedBuffer(data: Uint8Array | null): { buffer_id: string; path: string; len: number } | null {
+    if (!data || data.byteLength < SHARED_BUFFER_THRESHOLD) {
+        return null;
+    }
+    const bufferId = crypto.randomUUID();
+    const bufferPath = path.join(SHARED_BUFFER_DIR, `vellum-${bufferId}`);
+    try {
+        // Owner-only, so other users can't read the payload
+        fs.writeFileSync(bufferPath, data, { mode: 0o600 });
+    } catch {
+        return null;
+    }
+    return { buffer_id: bufferId, path: bufferPath, len: data.byteLength };
+}
+
+type HelloMessage = {
+    type: "hello";
+    protocol_version: number;
+    client_name: string;
+    capabilities: string[];
+    token: string;
+    /** Initial window attributes; the window opens once it has them. */
+    window?: WindowOptions;
+};
+
+/** The server's answer to the handshake: what it supports and which features were agreed on. */
+export type Welcome = {
+    server_version: string;
+    protocol_version: number;
+    supported_widget_kinds: string[];
+    features: string[];
+    /** Largest frame payload the server accepts, in bytes. */
+    max_frame_len?: number;
+};
+
+/**
+ * A typed Rust-side error. `code` is stable (e.g. `"widget_not_found"`,
+ * `"unsupported_operation"`); other fields depend on the code.
+ */
+export type ErrorReport = {
+    code: string;
+    message: string;
+    widget_id?: string | null;
+    command?: string;
+    [field: string]: unknown;
+};
 
 type RustToJsMessage =
     | { type: "uiEvent"; event: unknown }
-    | { type: "runtimeError"; source: string; message: string; fatal: boolean }
+    | ({ type: "runtimeError"; source: string; fatal: boolean } & ErrorReport)
+    | { type: "response"; request_id: number; result?: unknown; error?: ErrorReport }
+    | ({ type: "welcome" } & Welcome)
     | { type: "shutdown" };
 
+/** Rejection reason of a request whose command failed on the Rust side. */
+export class VellumCommandError extends Error {
+    readonly code: string;
+    readonly widgetId: string | null;
+
+    constructor(
+        readonly report: ErrorReport,
+        readonly command: JsToRustMessage["type"],
+    ) {
+        super(report.message);
+        this.name = "VellumCommandError";
+        this.code = report.code;
+        this.widgetId = report.widget_id ?? null;
+    }
+}
+
 export type Bridge = {
     send(message: JsToRustMessage): void;
+    /** Send a message and resolve with its result once Rust has handled it. */
+    request(message: JsToRustMessage): Promise<unknown>;
     onEvent(callback: (event: BridgeEvent) => void): () => void;
+    /** Resolves once the server accepted the handshake. */
+    ready: Promise<Welcome>;
+    /**
+     * Send `options` with the handshake. Returns false once it was sent, when
+     * the window exists already.
+     */
+    configureWindow(options: WindowOptions): boolean;
 };
 
 type VellumGlobal = typeof globalThis & {
     __Vellum_BRIDGE__?: Bridge;
 };
 
-function writeFrame(socket: net.Socket, message: JsToRustMessage): void {
+function writeFrame(socket: net.Socket, message: object, maxFrameLen = Infinity): void {
     const payload = Buffer.from(encode(message));
+    // The server closes the connection on oversized frames, so fail this one instead
+    if (payload.length > maxFrameLen) {
+        throw new Error(`Vellum frame of ${payload.length} bytes exceeds the server limit of ${maxFrameLen}`);
+    }
     const frame = Buffer.allocUnsafe(4 + payload.length);
     frame.writeUInt32LE(payload.length, 0);
     payload.copy(frame, 4);
@@ -94,6 +350,65 @@ function writeFrame(socket: net.Socket, message: JsToRustMessage): void {
 }
 
 function mapUiEvent(event: unknown): BridgeEvent {
+    const animationEnded = (event as { AnimationEnded?: { widget_id?: string; cancelled?: boolean } })
+        ?.AnimationEnded;
+    if (animationEnded) {
+        return {
+            type: "animationEnded",
+            widgetId: animationEnded.widget_id,
+            cancelled: animationEnded.cancelled ?? false,
+        };
+    }
+
+    const widgetResized = (event as {
+        WidgetResized?: { widget_id?: string; width?: number; height?: number };
+    })?.WidgetResized;
+    if (widgetResized) {
+        return {
+            type: "widgetResized",
+            widgetId: widgetResized.widget_id,
+            width: widgetResized.width,
+            height: widgetResized.height,
+        };
+    }
+
+    const windowState = (event as { WindowStateChanged?: WindowState })?.WindowStateChanged;
+    if (windowState) {
+        return { type: "windowStateChanged", state: windowState };
+    }
+
+    const fileHover = (event as {
+        FileHoverEnter?: { paths?: string[]; x?: number | null; y?: number | null; target_widget_id?: string | null };
+    })?.FileHoverEnter;
+    const fileDropped = (event as {
+        FileDropped?: { paths?: string[]; x?: number | null; y?: number | null; target_widget_id?: string | null };
+    })?.FileDropped;
+    const fileDrag = fileHover ?? fileDropped;
+    if (fileDrag) {
+        return {
+            type: fileHover ? "fileHoverEnter" : "fileDropped",
+            // The innermost widget under the pointer, so drop zones can filter on it
+            widgetId: fileDrag.target_widget_id ?? undefined,
+            paths: fileDrag.paths ?? [],
+            x: fileDrag.x ?? undefined,
+            y: fileDrag.y ?? undefined,
+        };
+    }
+    if ((event as { FileHoverLeave?: object })?.FileHoverLeave) {
+        return { type: "fileHoverLeave" };
+    }
+
+    const intersectionChanged = (event as {
+        IntersectionChanged?: { widget_id?: string; ratio?: number };
+    })?.IntersectionChanged;
+    if (intersectionChanged) {
+        return {
+            type: "intersectionChanged",
+            widgetId: intersectionChanged.widget_id,
+            ratio: intersectionChanged.ratio,
+        };
+    }
+
     const widgetAction = (event as { WidgetAction?: { widget_id?: string; action?: unknown } })?.WidgetAction;
     if (!widgetAction) {
         return { type: "unknown" };
@@ -138,17 +453,62 @@ export function initVellumBridge(): Bridge {
 
     const listeners = new Set<(event: BridgeEvent) => void>();
     let readBuffer = Buffer.alloc(0);
-    const messageQueue: JsToRustMessage[] = [];
+    const messageQueue: JsToRustFrame[] = [];
     let isConnected = false;
+    // Set once the server answered the handshake; frames are queued until then
+    let isReady = false;
+    let nativeStyles = false;
+    let sharedBuffers = false;
+    let maxFrameLen = Infinity;
+    let startupWindow: WindowOptions | null = null;
+    let helloSent = false;
+
+    // Large image payloads are registered as a shared buffer (which the server
+    // maps and unlinks), referenced by the command, then released.
+    const writeMessage = (frame: JsToRustFrame) => {
+        const shared =
+            sharedBuffers && (frame.type === "createWidget" || frame.type === "setImageData")
+                ? toSharedBuffer(frame.data)
+                : null;
+        if (!shared) {
+            writeFrame(socket!, toWire(frame, nativeStyles), maxFrameLen);
+            return;
+        }
+        writeFrame(socket!, { type: "registerBuffer", ...shared });
+        const wire = toWire({ ...frame, data: null, buffer_id: shared.buffer_id }, nativeStyles);
+        writeFrame(socket!, wire, maxFrameLen);
+        writeFrame(socket!, { type: "releaseBuffer", buffer_id: shared.buffer_id });
+    };
     let socket: net.Socket | null = null;
+    let nextRequestId = 1;
+    const pendingRequests = new Map<
+        number,
+        { command: JsToRustMessage["type"]; resolve: (result: unknown) => void; reject: (err: Error) => void }
+    >();
+
+    let resolveReady: (welcome: Welcome) => void = () => {};
+    const ready = new Promise<Welcome>((resolve) => {
+        resolveReady = resolve;
+    });
+
+    const sendFrame = (frame: JsToRustFrame) => {
+        if (isReady && socket && !socket.destroyed) {
+            writeMessage(frame);
+        } else {
+            messageQueue.push(frame);
+        }
+    };
 
     const bridge: Bridge = {
         send(message) {
-            if (isConnected && socket && !socket.destroyed) {
-                writeFrame(socket, message);
-            } else {
-                messageQueue.push(message);
-            }
+            sendFrame(message);
+        },
+        request(message) {
+            const requestId = nextRequestId++;
+            return new Promise((resolve, reject) => {
+                pendingRequests.set(requestId, { command: message.type, resolve, reject });
+                sendFrame({ ...message, request_id: requestId });
+            });
         },
         onEvent(callback) {
             listeners.add(callback);
@@ -156,28 +516,47 @@ export function initVellumBridge(): Bridge {
                 listeners.delete(callback);
             };
         },
+        ready,
+        configureWindow(options) {
+            if (helloSent) {
+                return false;
+            }
+            startupWindow = { ...startupWindow, ...options };
+            return true;
+        },
     };
 
     globalScope.__Vellum_BRIDGE__ = bridge;
 
-    const binPath = findVellumBinary();
-    const VellumProcess = spawn(binPath, [], {
-        env: { ...process.env, VELLUM_SOCKET: SOCKET_PATH },
-        stdio: "inherit",
-    });
+    const attach = ATTACH_ADDRESS ? parseAttachAddress(ATTACH_ADDRESS) : null;
+    const location = attach ? null : createSocketLocation();
 
-    VellumProcess.on("error", (err) => {
-        process.stderr.write(`[Vellum bridge] Failed to start Vellum binary: ${String(err)}\n`);
-        process.exit(1);
-    });
+    if (location) {
+        const binPath = findVellumBinary();
+        const VellumProcess = spawn(binPath, [], {
+            env: { ...process.env, VELLUM_SOCKET: location.path, VELLUM_SESSION_TOKEN: SESSION_TOKEN },
+            stdio: "inherit",
+        });
 
-    VellumProcess.on("exit", (code) => {
-        process.exit(code ?? 0);
-    });
+        VellumProcess.on("error", (err) => {
+            process.stderr.write(`[Vellum bridge] Failed to start Vellum binary: ${String(err)}\n`);
+            process.exit(1);
+        });
 
-    process.on("exit", () => {
-        VellumProcess.kill();
-    });
+        VellumProcess.on("exit", (code) => {
+            process.exit(code ?? 0);
+        });
+
+        process.on("exit", () => {
+            VellumProcess.kill();
+            if (location.ownsDir) {
+                fs.rmSync(location.dir, { recursive: true, force: true });
+            }
+        });
+    }
+
+    // A fatal runtime error (every error under VELLUM_STRICT) fails the process
+    let sawFatalError = false;
 
     const emitEvent = (event: BridgeEvent) => {
         for (const listener of listeners) {
@@ -196,21 +575,54 @@ export function initVellumBridge(): Bridge {
                 emitEvent(mapUiEvent(message.event));
                 return;
             }
+            if (message?.type === "welcome") {
+                // Commands queued before the handshake go out in order now
+                const { type: _, ...welcome } = message;
+                isReady = true;
+                nativeStyles = welcome.features.includes("native_styles");
+                sharedBuffers = welcome.features.includes("shared_buffers");
+                maxFrameLen = welcome.max_frame_len ?? Infinity;
+                for (const msg of messageQueue) {
+                    writeMessage(msg);
+                }
+                messageQueue.length = 0;
+                resolveReady(welcome);
+                return;
+            }
+            if (message?.type === "response") {
+                const pending = pendingRequests.get(message.request_id);
+                if (!pending) {
+                    return;
+                }
+                pendingRequests.delete(message.request_id);
+                if (message.error !== undefined && message.error !== null) {
+                    pending.reject(new VellumCommandError(message.error, pending.command));
+                } else {
+                    pending.resolve(message.result ?? null);
+                }
+                return;
+            }
             if (message?.type === "runtimeError") {
+                if (message.fatal) {
+                    sawFatalError = true;
+                }
                 emitEvent({
                     type: "runtimeError",
                     source: message.source,
                     message: message.message,
                     fatal: message.fatal,
+                    code: message.code,
+                    command: message.command,
+                    widgetId: message.widget_id ?? undefined,
                 });
                 process.stderr.write(
-                    `[Vellum bridge] Rust runtime error (${message.source}, fatal=${String(message.fatal)}): ${message.message}\n`,
+                    `[Vellum bridge] Rust runtime error (${message.source}, ${message.code}, fatal=${String(message.fatal)}): ${message.message}\n`,
                 );
                 return;
             }
             if (message?.type === "shutdown") {
                 if (socket) socket.end();
-                process.exit(0);
+                process.exit(sawFatalError ? 1 : 0);
             }
         } catch (err) {
             process.stderr.write(`[Vellum bridge] Decode error: ${String(err)}\n`);
@@ -231,13 +643,18 @@ export function initVellumBridge(): Bridge {
     };
 
     function tryConnect(retries = 20) {
-        socket = net.createConnection(SOCKET_PATH, () => {
+        const onConnect = () => {
             isConnected = true;
-
-            for (const msg of messageQueue) {
-                writeFrame(socket!, msg);
-            }
-            messageQueue.length = 0;
+            const hello: HelloMessage = {
+                type: "hello",
+                protocol_version: PROTOCOL_VERSION,
+                client_name: `@vellum-ui/core (bun ${process.versions.bun ?? "unknown"})`,
+                capabilities: CLIENT_CAPABILITIES,
+                token: SESSION_TOKEN,
+                ...(startupWindow ? { window: startupWindow } : {}),
+            };
+            writeFrame(socket!, hello);
+            helloSent = true;
 
             socket!.on("data", (chunk) => {
                 readBuffer = Buffer.concat([readBuffer, Buffer.from(chunk)]);
@@ -245,14 +662,17 @@ export function initVellumBridge(): Bridge {
             });
 
             socket!.on("end", () => {
-                process.exit(0);
+                process.exit(sawFatalError ? 1 : 0);
             });
 
             socket!.on("error", (err) => {
                 process.stderr.write(`[Vellum bridge] Socket connection error: ${String(err)}\n`);
                 process.exit(1);
             });
-        });
+        };
+        socket = attach
+            ? net.createConnection(attach, onConnect)
+            : net.createConnection(location!.path, onConnect);
 
         socket.on("error", (err) => {
             if (!isConnected) {
@@ -9,17 +9,37 @@ import type {
     SvgParams,
     TextInputParams,
     VideoParams,
+    VellumTheme,
+    AnimationKeyframe,
+    AnimationOptions,
+    StyleTransition,
+    StyleTransform,
+    Rect,
+    WidgetRect,
+    WindowSize,
+    WindowOptions,
+    WindowState,
+    CursorStyle,
+    IntersectionOptions,
 } from "./types.ts";
 import {
+    animate,
+    cancelAnimation,
     closeWindow,
+    configureWindow,
     createWidget,
+    defineStyleClass,
     exit,
     removeWidget,
     resizeWindow,
     setImageData,
     setStyleProperty,
+    setStyleVariables,
     setTitle,
+    setTheme,
+    setThemeVariant,
     setWidgetChecked,
+    setWidgetClasses,
     setWidgetStyle,
     setWidgetText,
     setWidgetValue,
@@ -27,7 +47,28 @@ import {
     playVideo,
     pauseVideo,
     seekVideo,
+    getWidgetRect,
+    getWindowSize,
+    getWindowState,
+    requestWindowAttention,
+    setCursor,
+    setWindowAlwaysOnTop,
+    setWindowDecorations,
+    setWindowFullscreen,
+    setWindowIcon,
+    setWindowMaxSize,
+    setWindowMaximized,
+    setWindowMinSize,
+    setWindowMinimized,
+    setWindowPosition,
+    hitTest,
+    observeResize,
+    unobserveResize,
+    observeIntersection,
+    unobserveIntersection,
 } from "./ops.ts";
+export { request } from "./ops.ts";
+export { PROTOCOL_VERSION, VellumCommandError, type ErrorReport, type Welcome } from "./bun_bridge.ts";
 import { events } from "./events.ts";
 
 let widgetIdCounter = 0;
@@ -37,17 +78,84 @@ export function nextId(): string {
 }
 
 export const window = {
+    configure: configureWindow,
     setTitle,
     resize: resizeWindow,
     close: closeWindow,
+    getSize: getWindowSize,
+    minimize: (): void => setWindowMinimized(true),
+    maximize: (): void => setWindowMaximized(true),
+    /** Leave the minimized, maximized and fullscreen states. */
+    restore: (): void => {
+        setWindowFullscreen(false);
+        setWindowMaximized(false);
+        setWindowMinimized(false);
+    },
+    setFullscreen: setWindowFullscreen,
+    setPosition: setWindowPosition,
+    setMinSize: setWindowMinSize,
+    setMaxSize: setWindowMaxSize,
+    setAlwaysOnTop: setWindowAlwaysOnTop,
+    setDecorations: setWindowDecorations,
+    setIcon: setWindowIcon,
+    requestAttention: requestWindowAttention,
+    getState: getWindowState,
+    setCursor,
+    /**
+     * Called with the window state once connected, after window commands,
+     * and when the window is laid out again (e.g. resized or maximized by the user).
+     */
+    onStateChange: (callback: (state: WindowState) => void): (() => void) =>
+        events.on("windowStateChanged", (event) => {
+            if (event.state) {
+                callback(event.state);
+            }
+        }),
+    /**
+     * Called when files from the OS are dropped on the window. The position
+     * and `widgetId` are best-effort and usually missing: the windowing layer
+     * doesn't report the pointer during an OS drag. Don't rely on them to
+     * pick a drop zone.
+     */
+    onFileDrop: (callback: (drop: FileDrop) => void): (() => void) =>
+        events.on("fileDropped", (event) => callback(toFileDrop(event))),
+    /** Called with the files when a drag enters the window, and with `null` when it leaves. */
+    onFileHover: (callback: (hover: FileDrop | null) => void): (() => void) => {
+        const offEnter = events.on("fileHoverEnter", (event) => callback(toFileDrop(event)));
+        const offLeave = events.on("fileHoverLeave", () => callback(null));
+        return () => {
+            offEnter();
+            offLeave();
+        };
+    },
 };
 
+/** Files dragged or dropped onto the window. */
+export interface FileDrop {
+    paths: string[];
+    /** Pointer position in window coordinates, when known (best-effort). */
+    x?: number;
+    y?: number;
+    /** Innermost widget under the pointer, when the position is known (best-effort). */
+    widgetId?: string;
+}
+
+function toFileDrop(event: VellumEvent): FileDrop {
+    return { paths: event.paths ?? [], x: event.x, y: event.y, widgetId: event.widgetId };
+}
+
 export const body = {
     setStyle: (style: VellumStyle): void => setWidgetStyle("__root__", style),
     setStyleProperty: (property: string, value: string | number): void =>
         setStyleProperty("__root__", property, String(value)),
 };
 
+export const theme = {
+    set: setTheme,
+    setVariant: setThemeVariant,
+    setVariables: setStyleVariables,
+};
+
 export const ui = {
     createWidget: (
         id: string,
@@ -56,8 +164,19 @@ export const ui = {
         text: string | null,
         style: BoxStyle | null,
         params?: object | null,
-        data?: Uint8Array | null
-    ): void => createWidget(id, kind, parentId ?? null, text ?? null, style ?? null, params ?? null, data ?? null),
+        data?: Uint8Array | null,
+        classes?: string[] | null
+    ): void =>
+        createWidget(
+            id,
+            kind,
+            parentId ?? null,
+            text ?? null,
+            style ?? null,
+            params ?? null,
+            data ?? null,
+            classes ?? null
+        ),
     removeWidget,
     setText: setWidgetText,
     setVisible: setWidgetVisible,
@@ -65,10 +184,20 @@ export const ui = {
     setChecked: setWidgetChecked,
     setStyle: setWidgetStyle,
     setStyleProperty,
+    setClasses: setWidgetClasses,
+    defineClass: defineStyleClass,
+    animate,
+    cancelAnimation,
     setImageData,
     playVideo,
     pauseVideo,
     seekVideo,
+    getRect: getWidgetRect,
+    hitTest,
+    observeResize,
+    unobserveResize,
+    observeIntersection,
+    unobserveIntersection,
 
     setWidgetText,
     setWidgetVisible,
@@ -206,7 +335,7 @@ export function portal(id: string, parentId: string | null, style?: VellumStyle)
 }
 
 export { exit };
-export type { VellumStyle, VellumEvent, BoxStyle };
+export type { VellumStyle, VellumEvent, BoxStyle, VellumTheme, AnimationKeyframe, AnimationOptions, StyleTransition, StyleTransform, Rect, WidgetRect, WindowSize, WindowOptions, WindowState, CursorStyle, IntersectionOptions };
 
 export function image(
     id: string,
@@ -250,6 +379,7 @@ export function video(
 export const app = {
     window,
     body,
+    theme,
     ui,
     events,
     nextId,
@@ -1,4 +1,15 @@
-import type { VellumStyle } from "./types.ts";
+import type {
+    AnimationKeyframe,
+    AnimationOptions,
+    CursorStyle,
+    IntersectionOptions,
+    VellumStyle,
+    VellumTheme,
+    WidgetRect,
+    WindowOptions,
+    WindowSize,
+    WindowState,
+} from "./types.ts";
 import { ensureBridge, type BridgeEvent, type Bridge, type JsToRustMessage } from "./bun_bridge.ts";
 
 const bridge: Bridge = ensureBridge();
@@ -9,6 +20,14 @@ export function onBridgeEvent(callback: (event: BridgeEvent) => void): () => voi
     return bridge.onEvent(callback);
 }
 
+/**
+ * Send a command and wait until the UI thread handled it. Resolves with the
+ * command's result (or `null`) and rejects with a `VellumCommandError` if it failed.
+ */
+export function request<T = unknown>(message: JsToRustMessage): Promise<T> {
+    return bridge.request(message) as Promise<T>;
+}
+
 export function setTitle(title: string): void {
     bridge.send({ type: "setTitle", title });
 }
@@ -17,10 +36,87 @@ export function resizeWindow(width: number, height: number): void {
     bridge.send({ type: "resizeWindow", width, height });
 }
 
+/**
+ * Initial window attributes. They travel with the handshake, so the window
+ * opens with them, as long as this runs right after importing `@vellum/core`
+ * (before the first `await`). Later calls only change the title and size.
+ */
+export function configureWindow(options: WindowOptions): void {
+    if (bridge.configureWindow(options)) {
+        return;
+    }
+    if (options.title !== undefined) {
+        setTitle(options.title);
+    }
+    if (options.width !== undefined && options.height !== undefined) {
+        resizeWindow(options.width, options.height);
+    }
+}
+
 export function closeWindow(): void {
     bridge.send({ type: "closeWindow" });
 }
 
+export function setWindowMinimized(minimized: boolean): void {
+    bridge.send({ type: "setWindowMinimized", minimized });
+}
+
+export function setWindowMaximized(maximized: boolean): void {
+    bridge.send({ type: "setWindowMaximized", maximized });
+}
+
+/** Borderless fullscreen on the monitor the window is on. */
+export function setWindowFullscreen(fullscreen: boolean): void {
+    bridge.send({ type: "setWindowFullscreen", fullscreen });
+}
+
+export function setWindowPosition(x: number, y: number): void {
+    bridge.send({ type: "setWindowPosition", x, y });
+}
+
+/** Smallest size the window may be resized to; `null` removes the limit. */
+export function setWindowMinSize(size: WindowSize | null): void {
+    bridge.send({ type: "setWindowMinSize", width: size?.width ?? null, height: size?.height ?? null });
+}
+
+/** Largest size the window may be resized to; `null` removes the limit. */
+export function setWindowMaxSize(size: WindowSize | null): void {
+    bridge.send({ type: "setWindowMaxSize", width: size?.width ?? null, height: size?.height ?? null });
+}
+
+export function setWindowAlwaysOnTop(alwaysOnTop: boolean): void {
+    bridge.send({ type: "setWindowAlwaysOnTop", always_on_top: alwaysOnTop });
+}
+
+export function setWindowDecorations(decorations: boolean): void {
+    bridge.send({ type: "setWindowDecorations", decorations });
+}
+
+/** Encoded image (PNG, ICO, ...) for the window icon; `null` restores the default. */
+export function setWindowIcon(data: Uint8Array | null): void {
+    bridge.send({ type: "setWindowIcon", data });
+}
+
+/**
+ * Flash the taskbar entry or bounce the dock icon until the window is
+ * focused. `critical` keeps at it where the platform distinguishes the two.
+ */
+export function requestWindowAttention(critical = false): void {
+    bridge.send({ type: "requestWindowAttention", critical });
+}
+
+export function getWindowState(): Promise<WindowState> {
+    return request<WindowState>({ type: "getWindowState" });
+}
+
+/**
+ * Show `cursor` over the whole window regardless of widget styles, e.g.
+ * while dragging; `null` goes back to the styles.
+ */
+export function setCursor(cursor: CursorStyle | null): void {
+    bridge.send({ type: "setCursor", cursor });
+}
+
 export function createWidget(
     id: string,
     kind: string,
@@ -28,7 +124,8 @@ export function createWidget(
     text: string | null,
     style: VellumStyle | null,
     params?: object | null,
-    data?: Uint8Array | null
+    data?: Uint8Array | null,
+    classes?: string[] | null
 ): void {
     bridge.send({
         type: "createWidget",
@@ -36,12 +133,21 @@ export function createWidget(
         kind,
         parent_id: parentId ?? null,
         text: text ?? null,
-        style_json: style ? JSON.stringify(style) : null,
-        widget_params_json: params ? JSON.stringify(params) : null,
+        style: style ?? null,
+        params: params ?? null,
         data: data ?? null,
+        classes: classes && classes.length > 0 ? classes : null,
     });
 }
 
+export function defineStyleClass(name: string, style: VellumStyle): void {
+    bridge.send({ type: "defineStyleClass", name, style });
+}
+
+export function setWidgetClasses(id: string, classes: string[]): void {
+    bridge.send({ type: "setWidgetClasses", id, classes });
+}
+
 export function removeWidget(id: string): void {
     bridge.send({ type: "removeWidget", id });
 }
@@ -63,7 +169,27 @@ export function setWidgetChecked(id: string, checked: boolean): void {
 }
 
 export function setWidgetStyle(id: string, style: VellumStyle): void {
-    bridge.send({ type: "setWidgetStyle", id, style_json: JSON.stringify(style) });
+    bridge.send({ type: "setWidgetStyle", id, style });
+}
+
+/**
+ * Animate style properties of a widget on the UI thread. Listen for
+ * `animationEnded` events to know when it finished or was cancelled.
+ */
+export function animate(id: string, keyframes: AnimationKeyframe[], options: AnimationOptions): void {
+    bridge.send({
+        type: "animate",
+        id,
+        keyframes_json: JSON.stringify(keyframes),
+        duration_ms: options.duration,
+        easing: options.easing ?? null,
+        iterations: options.iterations ?? null,
+        direction: options.direction ?? null,
+    });
+}
+
+export function cancelAnimation(id: string): void {
+    bridge.send({ type: "cancelAnimation", id });
 }
 
 export function setStyleProperty(
@@ -74,6 +200,26 @@ export function setStyleProperty(
     bridge.send({ type: "setStyleProperty", id, property, value: String(value) });
 }
 
+export function setTheme(theme: VellumTheme): void {
+    bridge.send({ type: "setTheme", theme_json: JSON.stringify(theme) });
+}
+
+export function setThemeVariant(variant: string | null): void {
+    bridge.send({ type: "setThemeVariant", variant });
+}
+
+/**
+ * Set style variables referenced as `var(--name)` in style values.
+ * A `null` value removes the variable; widgets using it are restyled automatically.
+ */
+export function setStyleVariables(variables: Record<string, string | number | null>): void {
+    const normalized: Record<string, string | null> = {};
+    for (const [name, value] of Object.entries(variables)) {
+        normalized[name] = value === null ? null : String(value);
+    }
+    bridge.send({ type: "setStyleVariables", variables: normalized });
+}
+
 export function exit(): void {
     bridge.send({ type: "exitApp" });
 }
@@ -93,3 +239,48 @@ export function pauseVideo(id: string): void {
 export function seekVideo(id: string, timeSecs: number): void {
     bridge.send({ type: "seekVideo", id, time_secs: timeSecs });
 }
+
+/** Bounds of a widget after the last layout pass. */
+export function getWidgetRect(id: string): Promise<WidgetRect> {
+    return request<WidgetRect>({ type: "getWidgetRect", id });
+}
+
+export function getWindowSize(): Promise<WindowSize> {
+    return request<WindowSize>({ type: "getWindowSize" });
+}
+
+/** Id of the innermost widget under a window point, or `null`. */
+export function hitTest(x: number, y: number): Promise<string | null> {
+    return request<string | null>({ type: "hitTest", x, y });
+}
+
+/**
+ * Emit `widgetResized` events (with `width` and `height`) whenever the widget
+ * is laid out at a new size, starting with its current size.
+ */
+export function observeResize(id: string): void {
+    bridge.send({ type: "observeResize", id });
+}
+
+export function unobserveResize(id: string): void {
+    bridge.send({ type: "unobserveResize", id });
+}
+
+/**
+ * Emit `intersectionChanged` events (with the visible `ratio`) whenever the
+ * widget's visible fraction crosses `threshold`, starting with its current
+ * visibility. Visibility is clipped by scroll containers up to `rootId`,
+ * or the window.
+ */
+export function observeIntersection(id: string, options: IntersectionOptions = {}): void {
+    bridge.send({
+        type: "observeIntersection",
+        id,
+        root_id: options.rootId ?? null,
+        threshold: options.threshold ?? 0,
+    });
+}
+
+export function unobserveIntersection(id: string): void {
+    bridge.send({ type: "unobserveIntersection", id });
+}
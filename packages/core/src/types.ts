@@ -8,6 +8,24 @@ export type MainAlign =
     | "space-around"
     | "space-evenly";
 
+/** Mouse cursor shapes, named as in CSS. */
+export type CursorStyle =
+    | "default"
+    | "pointer"
+    | "text"
+    | "grab"
+    | "grabbing"
+    | "move"
+    | "ew-resize"
+    | "ns-resize"
+    | "nesw-resize"
+    | "nwse-resize"
+    | "col-resize"
+    | "row-resize"
+    | "not-allowed"
+    | "crosshair"
+    | "wait";
+
 export interface BoxStyle {
     fontSize?: number;
     fontWeight?: number;
@@ -29,9 +47,17 @@ export interface BoxStyle {
     borderWidth?: number;
     cornerRadius?: number;
     borderRadius?: number;
+    opacity?: number;
     padding?: number | { top: number; right: number; bottom: number; left: number };
     width?: number;
     height?: number;
+    /** Cursor while the pointer is over the widget or its children. */
+    cursor?: CursorStyle;
+
+    /** CSS transform functions (`"rotate(90deg) scale(1.1)"`) or their components. */
+    transform?: string | StyleTransform;
+    /** e.g. `"center"` (the default), `"top left"`, `"50% 100%"` or `"8px 8px"`. */
+    transformOrigin?: string;
 
     flex?: number;
     direction?: FlexDirection;
@@ -40,11 +66,143 @@ export interface BoxStyle {
     gap?: number;
     mustFillMainAxis?: boolean;
 
+    /** Tween later changes of the listed properties instead of jumping. */
+    transition?: StyleTransition;
+
     [key: string]: unknown;
 }
 
 export type VellumStyle = BoxStyle;
 
+/**
+ * Transform components, applied as translate → rotate → skew → scale around the
+ * transform origin. Angles are in degrees.
+ */
+export interface StyleTransform {
+    translateX?: number;
+    translateY?: number;
+    rotate?: number;
+    scale?: number;
+    scaleX?: number;
+    scaleY?: number;
+    skewX?: number;
+    skewY?: number;
+}
+
+export interface StyleTransition {
+    /** Property names, or `"all"` (the default). A comma-separated string is accepted. */
+    properties?: string[] | string;
+    /** Duration in milliseconds. */
+    duration: number;
+    easing?: string;
+    /** Delay in milliseconds before the tween starts. */
+    delay?: number;
+}
+
+/** A keyframe: animated style properties at `offset` (0..1) of each iteration. */
+export type AnimationKeyframe = BoxStyle & { offset?: number };
+
+export interface AnimationOptions {
+    /** Duration of one iteration in milliseconds. */
+    duration: number;
+    /** CSS timing function, e.g. `"ease-in-out"` or `"cubic-bezier(0.2, 0, 0, 1)"`. */
+    easing?: string;
+    /** Number of iterations (default 1); `Infinity` repeats forever. */
+    iterations?: number;
+    direction?: "normal" | "reverse" | "alternate" | "alternate-reverse";
+}
+
+export interface Rect {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
+/** Laid-out bounds of a widget, in logical pixels. Transforms are not applied. */
+export interface WidgetRect {
+    /** Relative to the window's top-left corner. */
+    window: Rect;
+    /** Relative to the parent widget (the window for top-level widgets). */
+    parent: Rect;
+    /** Distance of the text baseline above the bottom edge. */
+    baseline: number;
+}
+
+export interface WindowSize {
+    width: number;
+    height: number;
+}
+
+/** Initial window attributes; sizes and positions are in logical pixels. */
+export interface WindowOptions {
+    title?: string;
+    width?: number;
+    height?: number;
+    minWidth?: number;
+    minHeight?: number;
+    maxWidth?: number;
+    maxHeight?: number;
+    /** Position of the window's top-left corner on the desktop. */
+    x?: number;
+    y?: number;
+    resizable?: boolean;
+    /** `false` hides the title bar and borders. */
+    decorations?: boolean;
+    /** Show the desktop through wherever nothing is painted. */
+    transparent?: boolean;
+    /** Path of an image file for the window icon. Ignored over network transports; use `setWindowIcon` there. */
+    icon?: string;
+}
+
+/** Current window state, in logical pixels (see `window.getState()`). */
+export interface WindowState {
+    minimized: boolean;
+    maximized: boolean;
+    fullscreen: boolean;
+    focused: boolean;
+    decorations: boolean;
+    alwaysOnTop: boolean;
+    /** Outer position; `null` where the platform doesn't tell (Wayland). */
+    x: number | null;
+    y: number | null;
+    width: number;
+    height: number;
+}
+
+export interface IntersectionOptions {
+    /** Ancestor scroll container used as the viewport; defaults to the window. */
+    rootId?: string;
+    /** Visible fraction (0..1) that counts as intersecting; 0 (the default) means any part. */
+    threshold?: number;
+}
+
+export interface ThemeTypography {
+    fontFamily?: string;
+    baseSize?: number;
+    lineHeight?: number;
+    sizes?: Record<string, number>;
+}
+
+export interface ThemeVariant {
+    palette?: Record<string, string>;
+    widgets?: Record<string, BoxStyle>;
+}
+
+/**
+ * Design tokens applied as default widget styles. Colors in `widgets` may
+ * reference palette entries by name (e.g. `color: "text"`).
+ */
+export interface VellumTheme {
+    palette?: Record<string, string>;
+    typography?: ThemeTypography;
+    spacing?: Record<string, number>;
+    radii?: Record<string, number>;
+    widgets?: Record<string, BoxStyle>;
+    variants?: Record<string, ThemeVariant>;
+    variant?: string | null;
+}
+
 export interface SvgParams {
     svgData?: string;
 }
@@ -88,4 +246,16 @@ export interface VellumEvent {
     key?: string;
     text?: string;
     focused?: boolean;
+    cancelled?: boolean;
+    ratio?: number;
+    /** Window state of a `windowStateChanged` event. */
+    state?: WindowState;
+    /** Files of a `fileHoverEnter` or `fileDropped` event. */
+    paths?: string[];
+    /** Stable error code of a `runtimeError` event. */
+    code?: string;
+    message?: string;
+    command?: string;
+    source?: string;
+    fatal?: boolean;
 }
@@ -15,7 +15,16 @@ export function createMountManager(
     }
 
     const init = collectInitialWidgetState(node);
-    runtime.ui.createWidget(node.widgetId, init.kind, parentWidgetId, init.text, init.style, init.params, init.data);
+    runtime.ui.createWidget(
+      node.widgetId,
+      init.kind,
+      parentWidgetId,
+      init.text,
+      init.style,
+      init.params,
+      init.data,
+      init.classes
+    );
     node.mounted = true;
     widgetNodeById.set(node.widgetId, node);
 
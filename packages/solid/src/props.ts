@@ -14,6 +14,7 @@ export function collectInitialWidgetState(node: HostElement): {
   style: VellumStyle | null;
   params: Record<string, unknown> | null;
   data: Uint8Array | null;
+  classes: string[] | null;
 } {
   const kind = normalizeWidgetKind(node.tag);
   const style = createEmptyStyle();
@@ -39,6 +40,7 @@ export function collectInitialWidgetState(node: HostElement): {
     if (name === "data") continue;
     if (name === "objectFit") continue;
     if (name === "src" || name === "playing" || name === "position") continue;
+    if (name === "class") continue;
 
     if (name === "text") {
       if (kind === "button") {
@@ -136,9 +138,22 @@ export function collectInitialWidgetState(node: HostElement): {
     style: hasStyle ? style : null,
     params: hasParams ? params : null,
     data,
+    classes: parseClassList(node.props.class),
   };
 }
 
+function parseClassList(value: unknown): string[] | null {
+  if (typeof value === "string") {
+    const classes = value.split(/\s+/).filter((name) => name.length > 0);
+    return classes.length > 0 ? classes : null;
+  }
+  if (Array.isArray(value)) {
+    const classes = value.filter((name): name is string => typeof name === "string" && name.length > 0);
+    return classes.length > 0 ? classes : null;
+  }
+  return null;
+}
+
 export function applyMountedProperty(runtime: VellumRuntime, node: HostElement, name: string, value: unknown): void {
   if (name === "children" || name === "ref" || name === "key" || name === "id") return;
   if (name === "type" || name === "src") return;
@@ -165,6 +180,11 @@ export function applyMountedProperty(runtime: VellumRuntime, node: HostElement,
     return;
   }
 
+  if (name === "class") {
+    runtime.ui.setClasses?.(node.widgetId, parseClassList(value) ?? []);
+    return;
+  }
+
   if (name === "text") {
     runtime.ui.setText(node.widgetId, String(value ?? ""));
     return;
@@ -28,7 +28,8 @@ export interface VellumRuntime {
       text: string | null,
       style: VellumStyle | null,
       params?: Record<string, unknown> | null,
-      data?: Uint8Array | null
+      data?: Uint8Array | null,
+      classes?: string[] | null
     ) => void;
     removeWidget: (id: string) => void;
     setText: (id: string, text: string) => void;
@@ -37,6 +38,7 @@ export interface VellumRuntime {
     setChecked: (id: string, checked: boolean) => void;
     setStyle: (id: string, style: VellumStyle) => void;
     setStyleProperty: (id: string, property: string, value: string | number | boolean) => void;
+    setClasses?: (id: string, classes: string[]) => void;
     setImageData?: (id: string, data: Uint8Array) => void;
     playVideo?: (id: string) => void;
     pauseVideo?: (id: string) => void;
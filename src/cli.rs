@@ -0,0 +1,325 @@
+use std::path::PathBuf;
+
+use crate::ipc::WindowOptions;
+
+pub const USAGE: &str = "\
+Usage:
+  vellum [window options]                 Wait for a client (see VELLUM_TRANSPORT)
+  vellum run [options] <entry> [args]     Start a JS client and serve it
+
+Options for run:
+  --socket <path>             Socket to serve the client on
+  --runtime <bun|node|deno>   JS runtime that runs <entry> (default: bun)
+  --dev                       Restart the client when it exits or its files change
+
+Window options (override vellum.json and the client):
+  --manifest <path>           App manifest (default: vellum.json next to <entry>,
+                              or in the working directory)
+  --title <title>             Window title
+  --size <width>x<height>     Window size in logical pixels
+  --min-size <width>x<height> Smallest size the window may be resized to
+  --max-size <width>x<height> Largest size the window may be resized to
+  --position <x>,<y>          Window position on the desktop
+  --fixed-size                Don't let the window be resized
+  --no-decorations            Hide the title bar and borders
+  --transparent               Show the desktop behind unpainted areas
+  --icon <path>               Window icon image
+
+  -h, --help                  Show this help
+  -V, --version               Show the version";
+
+/// What the binary was asked to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Wait for a client on the transport chosen by the environment
+    Serve(WindowArgs),
+    /// Spawn and supervise a client
+    Run(RunOptions),
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOptions {
+    /// Script the runtime executes
+    pub entry: PathBuf,
+    /// Passed on to the script
+    pub args: Vec<String>,
+    pub socket: Option<PathBuf>,
+    pub runtime: ClientRuntime,
+    pub window: WindowArgs,
+    pub dev: bool,
+}
+
+/// Where the initial window attributes come from, besides the client.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowArgs {
+    /// Manifest given with `--manifest`, instead of the one looked up
+    pub manifest: Option<PathBuf>,
+    /// Set by window flags; these win over the manifest and the client
+    pub overrides: WindowOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientRuntime {
+    Bun,
+    Node,
+    Deno,
+}
+
+impl ClientRuntime {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "bun" => Ok(ClientRuntime::Bun),
+            "node" => Ok(ClientRuntime::Node),
+            "deno" => Ok(ClientRuntime::Deno),
+            other => Err(format!(
+                "Unknown runtime '{other}' (expected bun, node or deno)"
+            )),
+        }
+    }
+
+    /// Program and arguments that run `entry`.
+    pub fn command_line(&self, entry: &str) -> (&'static str, Vec<String>) {
+        match self {
+            ClientRuntime::Bun => ("bun", vec!["run".to_string(), entry.to_string()]),
+            ClientRuntime::Node => ("node", vec![entry.to_string()]),
+            // The bridge needs the socket, env vars and (for shared buffers) /dev/shm
+            ClientRuntime::Deno => (
+                "deno",
+                vec![
+                    "run".to_string(),
+                    "--allow-all".to_string(),
+                    entry.to_string(),
+                ],
+            ),
+        }
+    }
+}
+
+/// Parse the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        None => Ok(Command::Serve(WindowArgs::default())),
+        Some("-h" | "--help" | "help") => Ok(Command::Help),
+        Some("-V" | "--version") => Ok(Command::Version),
+        Some("run") => {
+            args.next();
+            parse_run(args).map(Command::Run)
+        }
+        Some(flag) if flag.starts_with('-') => parse_serve(args).map(Command::Serve),
+        Some(other) => Err(format!("Unknown command '{other}'")),
+    }
+}
+
+/// Split `--flag=value` into the flag and its inline value.
+fn split_flag(arg: &str) -> (String, Option<String>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => {
+            (flag.to_string(), Some(value.to_string()))
+        }
+        _ => (arg.to_string(), None),
+    }
+}
+
+/// Value of `flag`: inline (`--flag=value`) or the next argument.
+fn flag_value(
+    flag: &str,
+    inline: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, String> {
+    inline
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("{flag} needs a value"))
+}
+
+/// Apply a window flag; `Ok(false)` if `flag` isn't one.
+fn parse_window_flag(
+    flag: &str,
+    inline: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+    window: &mut WindowArgs,
+) -> Result<bool, String> {
+    let options = &mut window.overrides;
+    match flag {
+        "--manifest" => window.manifest = Some(PathBuf::from(flag_value(flag, inline, args)?)),
+        "--title" => options.title = Some(flag_value(flag, inline, args)?),
+        "--size" => {
+            let (width, height) = parse_size(&flag_value(flag, inline, args)?)?;
+            (options.width, options.height) = (Some(width), Some(height));
+        }
+        "--min-size" => {
+            let (width, height) = parse_size(&flag_value(flag, inline, args)?)?;
+            (options.min_width, options.min_height) = (Some(width), Some(height));
+        }
+        "--max-size" => {
+            let (width, height) = parse_size(&flag_value(flag, inline, args)?)?;
+            (options.max_width, options.max_height) = (Some(width), Some(height));
+        }
+        "--position" => {
+            let (x, y) = parse_position(&flag_value(flag, inline, args)?)?;
+            (options.x, options.y) = (Some(x), Some(y));
+        }
+        "--fixed-size" => options.resizable = Some(false),
+        "--no-decorations" => options.decorations = Some(false),
+        "--transparent" => options.transparent = Some(true),
+        "--icon" => options.icon = Some(flag_value(flag, inline, args)?),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn parse_serve(mut args: impl Iterator<Item = String>) -> Result<WindowArgs, String> {
+    let mut window = WindowArgs::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = split_flag(&arg);
+        if !parse_window_flag(&flag, inline, &mut args, &mut window)? {
+            return Err(format!("Unknown option '{flag}'"));
+        }
+    }
+    Ok(window)
+}
+
+fn parse_run(mut args: impl Iterator<Item = String>) -> Result<RunOptions, String> {
+    let mut entry = None;
+    let mut socket = None;
+    let mut runtime = ClientRuntime::Bun;
+    let mut window = WindowArgs::default();
+    let mut dev = false;
+
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline) = split_flag(&arg);
+        match flag.as_str() {
+            "--socket" => socket = Some(PathBuf::from(flag_value(&flag, inline, &mut args)?)),
+            "--runtime" => runtime = ClientRuntime::parse(&flag_value(&flag, inline, &mut args)?)?,
+            "--dev" => dev = true,
+            "--" => {
+                entry = args.next();
+                break;
+            }
+            _ if flag.starts_with('-') => {
+                if !parse_window_flag(&flag, inline, &mut args, &mut window)? {
+                    return Err(format!("Unknown option '{flag}'"));
+                }
+            }
+            _ => {
+                entry = Some(arg);
+                break;
+            }
+        }
+    }
+
+    let entry = entry.ok_or("run needs the script to start, e.g. `vellum run app.ts`")?;
+    Ok(RunOptions {
+        entry: PathBuf::from(entry),
+        // Everything after the entry belongs to the script
+        args: args.collect(),
+        socket,
+        runtime,
+        window,
+        dev,
+    })
+}
+
+fn parse_position(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("Invalid position '{value}' (expected <x>,<y>, e.g. 100,80)");
+    let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+    let x: f64 = x.trim().parse().map_err(|_| invalid())?;
+    let y: f64 = y.trim().parse().map_err(|_| invalid())?;
+    if x.is_finite() && y.is_finite() {
+        Ok((x, y))
+    } else {
+        Err(invalid())
+    }
+}
+
+fn parse_size(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("Invalid size '{value}' (expected <width>x<height>, e.g. 1024x768)");
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: f64 = width.trim().parse().map_err(|_| invalid())?;
+    let height: f64 = height.trim().parse().map_err(|_| invalid())?;
+    if width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite() {
+        Ok((width, height))
+    } else {
+        Err(invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_run_options() {
+        let Command::Run(options) = parse_args(&[
+            "run",
+            "--title=Demo",
+            "--size",
+            "1024x768",
+            "--runtime",
+            "node",
+            "--dev",
+            "app.ts",
+            "--verbose",
+        ])
+        .unwrap() else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.entry, PathBuf::from("app.ts"));
+        assert_eq!(options.args, ["--verbose"]);
+        let window = &options.window.overrides;
+        assert_eq!(window.title.as_deref(), Some("Demo"));
+        assert_eq!((window.width, window.height), (Some(1024.0), Some(768.0)));
+        assert_eq!(options.runtime, ClientRuntime::Node);
+        assert!(options.dev);
+        assert_eq!(options.socket, None);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        assert_eq!(
+            parse_args(&[]).unwrap(),
+            Command::Serve(WindowArgs::default())
+        );
+        assert!(parse_args(&["run"]).is_err());
+        assert!(parse_args(&["run", "--size", "big", "app.ts"]).is_err());
+        assert!(parse_args(&["run", "--runtime", "python", "app.ts"]).is_err());
+        assert!(parse_args(&["run", "--socket"]).is_err());
+        assert!(parse_args(&["serve"]).is_err());
+        assert!(parse_args(&["--position", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_window_flags_in_serve_mode() {
+        let Command::Serve(window) = parse_args(&[
+            "--manifest",
+            "app/vellum.json",
+            "--min-size=320x240",
+            "--position",
+            "-20,40",
+            "--fixed-size",
+            "--no-decorations",
+            "--transparent",
+        ])
+        .unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(window.manifest, Some(PathBuf::from("app/vellum.json")));
+        let options = window.overrides;
+        assert_eq!(
+            (options.min_width, options.min_height),
+            (Some(320.0), Some(240.0))
+        );
+        assert_eq!((options.x, options.y), (Some(-20.0), Some(40.0)));
+        assert_eq!(options.resizable, Some(false));
+        assert_eq!(options.decorations, Some(false));
+        assert_eq!(options.transparent, Some(true));
+        assert_eq!(options.title, None);
+    }
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use super::commands::BoxStyle;
+
+/// A single keyframe of an `Animate` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Position in the iteration, 0.0..=1.0. Missing offsets are spread evenly
+    /// between their neighbours, as in CSS.
+    pub offset: Option<f64>,
+    /// Properties animated at this keyframe.
+    pub style: BoxStyle,
+}
+
+/// Timing function applied to the progress of each iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    /// `cubic-bezier(x1, y1, x2, y2)`; the named CSS easings map onto this.
+    CubicBezier(f64, f64, f64, f64),
+    /// `steps(n)` with jumps at the end of each step.
+    Steps(u32),
+}
+
+/// Playback direction of successive iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnimationDirection {
+    #[default]
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::EASE
+    }
+}
+
+impl Easing {
+    pub const EASE: Easing = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    pub const EASE_IN: Easing = Easing::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: Easing = Easing::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: Easing = Easing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+
+    /// Parse a CSS timing function: `linear`, `ease`, `ease-in`, `ease-out`,
+    /// `ease-in-out`, `cubic-bezier(x1, y1, x2, y2)` or `steps(n)`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "linear" => return Some(Easing::Linear),
+            "ease" => return Some(Easing::EASE),
+            "ease-in" | "easein" => return Some(Easing::EASE_IN),
+            "ease-out" | "easeout" => return Some(Easing::EASE_OUT),
+            "ease-in-out" | "easeinout" => return Some(Easing::EASE_IN_OUT),
+            _ => {}
+        }
+
+        if let Some(args) = s
+            .strip_prefix("cubic-bezier(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let values: Vec<f64> = args
+                .split(',')
+                .map(|v| v.trim().parse::<f64>())
+                .collect::<Result<_, _>>()
+                .ok()?;
+            let [x1, y1, x2, y2] = values[..] else {
+                return None;
+            };
+            // x control points must stay within [0, 1] for the curve to be a function of time
+            if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                return None;
+            }
+            return Some(Easing::CubicBezier(x1, y1, x2, y2));
+        }
+
+        if let Some(args) = s
+            .strip_prefix("steps(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let count = args.split(',').next()?.trim().parse::<u32>().ok()?;
+            return (count > 0).then_some(Easing::Steps(count));
+        }
+
+        None
+    }
+
+    /// Map linear progress `t` (0.0..=1.0) to eased progress.
+    pub fn sample(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::Steps(count) => {
+                let count = count as f64;
+                (t * count).floor().min(count) / count
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let x_param = solve_bezier_param(t, x1, x2);
+                bezier_component(x_param, y1, y2)
+            }
+        }
+    }
+}
+
+/// One coordinate of a cubic Bézier with end points (0, 0) and (1, 1).
+fn bezier_component(s: f64, p1: f64, p2: f64) -> f64 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+/// Find the curve parameter whose x coordinate is `x` (bisection; x is monotonic).
+fn solve_bezier_param(x: f64, x1: f64, x2: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut mid = x;
+    for _ in 0..32 {
+        mid = (low + high) * 0.5;
+        let value = bezier_component(mid, x1, x2);
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+        if value < x {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    mid
+}
+
+impl AnimationDirection {
+    /// Parse `normal`, `reverse`, `alternate` or `alternate-reverse`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "normal" => Some(AnimationDirection::Normal),
+            "reverse" => Some(AnimationDirection::Reverse),
+            "alternate" => Some(AnimationDirection::Alternate),
+            "alternate-reverse" | "alternatereverse" => Some(AnimationDirection::AlternateReverse),
+            _ => None,
+        }
+    }
+
+    /// Whether the iteration with the given index plays backwards.
+    pub fn is_reversed(&self, iteration: u64) -> bool {
+        let odd = iteration % 2 == 1;
+        match self {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => odd,
+            AnimationDirection::AlternateReverse => !odd,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easing_parse() {
+        assert_eq!(Easing::parse("linear"), Some(Easing::Linear));
+        assert_eq!(Easing::parse("Ease-In-Out"), Some(Easing::EASE_IN_OUT));
+        assert_eq!(
+            Easing::parse("cubic-bezier(0.1, 0.7, 1.0, 0.1)"),
+            Some(Easing::CubicBezier(0.1, 0.7, 1.0, 0.1))
+        );
+        assert_eq!(Easing::parse("steps(4)"), Some(Easing::Steps(4)));
+        assert_eq!(Easing::parse("cubic-bezier(2, 0, 0, 1)"), None);
+        assert_eq!(Easing::parse("bouncy"), None);
+    }
+
+    #[test]
+    fn test_easing_sample() {
+        for easing in [Easing::Linear, Easing::EASE, Easing::EASE_IN_OUT] {
+            assert!(easing.sample(0.0).abs() < 1e-6);
+            assert!((easing.sample(1.0) - 1.0).abs() < 1e-6);
+        }
+        assert!((Easing::Linear.sample(0.25) - 0.25).abs() < 1e-9);
+        // ease-in starts slower than linear
+        assert!(Easing::EASE_IN.sample(0.25) < 0.25);
+        assert_eq!(Easing::Steps(4).sample(0.3), 0.25);
+    }
+
+    #[test]
+    fn test_direction_reversal() {
+        assert!(!AnimationDirection::Alternate.is_reversed(0));
+        assert!(AnimationDirection::Alternate.is_reversed(1));
+        assert!(AnimationDirection::AlternateReverse.is_reversed(0));
+        assert_eq!(
+            AnimationDirection::parse("alternate_reverse"),
+            Some(AnimationDirection::AlternateReverse)
+        );
+    }
+}
@@ -1,10 +1,10 @@
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
 
 use masonry::core::ErasedAction;
 use masonry_winit::app::{EventLoopProxy, MasonryUserEvent, WindowId};
 
 use super::commands::ClientCommand;
-use super::{ClientCommandAction, UiEvent};
+use super::{ClientCommandAction, ClientRequestAction, UiEvent, WindowOptions};
 
 /// Sender for UI events (UI thread holds this)
 pub type UiEventSender = Sender<UiEvent>;
@@ -12,6 +12,13 @@ pub type UiEventSender = Sender<UiEvent>;
 /// Receiver for UI events (JS thread holds this)
 pub type UiEventReceiver = Receiver<UiEvent>;
 
+/// Sender for the first client's window attributes (IPC server thread holds this)
+pub type StartupWindowSender = SyncSender<Option<WindowOptions>>;
+
+/// Receiver for the first client's window attributes (main thread holds this
+/// until the window is created)
+pub type StartupWindowReceiver = Receiver<Option<WindowOptions>>;
+
 /// Sender that wraps EventLoopProxy to send ClientCommands directly to the UI event loop.
 /// This is held by the client thread and wakes the event loop on each send (zero polling).
 #[derive(Clone)]
@@ -33,6 +40,17 @@ impl ClientCommandSender {
             .send_event(MasonryUserEvent::AsyncAction(self.window_id, action))
             .map_err(|e| format!("EventLoopProxy send failed: {e:?}"))
     }
+
+    /// Send a ClientCommand whose outcome is answered with a `CommandResponse`.
+    pub fn send_request(&self, cmd: ClientCommand, request_id: u64) -> Result<(), String> {
+        let action: ErasedAction = Box::new(ClientRequestAction {
+            request_id,
+            command: cmd,
+        });
+        self.proxy
+            .send_event(MasonryUserEvent::AsyncAction(self.window_id, action))
+            .map_err(|e| format!("EventLoopProxy send failed: {e:?}"))
+    }
 }
 
 /// Contains all channel endpoints needed for IPC
@@ -47,6 +65,8 @@ pub struct IpcChannels {
 pub struct UiChannels {
     /// Send UI events to IPC server thread
     pub event_sender: UiEventSender,
+    /// Receive the window attributes asked for by the first client
+    pub startup_window: StartupWindowReceiver,
 }
 
 /// Channel endpoints held by the IPC server thread
@@ -55,6 +75,8 @@ pub struct IpcServerChannels {
     pub event_receiver: UiEventReceiver,
     /// Send commands to UI thread (via EventLoopProxy, zero polling)
     pub command_sender: ClientCommandSender,
+    /// Send the first client's window attributes before the window exists
+    pub startup_window: StartupWindowSender,
 }
 
 impl IpcChannels {
@@ -62,14 +84,17 @@ impl IpcChannels {
     /// The `proxy` and `window_id` are needed so JS commands can wake the UI event loop.
     pub fn new(proxy: EventLoopProxy, window_id: WindowId) -> Self {
         let (ui_event_tx, ui_event_rx) = mpsc::channel::<UiEvent>();
+        let (startup_window_tx, startup_window_rx) = mpsc::sync_channel(1);
 
         IpcChannels {
             ui: UiChannels {
                 event_sender: ui_event_tx,
+                startup_window: startup_window_rx,
             },
             ipc_server: IpcServerChannels {
                 event_receiver: ui_event_rx,
                 command_sender: ClientCommandSender::new(proxy, window_id),
+                startup_window: startup_window_tx,
             },
         }
     }
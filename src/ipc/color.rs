@@ -1,7 +1,7 @@
 use serde::{Deserialize, Deserializer, Serialize};
 
 /// Represents a parsed color value
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ColorValue {
     /// RGBA color (0-255 per channel)
     Rgba { r: u8, g: u8, b: u8, a: u8 },
@@ -132,6 +132,23 @@ impl ColorValue {
     }
 }
 
+impl ColorValue {
+    /// Multiply the alpha channel by `factor` (0.0..=1.0). Named colors are unchanged.
+    pub fn scale_alpha(&mut self, factor: f64) {
+        if let ColorValue::Rgba { a, .. } = self {
+            *a = (*a as f64 * factor.clamp(0.0, 1.0)).round() as u8;
+        }
+    }
+
+    /// Format as a string accepted by [`ColorValue::parse`] (`#rrggbbaa` or the name).
+    pub fn to_css_string(&self) -> String {
+        match self {
+            ColorValue::Rgba { r, g, b, a } => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+            ColorValue::Named(name) => name.clone(),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for ColorValue {
     fn deserialize<D>(deserializer: D) -> Result<ColorValue, D::Error>
     where
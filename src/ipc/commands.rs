@@ -1,5 +1,12 @@
+use super::animation::{AnimationDirection, Easing, Keyframe};
 use super::color::ColorValue;
+use super::errors::VellumError;
+use super::shared_buffer::PayloadBytes;
+use super::style_vars::is_var_reference;
+use super::theme::ThemeTokens;
+use super::transform::{TransformOrigin, TransformValue};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 /// Commands generated by the runtime and sent to the UI thread
@@ -16,8 +23,16 @@ pub enum ClientCommand {
         text: Option<String>,
         style: Option<BoxStyle>,
         data: Option<WidgetData>,
+        /// Named style classes, applied in order beneath the inline style
+        classes: Vec<String>,
     },
 
+    /// Define (or redefine) a named style class shared across widgets
+    DefineStyleClass { name: String, style: BoxStyle },
+
+    /// Replace the style classes of an existing widget
+    SetWidgetClasses { id: String, classes: Vec<String> },
+
     /// Remove a widget
     RemoveWidget { id: String },
 
@@ -30,6 +45,26 @@ pub enum ClientCommand {
     /// Apply style to an existing widget
     SetWidgetStyle { id: String, style: BoxStyle },
 
+    /// Set (or with `None`, remove) style variables referenced via `var(--name)`
+    SetStyleVariables {
+        variables: HashMap<String, Option<String>>,
+    },
+
+    /// Animate style properties of a widget through keyframes on the UI thread.
+    /// Replaces any animation already running on the widget.
+    Animate {
+        id: String,
+        keyframes: Vec<Keyframe>,
+        duration_ms: f64,
+        easing: Easing,
+        /// Number of iterations; may be fractional or `f64::INFINITY`
+        iterations: f64,
+        direction: AnimationDirection,
+    },
+
+    /// Stop the animation running on a widget, if any
+    CancelAnimation { id: String },
+
     /// Set a single style property on a widget
     SetStyleProperty {
         id: String,
@@ -50,19 +85,146 @@ pub enum ClientCommand {
     SeekVideo { id: String, time_secs: f64 },
 
     /// Set image data on an Image widget (raw file bytes)
-    SetImageData { id: String, data: Vec<u8> },
+    SetImageData { id: String, data: PayloadBytes },
 
     /// Set whether a checkbox is checked
     SetWidgetChecked { id: String, checked: bool },
 
+    /// Replace the active theme and restyle every widget
+    SetTheme { theme: ThemeTokens },
+
+    /// Switch the active theme variant (e.g. "light" / "dark"); `None` uses the base tokens
+    SetThemeVariant { variant: Option<String> },
+
     /// Request window resize
     ResizeWindow { width: u32, height: u32 },
 
     /// Request window close
     CloseWindow,
 
+    /// Minimize the window, or restore it when false
+    SetWindowMinimized(bool),
+
+    /// Maximize the window, or restore its previous size when false
+    SetWindowMaximized(bool),
+
+    /// Cover the current monitor without borders, or go back to a window
+    SetWindowFullscreen(bool),
+
+    /// Move the window's outer top-left corner (logical pixels)
+    SetWindowPosition { x: f64, y: f64 },
+
+    /// Smallest inner size the window may be resized to; None removes the limit
+    SetWindowMinSize(Option<(f64, f64)>),
+
+    /// Largest inner size the window may be resized to; None removes the limit
+    SetWindowMaxSize(Option<(f64, f64)>),
+
+    /// Keep the window above all others
+    SetWindowAlwaysOnTop(bool),
+
+    /// Show or hide the title bar and borders
+    SetWindowDecorations(bool),
+
+    /// Encoded image (PNG, ...) for the window icon; None restores the default
+    SetWindowIcon(Option<Vec<u8>>),
+
+    /// Flash the taskbar entry or bounce the dock icon until the window is
+    /// focused; `critical` keeps doing so where the platform tells the two apart
+    RequestWindowAttention { critical: bool },
+
+    /// Query the window state (answered with `WindowState`)
+    GetWindowState,
+
+    /// Show this cursor over the whole window, ahead of widget `cursor`
+    /// styles (e.g. while dragging); None goes back to the styles
+    SetCursor(Option<CursorStyle>),
+
     /// Request to exit the application
     ExitApp,
+
+    /// Remove every widget and forget styles, theme, animations and observers,
+    /// so a new client starts from an empty window
+    ResetUi,
+
+    /// Query the laid-out bounds of a widget (answered with `WidgetBounds`)
+    GetWidgetRect { id: String },
+
+    /// Query the logical size of the window content
+    GetWindowSize,
+
+    /// Query the JS id of the widget under a window point (`null` if none)
+    HitTest { x: f64, y: f64 },
+
+    /// Report size changes of a widget with `WidgetResized` events, starting
+    /// with its current size
+    ObserveResize { id: String },
+
+    /// Stop reporting size changes of a widget
+    UnobserveResize { id: String },
+
+    /// Report with `IntersectionChanged` events when the visible fraction of a
+    /// widget crosses `threshold` (0 means any visible part). Visibility is
+    /// clipped by scroll containers up to `root_id`, or the window if None.
+    ObserveIntersection {
+        id: String,
+        root_id: Option<String>,
+        threshold: f64,
+    },
+
+    /// Stop reporting visibility changes of a widget
+    UnobserveIntersection { id: String },
+}
+
+impl ClientCommand {
+    /// Variant name, used to attribute errors to the command that caused them.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClientCommand::SetTitle(_) => "SetTitle",
+            ClientCommand::CreateWidget { .. } => "CreateWidget",
+            ClientCommand::DefineStyleClass { .. } => "DefineStyleClass",
+            ClientCommand::SetWidgetClasses { .. } => "SetWidgetClasses",
+            ClientCommand::RemoveWidget { .. } => "RemoveWidget",
+            ClientCommand::SetWidgetText { .. } => "SetWidgetText",
+            ClientCommand::SetWidgetVisible { .. } => "SetWidgetVisible",
+            ClientCommand::SetWidgetStyle { .. } => "SetWidgetStyle",
+            ClientCommand::SetStyleVariables { .. } => "SetStyleVariables",
+            ClientCommand::Animate { .. } => "Animate",
+            ClientCommand::CancelAnimation { .. } => "CancelAnimation",
+            ClientCommand::SetStyleProperty { .. } => "SetStyleProperty",
+            ClientCommand::SetWidgetValue { .. } => "SetWidgetValue",
+            ClientCommand::PlayVideo { .. } => "PlayVideo",
+            ClientCommand::PauseVideo { .. } => "PauseVideo",
+            ClientCommand::SeekVideo { .. } => "SeekVideo",
+            ClientCommand::SetImageData { .. } => "SetImageData",
+            ClientCommand::SetWidgetChecked { .. } => "SetWidgetChecked",
+            ClientCommand::SetTheme { .. } => "SetTheme",
+            ClientCommand::SetThemeVariant { .. } => "SetThemeVariant",
+            ClientCommand::ResizeWindow { .. } => "ResizeWindow",
+            ClientCommand::CloseWindow => "CloseWindow",
+            ClientCommand::SetWindowMinimized(_) => "SetWindowMinimized",
+            ClientCommand::SetWindowMaximized(_) => "SetWindowMaximized",
+            ClientCommand::SetWindowFullscreen(_) => "SetWindowFullscreen",
+            ClientCommand::SetWindowPosition { .. } => "SetWindowPosition",
+            ClientCommand::SetWindowMinSize(_) => "SetWindowMinSize",
+            ClientCommand::SetWindowMaxSize(_) => "SetWindowMaxSize",
+            ClientCommand::SetWindowAlwaysOnTop(_) => "SetWindowAlwaysOnTop",
+            ClientCommand::SetWindowDecorations(_) => "SetWindowDecorations",
+            ClientCommand::SetWindowIcon(_) => "SetWindowIcon",
+            ClientCommand::RequestWindowAttention { .. } => "RequestWindowAttention",
+            ClientCommand::GetWindowState => "GetWindowState",
+            ClientCommand::SetCursor(_) => "SetCursor",
+            ClientCommand::ExitApp => "ExitApp",
+            ClientCommand::ResetUi => "ResetUi",
+            ClientCommand::GetWidgetRect { .. } => "GetWidgetRect",
+            ClientCommand::GetWindowSize => "GetWindowSize",
+            ClientCommand::HitTest { .. } => "HitTest",
+            ClientCommand::ObserveResize { .. } => "ObserveResize",
+            ClientCommand::UnobserveResize { .. } => "UnobserveResize",
+            ClientCommand::ObserveIntersection { .. } => "ObserveIntersection",
+            ClientCommand::UnobserveIntersection { .. } => "UnobserveIntersection",
+        }
+    }
 }
 
 /// Widget types that can be created
@@ -90,6 +252,109 @@ pub enum WidgetKind {
     Custom(String),
 }
 
+impl WidgetKind {
+    /// Every kind except `Custom`.
+    pub const BUILTIN: [WidgetKind; 19] = [
+        WidgetKind::Label,
+        WidgetKind::Button,
+        WidgetKind::Svg,
+        WidgetKind::Image,
+        WidgetKind::TextInput,
+        WidgetKind::TextArea,
+        WidgetKind::Checkbox,
+        WidgetKind::Flex,
+        WidgetKind::Container,
+        WidgetKind::SizedBox,
+        WidgetKind::ProgressBar,
+        WidgetKind::Spinner,
+        WidgetKind::Slider,
+        WidgetKind::Prose,
+        WidgetKind::Grid,
+        WidgetKind::ZStack,
+        WidgetKind::Portal,
+        WidgetKind::Hoverable,
+        WidgetKind::Video,
+    ];
+
+    /// Key used to look up per-kind defaults (e.g. in theme tokens).
+    /// All custom widgets share the `Custom` key.
+    pub fn style_key(&self) -> &str {
+        match self {
+            WidgetKind::Label => "Label",
+            WidgetKind::Button => "Button",
+            WidgetKind::Svg => "Svg",
+            WidgetKind::Image => "Image",
+            WidgetKind::TextInput => "TextInput",
+            WidgetKind::TextArea => "TextArea",
+            WidgetKind::Checkbox => "Checkbox",
+            WidgetKind::Flex => "Flex",
+            WidgetKind::Container => "Container",
+            WidgetKind::SizedBox => "SizedBox",
+            WidgetKind::ProgressBar => "ProgressBar",
+            WidgetKind::Spinner => "Spinner",
+            WidgetKind::Slider => "Slider",
+            WidgetKind::Prose => "Prose",
+            WidgetKind::Grid => "Grid",
+            WidgetKind::ZStack => "ZStack",
+            WidgetKind::Portal => "Portal",
+            WidgetKind::Hoverable => "Hoverable",
+            WidgetKind::Video => "Video",
+            WidgetKind::Custom(_) => "Custom",
+        }
+    }
+
+    /// Whether this widget renders text and therefore takes typography styles.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            WidgetKind::Label
+                | WidgetKind::TextInput
+                | WidgetKind::TextArea
+                | WidgetKind::Prose
+                | WidgetKind::Custom(_)
+        )
+    }
+}
+
+/// Widget parameters as sent by the client, before they are narrowed to the
+/// `WidgetData` of the widget's kind. Unknown keys are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WidgetParams {
+    #[serde(alias = "svg_data", alias = "svg")]
+    pub svg_data: Option<String>,
+    #[serde(alias = "object_fit")]
+    pub object_fit: Option<String>,
+    pub checked: Option<bool>,
+    pub placeholder: Option<String>,
+    pub progress: Option<f64>,
+    pub value: Option<f64>,
+    #[serde(alias = "min_value", alias = "min")]
+    pub min_value: Option<f64>,
+    #[serde(alias = "max_value", alias = "max")]
+    pub max_value: Option<f64>,
+    pub step: Option<f64>,
+    pub src: Option<String>,
+}
+
+impl WidgetParams {
+    /// Fill fields that are unset in `self` from `other`.
+    pub fn or(self, other: WidgetParams) -> WidgetParams {
+        WidgetParams {
+            svg_data: self.svg_data.or(other.svg_data),
+            object_fit: self.object_fit.or(other.object_fit),
+            checked: self.checked.or(other.checked),
+            placeholder: self.placeholder.or(other.placeholder),
+            progress: self.progress.or(other.progress),
+            value: self.value.or(other.value),
+            min_value: self.min_value.or(other.min_value),
+            max_value: self.max_value.or(other.max_value),
+            step: self.step.or(other.step),
+            src: self.src.or(other.src),
+        }
+    }
+}
+
 /// Widget-specific initialization data.
 /// Each variant carries only the data relevant to that widget kind,
 /// ensuring type safety and preventing nonsensical combinations.
@@ -103,7 +368,7 @@ pub enum WidgetData {
 
     /// Image widget — raw image bytes + display mode
     Image {
-        data: Vec<u8>,
+        data: PayloadBytes,
         object_fit: Option<String>,
     },
 
@@ -182,10 +447,17 @@ pub struct BoxStyle {
     pub hover_border_color: Option<ColorValue>,
     pub border_width: Option<f64>,
     pub corner_radius: Option<f64>,
+    pub opacity: Option<f64>,
     pub padding: Option<PaddingValue>,
     pub width: Option<f64>,
     pub height: Option<f64>,
     pub icon_size: Option<f64>,
+    /// Mouse cursor while the pointer is over the widget or its descendants
+    pub cursor: Option<CursorStyle>,
+
+    // -- 2D transform applied at paint time (hit-testing follows it) --
+    pub transform: Option<TransformValue>,
+    pub transform_origin: Option<TransformOrigin>,
 
     // -- Flex-child property (how this widget behaves inside a Flex parent) --
     pub flex: Option<f64>,
@@ -196,6 +468,172 @@ pub struct BoxStyle {
     pub main_axis_alignment: Option<MainAlign>,
     pub gap: Option<f64>,
     pub must_fill_main_axis: Option<bool>,
+
+    // -- Tween later changes of the listed properties instead of jumping --
+    pub transition: Option<TransitionSpec>,
+
+    // -- Variable references: property name (camelCase) → `var(--name, fallback)` --
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub var_refs: HashMap<String, String>,
+}
+
+impl BoxStyle {
+    /// Overlay every property set in `other` onto `self` (later wins).
+    /// A literal value replaces an earlier variable reference for the same property.
+    pub fn merge_from(&mut self, other: &BoxStyle) {
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if other.$field.is_some() {
+                        self.$field = other.$field.clone();
+                        if !self.var_refs.is_empty() {
+                            self.var_refs.remove(&camel_case(stringify!($field)));
+                        }
+                    }
+                )*
+            };
+        }
+
+        merge!(
+            font_size,
+            font_weight,
+            font_style,
+            font_family,
+            color,
+            letter_spacing,
+            line_height,
+            word_spacing,
+            underline,
+            strikethrough,
+            text_align,
+            background,
+            border_color,
+            hover_border_color,
+            border_width,
+            corner_radius,
+            opacity,
+            padding,
+            width,
+            height,
+            icon_size,
+            cursor,
+            transform,
+            transform_origin,
+            flex,
+            direction,
+            cross_axis_alignment,
+            main_axis_alignment,
+            gap,
+            must_fill_main_axis,
+            transition,
+        );
+
+        for (property, reference) in &other.var_refs {
+            self.var_refs.insert(property.clone(), reference.clone());
+        }
+    }
+
+    /// Copy of the style with `opacity` folded into the alpha of its colors,
+    /// which is how opacity is rendered. Applied when a style reaches a widget.
+    pub fn with_opacity_applied(&self) -> BoxStyle {
+        let mut style = self.clone();
+        if let Some(opacity) = style.opacity {
+            for color in [
+                &mut style.color,
+                &mut style.background,
+                &mut style.border_color,
+                &mut style.hover_border_color,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                color.scale_alpha(opacity);
+            }
+        }
+        style
+    }
+
+    /// Text properties a container passes down to its descendants
+    /// (`font_family`, `font_size`, `color`, `line_height`).
+    pub fn inherited_text(&self) -> BoxStyle {
+        BoxStyle {
+            font_family: self.font_family.clone(),
+            font_size: self.font_size,
+            color: self.color.clone(),
+            line_height: self.line_height,
+            ..Default::default()
+        }
+    }
+
+    /// Whether this (partial) style sets any property inherited by descendants.
+    pub fn sets_inherited_text(&self) -> bool {
+        self.font_family.is_some()
+            || self.font_size.is_some()
+            || self.color.is_some()
+            || self.line_height.is_some()
+            || self
+                .var_refs
+                .keys()
+                .any(|property| INHERITED_TEXT_PROPERTIES.contains(&property.as_str()))
+    }
+
+    /// Build a partial style from a single `property` / `value` pair as sent by
+    /// `SetStyleProperty` (and used when resolving variable references).
+    pub fn from_property(property: &str, value: &str) -> Result<BoxStyle, serde_json::Error> {
+        if is_var_reference(value) {
+            let mut style = BoxStyle::default();
+            style
+                .var_refs
+                .insert(property.to_string(), value.trim().to_string());
+            return Ok(style);
+        }
+
+        // Build a JSON value properly so control characters (newlines in SVG
+        // strings, etc.) are escaped correctly instead of being embedded raw.
+        let parsed_value: serde_json::Value = if let Ok(n) = value.parse::<f64>() {
+            serde_json::Value::Number(
+                serde_json::Number::from_f64(n).unwrap_or_else(|| serde_json::Number::from(0)),
+            )
+        } else if value == "true" {
+            serde_json::Value::Bool(true)
+        } else if value == "false" {
+            serde_json::Value::Bool(false)
+        } else if (value.starts_with('{') || value.starts_with('['))
+            && let Ok(json) = serde_json::from_str::<serde_json::Value>(value)
+        {
+            json
+        } else {
+            // Strip surrounding quotes if present, then use
+            // serde_json::Value::String which handles escaping.
+            let raw = value
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(value);
+            serde_json::Value::String(raw.to_string())
+        };
+
+        serde_json::from_value(serde_json::json!({ property: parsed_value }))
+    }
+}
+
+/// Wire names of the properties returned by [`BoxStyle::inherited_text`].
+const INHERITED_TEXT_PROPERTIES: [&str; 4] = ["fontFamily", "fontSize", "color", "lineHeight"];
+
+/// Convert a snake_case field name to its camelCase wire name.
+fn camel_case(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut upper_next = false;
+    for ch in field.chars() {
+        if ch == '_' {
+            upper_next = true;
+        } else if upper_next {
+            out.push(ch.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
 }
 
 /// Font style (normal vs italic)
@@ -216,6 +654,74 @@ pub enum TextAlignValue {
     Justify,
 }
 
+/// Which property changes are tweened, and how.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TransitionSpec {
+    /// Property names (`"background"`, `"cornerRadius"`, `"corner-radius"`) or `"all"`.
+    /// Also accepted as one comma-separated string.
+    #[serde(deserialize_with = "deserialize_property_list")]
+    pub properties: Vec<String>,
+    /// Duration in milliseconds
+    pub duration: f64,
+    /// CSS timing function; defaults to `ease`
+    pub easing: Option<String>,
+    /// Delay before the tween starts, in milliseconds
+    pub delay: f64,
+}
+
+impl Default for TransitionSpec {
+    fn default() -> Self {
+        Self {
+            properties: vec!["all".to_string()],
+            duration: 0.0,
+            easing: None,
+            delay: 0.0,
+        }
+    }
+}
+
+fn deserialize_property_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawList {
+        List(Vec<String>),
+        Joined(String),
+    }
+
+    Ok(match RawList::deserialize(deserializer)? {
+        RawList::List(list) => list,
+        RawList::Joined(joined) => joined
+            .split(',')
+            .map(str::trim)
+            .filter(|property| !property.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+impl TransitionSpec {
+    /// Whether changes to the field (snake_case `BoxStyle` field name) are tweened.
+    pub fn covers(&self, field: &str) -> bool {
+        self.properties.iter().any(|property| {
+            let property = property.trim();
+            property.eq_ignore_ascii_case("all")
+                || property.replace('-', "_") == field
+                || property == camel_case(field)
+        })
+    }
+
+    pub fn easing(&self) -> Easing {
+        self.easing
+            .as_deref()
+            .and_then(Easing::parse)
+            .unwrap_or_default()
+    }
+}
+
 /// Flex direction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -235,6 +741,31 @@ pub enum CrossAlign {
     Baseline,
 }
 
+/// Mouse cursor shape, named as in CSS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorStyle {
+    Default,
+    Pointer,
+    Text,
+    Grab,
+    Grabbing,
+    Move,
+    /// Horizontal (east-west) resize
+    EwResize,
+    /// Vertical (north-south) resize
+    NsResize,
+    /// Diagonal resize, top right to bottom left
+    NeswResize,
+    /// Diagonal resize, top left to bottom right
+    NwseResize,
+    ColResize,
+    RowResize,
+    NotAllowed,
+    Crosshair,
+    Wait,
+}
+
 /// Main axis alignment for Flex
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -248,7 +779,7 @@ pub enum MainAlign {
 }
 
 /// Padding (uniform or per-side)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum PaddingValue {
     Uniform(f64),
@@ -260,6 +791,72 @@ pub enum PaddingValue {
     },
 }
 
+impl PaddingValue {
+    /// Parse the comma-separated shorthand: `"8"`, `"8, 16"`, `"8, 16, 4"`
+    /// or `"8, 16, 4, 2"` (top, right, bottom, left as in CSS).
+    pub fn parse(raw: &str) -> Option<Self> {
+        let values: Vec<f64> = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .map(str::parse::<f64>)
+            .collect::<Result<_, _>>()
+            .ok()?;
+
+        let sides = |top, right, bottom, left| PaddingValue::Sides {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        match *values.as_slice() {
+            [all] => Some(PaddingValue::Uniform(all)),
+            [vertical, horizontal] => Some(sides(vertical, horizontal, vertical, horizontal)),
+            [top, horizontal, bottom] => Some(sides(top, horizontal, bottom, horizontal)),
+            [top, right, bottom, left] => Some(sides(top, right, bottom, left)),
+            _ => None,
+        }
+    }
+}
+
+/// Forms accepted on the wire: a number, a per-side object or the shorthand string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPadding {
+    Uniform(f64),
+    Sides {
+        top: f64,
+        right: f64,
+        bottom: f64,
+        left: f64,
+    },
+    Shorthand(String),
+}
+
+impl<'de> Deserialize<'de> for PaddingValue {
+    fn deserialize<D>(deserializer: D) -> Result<PaddingValue, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawPadding::deserialize(deserializer)? {
+            RawPadding::Uniform(all) => Ok(PaddingValue::Uniform(all)),
+            RawPadding::Sides {
+                top,
+                right,
+                bottom,
+                left,
+            } => Ok(PaddingValue::Sides {
+                top,
+                right,
+                bottom,
+                left,
+            }),
+            RawPadding::Shorthand(raw) => PaddingValue::parse(&raw)
+                .ok_or_else(|| serde::de::Error::custom(format!("Invalid padding: {raw}"))),
+        }
+    }
+}
+
 /// Wrapper around ClientCommand that can be sent as an ErasedAction through the EventLoopProxy.
 /// ErasedAction = Box<dyn AnyDebug + Send>, where AnyDebug is auto-implemented for Any + Debug.
 /// So this type just needs Debug + Send + 'static (ClientCommand is Clone+Debug, all String fields).
@@ -271,6 +868,17 @@ impl fmt::Debug for ClientCommandAction {
     }
 }
 
+/// A command sent as a request: its outcome is answered with a
+/// `UiEvent::CommandResponse` carrying the same `request_id`.
+#[derive(Debug)]
+pub struct ClientRequestAction {
+    pub request_id: u64,
+    pub command: ClientCommand,
+}
+
+/// Outcome of handling a command: an optional result value, or why it failed.
+pub type CommandResult = Result<Option<serde_json::Value>, VellumError>;
+
 // ── Helpers for parsing style from JSON-like data ──
 
 #[cfg(test)]
@@ -305,6 +913,7 @@ mod tests {
                 ..Default::default()
             }),
             data: None,
+            classes: vec!["primary".to_string()],
         };
 
         let serialized = serde_json::to_string(&cmd).unwrap();
@@ -313,14 +922,103 @@ mod tests {
         assert!(serialized.contains("btn_1"));
 
         let deserialized: ClientCommand = serde_json::from_str(&serialized).unwrap();
-        if let ClientCommand::CreateWidget { id, kind, .. } = deserialized {
+        if let ClientCommand::CreateWidget {
+            id, kind, classes, ..
+        } = deserialized
+        {
             assert_eq!(id, "btn_1");
             assert!(matches!(kind, WidgetKind::Button));
+            assert_eq!(classes, vec!["primary".to_string()]);
         } else {
             panic!("Deserialized to wrong variant");
         }
     }
 
+    #[test]
+    fn test_box_style_merge_from() {
+        let mut base = BoxStyle {
+            font_size: Some(14.0),
+            color: Some(ColorValue::Named("text".to_string())),
+            ..Default::default()
+        };
+        let overrides = BoxStyle {
+            font_size: Some(20.0),
+            gap: Some(8.0),
+            ..Default::default()
+        };
+
+        base.merge_from(&overrides);
+
+        assert_eq!(base.font_size, Some(20.0));
+        assert_eq!(base.gap, Some(8.0));
+        assert!(matches!(base.color, Some(ColorValue::Named(_))));
+    }
+
+    #[test]
+    fn test_box_style_from_property() {
+        let style = BoxStyle::from_property("fontSize", "18").unwrap();
+        assert_eq!(style.font_size, Some(18.0));
+
+        let style = BoxStyle::from_property("background", "var(--accent, #fff)").unwrap();
+        assert!(style.background.is_none());
+        assert_eq!(
+            style.var_refs.get("background").map(String::as_str),
+            Some("var(--accent, #fff)")
+        );
+
+        assert!(BoxStyle::from_property("fontSize", "large").is_err());
+    }
+
+    #[test]
+    fn test_inherited_text_keeps_only_text_properties() {
+        let style = BoxStyle {
+            font_family: Some("Inter".to_string()),
+            font_size: Some(14.0),
+            gap: Some(8.0),
+            ..Default::default()
+        };
+        let inherited = style.inherited_text();
+        assert_eq!(inherited.font_family.as_deref(), Some("Inter"));
+        assert_eq!(inherited.font_size, Some(14.0));
+        assert!(inherited.gap.is_none());
+
+        assert!(style.sets_inherited_text());
+        assert!(
+            !BoxStyle::from_property("gap", "4")
+                .unwrap()
+                .sets_inherited_text()
+        );
+        assert!(
+            BoxStyle::from_property("color", "var(--text)")
+                .unwrap()
+                .sets_inherited_text()
+        );
+    }
+
+    #[test]
+    fn test_transition_spec_covers() {
+        let spec: TransitionSpec = serde_json::from_str(
+            r#"{ "properties": ["cornerRadius", "border-color"], "duration": 150 }"#,
+        )
+        .unwrap();
+        assert!(spec.covers("corner_radius"));
+        assert!(spec.covers("border_color"));
+        assert!(!spec.covers("background"));
+        assert_eq!(spec.easing(), Easing::EASE);
+        assert!(TransitionSpec::default().covers("width"));
+    }
+
+    #[test]
+    fn test_merge_literal_replaces_var_ref() {
+        let mut base = BoxStyle::from_property("cornerRadius", "var(--radius-md)").unwrap();
+        base.merge_from(&BoxStyle {
+            corner_radius: Some(4.0),
+            ..Default::default()
+        });
+        assert!(base.var_refs.is_empty());
+        assert_eq!(base.corner_radius, Some(4.0));
+    }
+
     #[test]
     fn test_client_command_action_debug() {
         let cmd = ClientCommand::ExitApp;
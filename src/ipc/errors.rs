@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+
+use super::commands::WidgetKind;
+
+/// Why a command or the runtime failed. Serialized with a stable snake_case
+/// `code` (e.g. `"widget_not_found"`) next to the variant's fields, so clients
+/// can match on errors without parsing messages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum VellumError {
+    /// No widget is registered under the id
+    WidgetNotFound { widget_id: String },
+    /// A widget with the id already exists
+    DuplicateId { widget_id: String },
+    /// The operation does not apply to this kind of widget (or is not implemented)
+    UnsupportedOperation {
+        widget_id: Option<String>,
+        kind: String,
+        op: String,
+    },
+    /// A style property could not be parsed
+    InvalidStyle {
+        widget_id: Option<String>,
+        property: String,
+        value: String,
+        reason: String,
+    },
+    /// A command argument is out of range or inconsistent with the widget tree
+    InvalidArgument {
+        widget_id: Option<String>,
+        reason: String,
+    },
+    /// A payload (frame, JSON, image, SVG, ...) could not be decoded
+    DecodeFailed {
+        widget_id: Option<String>,
+        what: String,
+        reason: String,
+    },
+    /// The parent widget cannot hold the child
+    ParentRejectsChild {
+        widget_id: String,
+        parent_id: String,
+        parent_kind: String,
+    },
+    /// The client speaks a protocol version outside `min_version..=max_version`,
+    /// or (`client_version: None`) opened a connection that needs no session
+    /// token with something other than a `Hello`
+    IncompatibleProtocol {
+        client_version: Option<u32>,
+        min_version: u32,
+        max_version: u32,
+    },
+    /// The client did not present the session token it was given
+    Unauthorized { reason: String },
+    /// The client broke the framing (oversized or undecodable frames); the
+    /// connection is closed
+    ProtocolViolation { reason: String },
+    /// Failure of the runtime itself rather than of a command
+    Internal { reason: String },
+}
+
+impl VellumError {
+    pub fn widget_not_found(id: &str) -> Self {
+        VellumError::WidgetNotFound {
+            widget_id: id.to_string(),
+        }
+    }
+
+    pub fn unsupported(id: Option<&str>, kind: &WidgetKind, op: &str) -> Self {
+        VellumError::UnsupportedOperation {
+            widget_id: id.map(str::to_string),
+            kind: kind.style_key().to_string(),
+            op: op.to_string(),
+        }
+    }
+
+    pub fn internal(reason: impl Into<String>) -> Self {
+        VellumError::Internal {
+            reason: reason.into(),
+        }
+    }
+
+    /// Stable identifier of the error kind, as serialized in `code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VellumError::WidgetNotFound { .. } => "widget_not_found",
+            VellumError::DuplicateId { .. } => "duplicate_id",
+            VellumError::UnsupportedOperation { .. } => "unsupported_operation",
+            VellumError::InvalidStyle { .. } => "invalid_style",
+            VellumError::InvalidArgument { .. } => "invalid_argument",
+            VellumError::DecodeFailed { .. } => "decode_failed",
+            VellumError::ParentRejectsChild { .. } => "parent_rejects_child",
+            VellumError::IncompatibleProtocol { .. } => "incompatible_protocol",
+            VellumError::Unauthorized { .. } => "unauthorized",
+            VellumError::ProtocolViolation { .. } => "protocol_violation",
+            VellumError::Internal { .. } => "internal",
+        }
+    }
+
+    /// The widget the error is about, if any.
+    pub fn widget_id(&self) -> Option<&str> {
+        match self {
+            VellumError::WidgetNotFound { widget_id }
+            | VellumError::DuplicateId { widget_id }
+            | VellumError::ParentRejectsChild { widget_id, .. } => Some(widget_id),
+            VellumError::UnsupportedOperation { widget_id, .. }
+            | VellumError::InvalidStyle { widget_id, .. }
+            | VellumError::InvalidArgument { widget_id, .. }
+            | VellumError::DecodeFailed { widget_id, .. } => widget_id.as_deref(),
+            VellumError::IncompatibleProtocol { .. }
+            | VellumError::Unauthorized { .. }
+            | VellumError::ProtocolViolation { .. }
+            | VellumError::Internal { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for VellumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VellumError::WidgetNotFound { widget_id } => {
+                write!(f, "Widget '{widget_id}' not found")
+            }
+            VellumError::DuplicateId { widget_id } => {
+                write!(f, "Widget id '{widget_id}' is already in use")
+            }
+            VellumError::UnsupportedOperation {
+                widget_id,
+                kind,
+                op,
+            } => {
+                write!(f, "{op} is not supported on {kind}")?;
+                match widget_id {
+                    Some(id) => write!(f, " widget '{id}'"),
+                    None => Ok(()),
+                }
+            }
+            VellumError::InvalidStyle {
+                widget_id,
+                property,
+                value,
+                reason,
+            } => {
+                write!(f, "Invalid style {property}={value}")?;
+                if let Some(id) = widget_id {
+                    write!(f, " for widget '{id}'")?;
+                }
+                write!(f, ": {reason}")
+            }
+            VellumError::InvalidArgument { widget_id, reason } => match widget_id {
+                Some(id) => write!(f, "Invalid argument for widget '{id}': {reason}"),
+                None => write!(f, "Invalid argument: {reason}"),
+            },
+            VellumError::DecodeFailed {
+                widget_id,
+                what,
+                reason,
+            } => {
+                write!(f, "Failed to decode {what}")?;
+                if let Some(id) = widget_id {
+                    write!(f, " for widget '{id}'")?;
+                }
+                write!(f, ": {reason}")
+            }
+            VellumError::ParentRejectsChild {
+                widget_id,
+                parent_id,
+                parent_kind,
+            } => write!(
+                f,
+                "{parent_kind} '{parent_id}' cannot hold child widget '{widget_id}'"
+            ),
+            VellumError::IncompatibleProtocol {
+                client_version,
+                min_version,
+                max_version,
+            } => {
+                match client_version {
+                    Some(version) => {
+                        write!(f, "Client protocol version {version} is not supported")?
+                    }
+                    None => write!(f, "Client did not send a Hello handshake")?,
+                }
+                write!(
+                    f,
+                    "; this server speaks protocol versions {min_version} to {max_version}"
+                )
+            }
+            VellumError::Unauthorized { reason } => write!(f, "Unauthorized client: {reason}"),
+            VellumError::ProtocolViolation { reason } => {
+                write!(f, "Protocol violation, closing the connection: {reason}")
+            }
+            VellumError::Internal { reason } => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for VellumError {}
+
+/// An error as reported to JS: the typed error (flattened, so `code` and
+/// `widget_id` sit at the top level), a readable message, and the command
+/// that caused it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(flatten)]
+    pub error: VellumError,
+}
+
+impl ErrorReport {
+    pub fn new(error: VellumError, command: Option<&str>) -> Self {
+        Self {
+            message: error.to_string(),
+            command: command.map(str::to_string),
+            error,
+        }
+    }
+}
+
+/// Whether `VELLUM_STRICT` is set (to anything but `0` / `false`). In strict
+/// mode every reported runtime error is fatal, so dropped styles or failed
+/// commands stop the app instead of being logged and skipped.
+pub fn strict_mode() -> bool {
+    static STRICT: OnceLock<bool> = OnceLock::new();
+    *STRICT.get_or_init(|| {
+        std::env::var("VELLUM_STRICT")
+            .is_ok_and(|value| !matches!(value.trim(), "" | "0" | "false"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_report_serialization() {
+        let report = ErrorReport::new(
+            VellumError::unsupported(Some("btn"), &WidgetKind::Button, "SetWidgetText"),
+            Some("SetWidgetText"),
+        );
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["code"], "unsupported_operation");
+        assert_eq!(value["widget_id"], "btn");
+        assert_eq!(value["kind"], "Button");
+        assert_eq!(value["command"], "SetWidgetText");
+        assert_eq!(
+            value["message"],
+            "SetWidgetText is not supported on Button widget 'btn'"
+        );
+
+        let decoded: ErrorReport = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded, report);
+    }
+
+    #[test]
+    fn test_code_matches_serialized_tag() {
+        let errors = [
+            VellumError::widget_not_found("a"),
+            VellumError::DuplicateId {
+                widget_id: "a".to_string(),
+            },
+            VellumError::InvalidArgument {
+                widget_id: None,
+                reason: "threshold".to_string(),
+            },
+            VellumError::internal("channel closed"),
+        ];
+        for error in errors {
+            let value = serde_json::to_value(&error).unwrap();
+            assert_eq!(value["code"], error.code());
+        }
+        assert_eq!(VellumError::widget_not_found("a").widget_id(), Some("a"));
+    }
+}
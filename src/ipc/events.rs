@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::errors::ErrorReport;
+use super::window::WindowState;
+
 /// Events generated by the UI thread and sent to the JS runtime
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UiEvent {
@@ -8,10 +11,50 @@ pub enum UiEvent {
         widget_id: String,
         action: WidgetActionKind,
     },
+    /// Keyframe animation on a widget finished or was cancelled
+    AnimationEnded { widget_id: String, cancelled: bool },
+    /// Observed widget was laid out at a new size (logical pixels)
+    WidgetResized {
+        widget_id: String,
+        width: f64,
+        height: f64,
+    },
+    /// Observed widget crossed its visibility threshold; `ratio` is the
+    /// visible fraction (0..1)
+    IntersectionChanged { widget_id: String, ratio: f64 },
+    /// The window state differs from the last one reported; checked after
+    /// window commands and layout passes
+    WindowStateChanged(WindowState),
+    /// Files dragged from the OS entered the window. Position and target are
+    /// best-effort: winit doesn't report the pointer during an OS drag, so
+    /// they come from the last pointer move seen in the window and are
+    /// usually absent. Positions are in window coordinates.
+    FileHoverEnter {
+        paths: Vec<String>,
+        x: Option<f64>,
+        y: Option<f64>,
+        /// Innermost JS widget under the pointer
+        target_widget_id: Option<String>,
+    },
+    /// The file drag left the window or was cancelled
+    FileHoverLeave {},
+    /// Files were dropped on the window; this also ends the hover
+    FileDropped {
+        paths: Vec<String>,
+        x: Option<f64>,
+        y: Option<f64>,
+        target_widget_id: Option<String>,
+    },
+    /// Outcome of a command sent with a request id
+    CommandResponse {
+        request_id: u64,
+        result: Option<serde_json::Value>,
+        error: Option<ErrorReport>,
+    },
     /// Runtime error emitted by Rust side and forwarded to JS.
     RuntimeError {
         source: String,
-        message: String,
+        error: ErrorReport,
         fatal: bool,
     },
 }
@@ -54,25 +97,43 @@ mod tests {
     fn test_runtime_error_serialization() {
         let event = UiEvent::RuntimeError {
             source: "js".to_string(),
-            message: "Syntax Error".to_string(),
+            error: ErrorReport::new(crate::ipc::VellumError::internal("Syntax Error"), None),
             fatal: true,
         };
 
         let serialized = serde_json::to_string(&event).unwrap();
         assert!(serialized.contains("RuntimeError"));
+        assert!(serialized.contains(r#""code":"internal""#));
 
         let deserialized: UiEvent = serde_json::from_str(&serialized).unwrap();
         if let UiEvent::RuntimeError {
             source,
-            message,
+            error,
             fatal,
         } = deserialized
         {
             assert_eq!(source, "js");
-            assert_eq!(message, "Syntax Error");
+            assert_eq!(error.message, "Syntax Error");
             assert!(fatal);
         } else {
             panic!("Expected RuntimeError");
         }
     }
+
+    #[test]
+    fn test_file_drag_events_are_maps() {
+        // Clients look events up by key, so even the empty leave event is a map
+        let leave = serde_json::to_value(UiEvent::FileHoverLeave {}).unwrap();
+        assert_eq!(leave, serde_json::json!({ "FileHoverLeave": {} }));
+
+        let dropped = serde_json::to_value(UiEvent::FileDropped {
+            paths: vec!["/tmp/a.png".to_string()],
+            x: Some(10.0),
+            y: None,
+            target_widget_id: Some("dropzone".to_string()),
+        })
+        .unwrap();
+        assert_eq!(dropped["FileDropped"]["paths"][0], "/tmp/a.png");
+        assert_eq!(dropped["FileDropped"]["target_widget_id"], "dropzone");
+    }
 }
@@ -0,0 +1,178 @@
+use std::io;
+use std::path::Path;
+
+use super::{VellumError, WidgetKind};
+
+/// Environment variable through which the process that spawns the runtime
+/// hands it the session token its client will present.
+pub const SESSION_TOKEN_ENV: &str = "VELLUM_SESSION_TOKEN";
+
+/// Version of the wire protocol spoken by this server.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest client protocol version this server still understands.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional protocol features this server implements. A client lists the
+/// ones it uses in `Hello.capabilities`; `Welcome.features` is the overlap.
+/// `native_styles` (protocol 2): styles and widget params are sent as nested
+/// maps in `style` / `params` instead of `style_json` / `widget_params_json`.
+/// `shared_buffers` (Linux): large payloads are passed as `/dev/shm` files
+/// registered with `RegisterBuffer` and referenced by `buffer_id`.
+/// `window_control`: the `SetWindow*` commands, `RequestWindowAttention`,
+/// `GetWindowState` and `WindowStateChanged` events.
+/// `cursor`: the `cursor` style property and `SetCursor`.
+pub const FEATURES: &[&str] = &[
+    "requests",
+    "typed_errors",
+    "observers",
+    "geometry_queries",
+    "native_styles",
+    "window_control",
+    "cursor",
+    #[cfg(target_os = "linux")]
+    "shared_buffers",
+];
+
+/// Features that need the client on the same machine; they are not offered
+/// over network transports.
+pub const SAME_HOST_FEATURES: &[&str] = &["shared_buffers"];
+
+/// What both sides agreed on at connect time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub protocol_version: u32,
+    pub client_name: String,
+    pub features: Vec<String>,
+}
+
+impl Session {
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// Check a client's `Hello` and agree on the features to use, or explain why
+/// the client is incompatible.
+pub fn negotiate(
+    protocol_version: u32,
+    client_name: String,
+    capabilities: &[String],
+) -> Result<Session, VellumError> {
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version) {
+        return Err(VellumError::IncompatibleProtocol {
+            client_version: Some(protocol_version),
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+        });
+    }
+    let features = FEATURES
+        .iter()
+        .filter(|feature| capabilities.iter().any(|c| c == *feature))
+        .map(|feature| feature.to_string())
+        .collect();
+    Ok(Session {
+        protocol_version,
+        client_name,
+        features,
+    })
+}
+
+/// Shared secret a client must present in `Hello.token` before it may drive
+/// the UI.
+#[derive(Clone, PartialEq)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    /// The token handed down in `VELLUM_SESSION_TOKEN`, if any.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(SESSION_TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(SessionToken)
+    }
+
+    /// A fresh 256-bit token, hex encoded.
+    pub fn generate() -> io::Result<Self> {
+        let bytes = random_bytes()?;
+        Ok(SessionToken(
+            bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+        ))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Compare in constant time, so the token can't be guessed byte by byte.
+    pub fn matches(&self, presented: &str) -> bool {
+        let (expected, presented) = (self.0.as_bytes(), presented.as_bytes());
+        expected.len() == presented.len()
+            && expected
+                .iter()
+                .zip(presented)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// Write the token to a file only the current user can read, for clients
+    /// that were not spawned with it.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        io::Write::write_all(&mut options.open(path)?, self.0.as_bytes())
+    }
+}
+
+impl std::fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SessionToken(..)")
+    }
+}
+
+pub(crate) fn random_bytes() -> io::Result<[u8; 32]> {
+    let mut bytes = [0; 32];
+    getrandom::fill(&mut bytes).map_err(io::Error::from)?;
+    Ok(bytes)
+}
+
+/// Built-in widget kinds, as advertised in `Welcome.supported_widget_kinds`.
+pub fn supported_widget_kinds() -> Vec<String> {
+    WidgetKind::BUILTIN
+        .iter()
+        .map(|kind| kind.style_key().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_keeps_shared_features() {
+        let capabilities = vec!["requests".to_string(), "telepathy".to_string()];
+        let session = negotiate(PROTOCOL_VERSION, "test".to_string(), &capabilities).unwrap();
+        assert_eq!(session.features, ["requests"]);
+        assert!(session.has_feature("requests"));
+        assert!(!session.has_feature("telepathy"));
+    }
+
+    #[test]
+    fn test_session_token_matching() {
+        let token = SessionToken::generate().unwrap();
+        assert_eq!(token.as_str().len(), 64);
+        assert!(token.matches(token.as_str()));
+        assert!(!token.matches(""));
+        assert!(!token.matches(&token.as_str()[1..]));
+        assert_ne!(token, SessionToken::generate().unwrap());
+    }
+
+    #[test]
+    fn test_negotiate_rejects_unknown_versions() {
+        for version in [MIN_PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            let error = negotiate(version, "test".to_string(), &[]).unwrap_err();
+            assert_eq!(error.code(), "incompatible_protocol");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::{Duration, Instant};
+
+/// Largest frame accepted by default (64 MiB); larger payloads should use shared buffers.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+/// Sustained messages per second a client may send by default.
+pub const DEFAULT_RATE_LIMIT: u32 = 20_000;
+/// Messages a client may send at once before being throttled (e.g. mounting a large tree).
+pub const DEFAULT_RATE_BURST: u32 = 50_000;
+/// How long a new client may take to send its `Hello` by default.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Frames in a row that aren't even a typed message, after which the stream is
+/// assumed desynchronized.
+pub const MAX_CONSECUTIVE_CORRUPT_FRAMES: u32 = 8;
+
+/// Limits applied to a client connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpcLimits {
+    pub max_frame_len: usize,
+    /// Messages per second; `0` disables rate limiting
+    pub rate_limit: u32,
+    pub rate_burst: u32,
+    /// Time allowed between accepting a client and its `Hello`
+    pub handshake_timeout: Duration,
+}
+
+impl Default for IpcLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            rate_limit: DEFAULT_RATE_LIMIT,
+            rate_burst: DEFAULT_RATE_BURST,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+}
+
+impl IpcLimits {
+    /// Defaults, overridden by `VELLUM_MAX_FRAME_BYTES`, `VELLUM_RATE_LIMIT`
+    /// and `VELLUM_RATE_BURST`. Unparseable values are ignored with a warning.
+    pub fn from_env() -> Self {
+        let mut limits = IpcLimits::default();
+        if let Some(max) = env_number::<usize>("VELLUM_MAX_FRAME_BYTES") {
+            limits.max_frame_len = max;
+        }
+        if let Some(rate) = env_number::<u32>("VELLUM_RATE_LIMIT") {
+            limits.rate_limit = rate;
+        }
+        if let Some(burst) = env_number::<u32>("VELLUM_RATE_BURST") {
+            limits.rate_burst = burst;
+        }
+        limits
+    }
+
+    pub fn rate_limiter(&self) -> Option<RateLimiter> {
+        (self.rate_limit > 0).then(|| RateLimiter::new(self.rate_limit, self.rate_burst))
+    }
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let raw = std::env::var(name).ok()?;
+    let parsed = raw.trim().parse().ok();
+    if parsed.is_none() {
+        eprintln!("[IPC] Ignoring {name}={raw}: not a number");
+    }
+    parsed
+}
+
+/// Token bucket: `burst` messages at once, refilled at `rate` per second.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: f64::from(rate),
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token for one message at `now`, or return how long to wait
+    /// until one is available.
+    pub fn acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    /// Block until a message may be processed. Throttling (rather than
+    /// dropping) keeps the command stream intact; the socket applies backpressure.
+    pub fn throttle(&mut self) {
+        while let Err(wait) = self.acquire(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_allows_burst_then_refills() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(10, 3);
+        for _ in 0..3 {
+            assert!(limiter.acquire(start).is_ok());
+        }
+        let wait = limiter.acquire(start).unwrap_err();
+        assert!((wait.as_secs_f64() - 0.1).abs() < 1e-9);
+
+        // One token per 100ms, never more than the burst
+        assert!(limiter.acquire(start + Duration::from_millis(100)).is_ok());
+        assert!(limiter.acquire(start + Duration::from_millis(100)).is_err());
+        for _ in 0..3 {
+            assert!(limiter.acquire(start + Duration::from_secs(60)).is_ok());
+        }
+        assert!(limiter.acquire(start + Duration::from_secs(60)).is_err());
+    }
+
+    #[test]
+    fn test_zero_rate_disables_limiting() {
+        let limits = IpcLimits {
+            rate_limit: 0,
+            ..IpcLimits::default()
+        };
+        assert!(limits.rate_limiter().is_none());
+    }
+}
@@ -1,14 +1,31 @@
 // IPC (Inter-Process Communication) Module
 // Handles communication between the UI thread and JS runtime thread
 
+pub mod animation;
 pub mod channels;
 pub mod color;
 pub mod commands;
+pub mod errors;
 pub mod events;
+pub mod handshake;
+pub mod limits;
 pub mod msgpack;
 pub mod server;
+pub mod shared_buffer;
+pub mod style_vars;
+pub mod theme;
+pub mod transform;
+pub mod transport;
+pub mod websocket;
+pub mod window;
 
+pub use animation::{AnimationDirection, Easing, Keyframe};
 pub use channels::*;
 pub use color::ColorValue;
 pub use commands::*;
+pub use errors::{ErrorReport, VellumError, strict_mode};
 pub use events::*;
+pub use shared_buffer::{PayloadBytes, SharedBuffers};
+pub use theme::ThemeTokens;
+pub use transform::{OriginLength, TransformOrigin, TransformValue};
+pub use window::{WindowOptions, WindowState};
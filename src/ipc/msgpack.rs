@@ -1,9 +1,13 @@
+use std::collections::HashMap;
 use std::io::{self, Read, Write};
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::UiEvent;
+use super::{
+    BoxStyle, CursorStyle, ErrorReport, PaddingValue, TransformOrigin, TransformValue, UiEvent,
+    WidgetParams, WindowOptions,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -11,17 +15,61 @@ pub enum ServerMessage {
     UiEvent {
         event: UiEvent,
     },
+    /// Carries the report's `code`, `message`, `widget_id` and `command` at the top level.
     RuntimeError {
         source: String,
-        message: String,
         fatal: bool,
+        #[serde(flatten)]
+        error: ErrorReport,
+    },
+    /// Answer to a client message that carried a `request_id`: `error` is set
+    /// if the command failed, otherwise `result` holds its value, if any.
+    Response {
+        request_id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<ErrorReport>,
+    },
+    /// Reply to the client's `Hello` once its protocol version was accepted.
+    Welcome {
+        server_version: String,
+        protocol_version: u32,
+        supported_widget_kinds: Vec<String>,
+        features: Vec<String>,
+        /// Frames longer than this close the connection
+        max_frame_len: usize,
     },
     Shutdown,
 }
 
+/// A client message as framed on the wire. Any message may carry a
+/// `request_id`, which asks for a `ServerMessage::Response` once it was handled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientFrame {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<u64>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ClientMessage {
+    /// Must be the first message on a connection; answered by `Welcome`.
+    Hello {
+        protocol_version: u32,
+        client_name: String,
+        #[serde(default)]
+        capabilities: Vec<String>,
+        /// The session token the runtime was started with
+        #[serde(default)]
+        token: Option<String>,
+        /// Initial window attributes; only the first client's are used, since
+        /// the window is shown once it has connected
+        #[serde(default)]
+        window: Option<WindowOptions>,
+    },
     SetTitle {
         title: String,
     },
@@ -30,10 +78,34 @@ pub enum ClientMessage {
         kind: String,
         parent_id: Option<String>,
         text: Option<String>,
+        #[serde(default)]
         style_json: Option<String>,
+        #[serde(default)]
         widget_params_json: Option<String>,
+        /// Native form of `style_json` (`native_styles` feature); wins if both are set.
+        #[serde(default)]
+        style: Option<NativeStyle>,
+        /// Native form of `widget_params_json` (`native_styles` feature).
+        #[serde(default)]
+        params: Option<WidgetParams>,
         #[serde(default, with = "serde_bytes")]
         data: Option<Vec<u8>>,
+        /// Shared buffer holding `data` instead (`shared_buffers` feature).
+        #[serde(default)]
+        buffer_id: Option<String>,
+        #[serde(default)]
+        classes: Option<Vec<String>>,
+    },
+    DefineStyleClass {
+        name: String,
+        #[serde(default)]
+        style_json: Option<String>,
+        #[serde(default)]
+        style: Option<NativeStyle>,
+    },
+    SetWidgetClasses {
+        id: String,
+        classes: Vec<String>,
     },
     RemoveWidget {
         id: String,
@@ -48,7 +120,25 @@ pub enum ClientMessage {
     },
     SetWidgetStyle {
         id: String,
-        style_json: String,
+        #[serde(default)]
+        style_json: Option<String>,
+        #[serde(default)]
+        style: Option<NativeStyle>,
+    },
+    Animate {
+        id: String,
+        keyframes_json: String,
+        duration_ms: f64,
+        #[serde(default)]
+        easing: Option<String>,
+        /// Omitted means 1; `Infinity` repeats forever.
+        #[serde(default)]
+        iterations: Option<f64>,
+        #[serde(default)]
+        direction: Option<String>,
+    },
+    CancelAnimation {
+        id: String,
     },
     SetStyleProperty {
         id: String,
@@ -63,16 +153,84 @@ pub enum ClientMessage {
         id: String,
         checked: bool,
     },
+    SetTheme {
+        theme_json: String,
+    },
+    SetThemeVariant {
+        variant: Option<String>,
+    },
+    SetStyleVariables {
+        variables: HashMap<String, Option<String>>,
+    },
     ResizeWindow {
         width: u32,
         height: u32,
     },
     CloseWindow,
+    SetWindowMinimized {
+        minimized: bool,
+    },
+    SetWindowMaximized {
+        maximized: bool,
+    },
+    SetWindowFullscreen {
+        fullscreen: bool,
+    },
+    SetWindowPosition {
+        x: f64,
+        y: f64,
+    },
+    /// Both null removes the limit
+    SetWindowMinSize {
+        #[serde(default)]
+        width: Option<f64>,
+        #[serde(default)]
+        height: Option<f64>,
+    },
+    SetWindowMaxSize {
+        #[serde(default)]
+        width: Option<f64>,
+        #[serde(default)]
+        height: Option<f64>,
+    },
+    SetWindowAlwaysOnTop {
+        always_on_top: bool,
+    },
+    SetWindowDecorations {
+        decorations: bool,
+    },
+    SetWindowIcon {
+        #[serde(default, with = "serde_bytes")]
+        data: Option<Vec<u8>>,
+    },
+    RequestWindowAttention {
+        #[serde(default)]
+        critical: bool,
+    },
+    GetWindowState,
+    /// Null clears the override
+    SetCursor {
+        #[serde(default)]
+        cursor: Option<CursorStyle>,
+    },
     ExitApp,
     SetImageData {
         id: String,
-        #[serde(with = "serde_bytes")]
-        data: Vec<u8>,
+        #[serde(default, with = "serde_bytes")]
+        data: Option<Vec<u8>>,
+        /// Shared buffer holding `data` instead (`shared_buffers` feature).
+        #[serde(default)]
+        buffer_id: Option<String>,
+    },
+    /// Map the client's `/dev/shm` file `path` of `len` bytes as buffer
+    /// `buffer_id`, for later commands to reference. The server unlinks the file.
+    RegisterBuffer {
+        buffer_id: String,
+        path: String,
+        len: u64,
+    },
+    ReleaseBuffer {
+        buffer_id: String,
     },
     PlayVideo {
         id: String,
@@ -84,6 +242,30 @@ pub enum ClientMessage {
         id: String,
         time_secs: f64,
     },
+    GetWidgetRect {
+        id: String,
+    },
+    GetWindowSize,
+    HitTest {
+        x: f64,
+        y: f64,
+    },
+    ObserveResize {
+        id: String,
+    },
+    UnobserveResize {
+        id: String,
+    },
+    ObserveIntersection {
+        id: String,
+        #[serde(default)]
+        root_id: Option<String>,
+        #[serde(default)]
+        threshold: Option<f64>,
+    },
+    UnobserveIntersection {
+        id: String,
+    },
 }
 
 pub fn write_msgpack_frame<W, T>(writer: &mut W, value: &T) -> io::Result<()>
@@ -105,22 +287,408 @@ where
     Ok(())
 }
 
-pub fn read_msgpack_frame<R, T>(reader: &mut R) -> io::Result<T>
+pub fn read_msgpack_frame<R, T>(reader: &mut R, max_len: usize) -> io::Result<T>
 where
     R: Read,
     T: DeserializeOwned,
 {
+    decode_msgpack(&read_frame_payload(reader, max_len)?)
+}
+
+/// Read one length-prefixed frame without decoding it. A length above
+/// `max_len` is rejected before anything is allocated; the stream can't be
+/// trusted after that, so callers should disconnect.
+pub fn read_frame_payload<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Vec<u8>> {
     let mut len_bytes = [0_u8; 4];
     reader.read_exact(&mut len_bytes)?;
     let len = u32::from_le_bytes(len_bytes) as usize;
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds the {max_len} byte limit"),
+        ));
+    }
 
     let mut payload = vec![0_u8; len];
     reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
 
-    rmp_serde::from_slice::<T>(&payload).map_err(|e| {
+pub fn decode_msgpack<T: DeserializeOwned>(payload: &[u8]) -> io::Result<T> {
+    rmp_serde::from_slice::<T>(payload).map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData,
             format!("msgpack decode failed: {e}"),
         )
     })
 }
+
+/// The parts of a client frame every message shares. Decoded on its own when
+/// the full message doesn't decode, so the failure can still be attributed.
+#[derive(Debug, Deserialize)]
+pub struct FrameHeader {
+    #[serde(default)]
+    pub request_id: Option<u64>,
+    #[serde(rename = "type")]
+    pub message_type: String,
+}
+
+/// A style sent as a nested map (`native_styles`). Unparseable `padding`,
+/// `transform` and `transformOrigin` values are set aside in `dropped` as
+/// `(property, raw value)`, as the `style_json` path does, rather than failing
+/// the whole message.
+#[derive(Debug, Clone, Default)]
+pub struct NativeStyle {
+    pub style: BoxStyle,
+    pub dropped: Vec<(String, String)>,
+}
+
+/// The shorthands kept raw, so a bad one can be dropped on its own.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawNativeStyle {
+    #[serde(default)]
+    padding: Option<serde_json::Value>,
+    #[serde(default)]
+    transform: Option<serde_json::Value>,
+    #[serde(default)]
+    transform_origin: Option<serde_json::Value>,
+    #[serde(flatten)]
+    style: BoxStyle,
+}
+
+/// Parse one raw shorthand, recording it in `dropped` if it's invalid.
+fn lenient<T: DeserializeOwned>(
+    property: &str,
+    raw: Option<serde_json::Value>,
+    dropped: &mut Vec<(String, String)>,
+) -> Option<T> {
+    let raw = raw?;
+    match T::deserialize(&raw) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            let raw = match raw {
+                serde_json::Value::String(raw) => raw,
+                other => other.to_string(),
+            };
+            dropped.push((property.to_string(), raw));
+            None
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NativeStyle {
+    fn deserialize<D>(deserializer: D) -> Result<NativeStyle, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = RawNativeStyle::deserialize(deserializer)?;
+        let mut dropped = Vec::new();
+        let mut style = raw.style;
+        style.padding = lenient::<PaddingValue>("padding", raw.padding, &mut dropped);
+        style.transform = lenient::<TransformValue>("transform", raw.transform, &mut dropped);
+        style.transform_origin =
+            lenient::<TransformOrigin>("transformOrigin", raw.transform_origin, &mut dropped);
+        Ok(NativeStyle { style, dropped })
+    }
+}
+
+impl Serialize for NativeStyle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.style.serialize(serializer)
+    }
+}
+
+impl ClientMessage {
+    /// Protocol feature a client must have negotiated to send this message,
+    /// with the name of the command that needs it.
+    pub fn required_feature(&self) -> Option<(&'static str, &'static str)> {
+        let required = match self {
+            ClientMessage::RegisterBuffer { .. } => ("shared_buffers", "RegisterBuffer"),
+            ClientMessage::ReleaseBuffer { .. } => ("shared_buffers", "ReleaseBuffer"),
+            ClientMessage::CreateWidget {
+                buffer_id: Some(_), ..
+            } => ("shared_buffers", "CreateWidget"),
+            ClientMessage::SetImageData {
+                buffer_id: Some(_), ..
+            } => ("shared_buffers", "SetImageData"),
+            ClientMessage::CreateWidget { style, params, .. }
+                if style.is_some() || params.is_some() =>
+            {
+                ("native_styles", "CreateWidget")
+            }
+            ClientMessage::DefineStyleClass { style: Some(_), .. } => {
+                ("native_styles", "DefineStyleClass")
+            }
+            ClientMessage::SetWidgetStyle { style: Some(_), .. } => {
+                ("native_styles", "SetWidgetStyle")
+            }
+            ClientMessage::SetWindowMinimized { .. } => ("window_control", "SetWindowMinimized"),
+            ClientMessage::SetWindowMaximized { .. } => ("window_control", "SetWindowMaximized"),
+            ClientMessage::SetWindowFullscreen { .. } => ("window_control", "SetWindowFullscreen"),
+            ClientMessage::SetWindowPosition { .. } => ("window_control", "SetWindowPosition"),
+            ClientMessage::SetWindowMinSize { .. } => ("window_control", "SetWindowMinSize"),
+            ClientMessage::SetWindowMaxSize { .. } => ("window_control", "SetWindowMaxSize"),
+            ClientMessage::SetWindowAlwaysOnTop { .. } => {
+                ("window_control", "SetWindowAlwaysOnTop")
+            }
+            ClientMessage::SetWindowDecorations { .. } => {
+                ("window_control", "SetWindowDecorations")
+            }
+            ClientMessage::SetWindowIcon { .. } => ("window_control", "SetWindowIcon"),
+            ClientMessage::RequestWindowAttention { .. } => {
+                ("window_control", "RequestWindowAttention")
+            }
+            ClientMessage::GetWindowState => ("window_control", "GetWindowState"),
+            ClientMessage::SetCursor { .. } => ("cursor", "SetCursor"),
+            ClientMessage::GetWidgetRect { .. } => ("geometry_queries", "GetWidgetRect"),
+            ClientMessage::GetWindowSize => ("geometry_queries", "GetWindowSize"),
+            ClientMessage::HitTest { .. } => ("geometry_queries", "HitTest"),
+            ClientMessage::ObserveResize { .. } => ("observers", "ObserveResize"),
+            ClientMessage::UnobserveResize { .. } => ("observers", "UnobserveResize"),
+            ClientMessage::ObserveIntersection { .. } => ("observers", "ObserveIntersection"),
+            ClientMessage::UnobserveIntersection { .. } => ("observers", "UnobserveIntersection"),
+            _ => return None,
+        };
+        Some(required)
+    }
+}
+
+impl FrameHeader {
+    /// The message type as a command name (`createWidget` → `CreateWidget`).
+    pub fn command_name(&self) -> String {
+        let mut chars = self.message_type.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// Deterministic xorshift generator for the property tests, so failures reproduce.
+#[cfg(test)]
+pub(crate) struct TestRng(u64);
+
+#[cfg(test)]
+impl TestRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Corrupt `bytes` with a few random flips, truncations and insertions.
+    pub(crate) fn mutate(&mut self, bytes: &mut Vec<u8>) {
+        for _ in 0..1 + self.below(4) {
+            match self.below(3) {
+                0 if !bytes.is_empty() => {
+                    let i = self.below(bytes.len());
+                    bytes[i] = self.next() as u8;
+                }
+                1 if !bytes.is_empty() => bytes.truncate(self.below(bytes.len())),
+                _ => {
+                    let i = self.below(bytes.len() + 1);
+                    bytes.insert(i, self.next() as u8);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::limits::DEFAULT_MAX_FRAME_LEN;
+
+    #[test]
+    fn test_oversized_frame_is_rejected_before_allocating() {
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&u32::MAX.to_le_bytes());
+        stream.extend_from_slice(&[0; 16]);
+        let error = read_frame_payload(&mut stream.as_slice(), 1024).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_random_streams_respect_frame_limit() {
+        const MAX_LEN: usize = 256;
+        let mut rng = TestRng::new(0x5eed);
+        for _ in 0..2_000 {
+            let mut stream = Vec::new();
+            for _ in 0..rng.below(4) {
+                // Mostly plausible lengths, sometimes huge ones
+                let len = if rng.below(4) == 0 {
+                    rng.next() as u32
+                } else {
+                    rng.below(2 * MAX_LEN) as u32
+                };
+                stream.extend_from_slice(&len.to_le_bytes());
+                stream.extend((0..rng.below(2 * MAX_LEN)).map(|_| rng.next() as u8));
+            }
+            let mut reader = stream.as_slice();
+            while let Ok(payload) = read_frame_payload(&mut reader, MAX_LEN) {
+                assert!(payload.len() <= MAX_LEN);
+                let _ = decode_msgpack::<ClientFrame>(&payload);
+                let _ = decode_msgpack::<FrameHeader>(&payload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_client_frame_request_id_roundtrip() {
+        let frame = ClientFrame {
+            request_id: Some(7),
+            message: ClientMessage::SetImageData {
+                id: "img".to_string(),
+                data: Some(vec![1, 2, 3]),
+                buffer_id: None,
+            },
+        };
+        let mut buf = Vec::new();
+        write_msgpack_frame(&mut buf, &frame).unwrap();
+        let decoded: ClientFrame =
+            read_msgpack_frame(&mut buf.as_slice(), DEFAULT_MAX_FRAME_LEN).unwrap();
+        assert_eq!(decoded.request_id, Some(7));
+        assert!(matches!(
+            decoded.message,
+            ClientMessage::SetImageData { ref id, data: Some(ref data), .. }
+                if id == "img" && data == &[1, 2, 3]
+        ));
+
+        // Messages without a request id keep decoding as before
+        let mut buf = Vec::new();
+        write_msgpack_frame(&mut buf, &ClientMessage::CloseWindow).unwrap();
+        let decoded: ClientFrame =
+            read_msgpack_frame(&mut buf.as_slice(), DEFAULT_MAX_FRAME_LEN).unwrap();
+        assert_eq!(decoded.request_id, None);
+        assert!(matches!(decoded.message, ClientMessage::CloseWindow));
+    }
+
+    #[test]
+    fn test_native_style_decodes_from_nested_map() {
+        let frame = serde_json::json!({
+            "type": "createWidget",
+            "id": "card",
+            "kind": "slider",
+            "parent_id": null,
+            "text": null,
+            "style": {
+                "padding": "8, 16",
+                "background": "#ff0000",
+                "transition": { "properties": "background, width", "duration": 150 },
+            },
+            "params": { "minValue": 0, "maxValue": 10, "value": 3 },
+        });
+        let payload = rmp_serde::to_vec_named(&frame).unwrap();
+        let decoded: ClientFrame = decode_msgpack(&payload).unwrap();
+        let ClientMessage::CreateWidget {
+            style: Some(NativeStyle { style, dropped }),
+            params: Some(params),
+            style_json: None,
+            ..
+        } = decoded.message
+        else {
+            panic!("expected a native CreateWidget");
+        };
+        assert!(dropped.is_empty());
+        assert_eq!(
+            style.padding,
+            Some(crate::ipc::PaddingValue::Sides {
+                top: 8.0,
+                right: 16.0,
+                bottom: 8.0,
+                left: 16.0,
+            })
+        );
+        assert_eq!(
+            style.transition.unwrap().properties,
+            ["background", "width"]
+        );
+        assert_eq!(params.max_value, Some(10.0));
+        assert_eq!(params.value, Some(3.0));
+    }
+
+    #[test]
+    fn test_native_style_drops_invalid_shorthands() {
+        let frame = serde_json::json!({
+            "type": "setWidgetStyle",
+            "id": "card",
+            "style": {
+                "padding": "oops",
+                "transform": "perspective(10px)",
+                "transformOrigin": { "x": 1 },
+                "background": "#ff0000",
+                "opacity": 0.5,
+            },
+        });
+        let payload = rmp_serde::to_vec_named(&frame).unwrap();
+        let decoded: ClientFrame = decode_msgpack(&payload).unwrap();
+        let ClientMessage::SetWidgetStyle {
+            style: Some(NativeStyle { style, dropped }),
+            ..
+        } = decoded.message
+        else {
+            panic!("expected a native SetWidgetStyle");
+        };
+        assert!(style.padding.is_none() && style.transform.is_none());
+        assert!(style.transform_origin.is_none());
+        assert!(style.background.is_some());
+        assert_eq!(style.opacity, Some(0.5));
+        assert_eq!(
+            dropped,
+            [
+                ("padding".to_string(), "oops".to_string()),
+                ("transform".to_string(), "perspective(10px)".to_string()),
+                ("transformOrigin".to_string(), r#"{"x":1}"#.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frame_header_survives_bad_message() {
+        let frame = serde_json::json!({
+            "type": "setWidgetStyle",
+            "request_id": 4,
+            "id": "card",
+            "style": { "fontSize": "huge" },
+        });
+        let payload = rmp_serde::to_vec_named(&frame).unwrap();
+        assert!(decode_msgpack::<ClientFrame>(&payload).is_err());
+        let header: FrameHeader = decode_msgpack(&payload).unwrap();
+        assert_eq!(header.request_id, Some(4));
+        assert_eq!(header.command_name(), "SetWidgetStyle");
+    }
+
+    #[test]
+    fn test_runtime_error_frame_is_flat() {
+        let message = ServerMessage::RuntimeError {
+            source: "ui-handler".to_string(),
+            fatal: false,
+            error: ErrorReport::new(
+                crate::ipc::VellumError::widget_not_found("chart"),
+                Some("SetWidgetStyle"),
+            ),
+        };
+        let mut buf = Vec::new();
+        write_msgpack_frame(&mut buf, &message).unwrap();
+        let decoded: serde_json::Value =
+            read_msgpack_frame(&mut buf.as_slice(), DEFAULT_MAX_FRAME_LEN).unwrap();
+        assert_eq!(decoded["type"], "runtimeError");
+        assert_eq!(decoded["code"], "widget_not_found");
+        assert_eq!(decoded["widget_id"], "chart");
+        assert_eq!(decoded["command"], "SetWidgetStyle");
+        assert_eq!(decoded["message"], "Widget 'chart' not found");
+    }
+}
@@ -3,58 +3,56 @@
 
 // removed pub mod style_parser;
 
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Write};
 use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
 use std::time::Duration;
 
-use crate::ipc::msgpack::{ClientMessage, ServerMessage, read_msgpack_frame, write_msgpack_frame};
-use crate::ipc::{BoxStyle, ClientCommand, IpcServerChannels, UiEvent, WidgetData, WidgetKind};
-use crate::socket::{bind_socket, get_socket_path};
+use crate::ipc::handshake::{self, Session, SessionToken};
+use crate::ipc::limits::{IpcLimits, MAX_CONSECUTIVE_CORRUPT_FRAMES};
+use crate::ipc::msgpack::{
+    ClientFrame, ClientMessage, FrameHeader, NativeStyle, ServerMessage, decode_msgpack,
+    read_frame_payload, read_msgpack_frame, write_msgpack_frame,
+};
+use crate::ipc::style_vars::is_var_reference;
+use crate::ipc::transport::{Connection, Transport};
+use crate::ipc::{
+    AnimationDirection, BoxStyle, ClientCommand, ClientCommandSender, Easing, ErrorReport,
+    IpcServerChannels, Keyframe, PaddingValue, PayloadBytes, SharedBuffers, ThemeTokens,
+    TransformOrigin, TransformValue, UiEvent, UiEventReceiver, VellumError, WidgetData, WidgetKind,
+    WidgetParams, WindowOptions, strict_mode,
+};
 
 /// Run the JS runtime bridge on a background thread.
 ///
-/// This binds a Unix Domain Socket and communicates via
-/// length-prefixed MsgPack frames.
-pub fn run_ipc_server(channels: IpcServerChannels) {
-    if let Err(e) = run_socket_server(channels) {
-        eprintln!("[IPC] Runtime socket error: {e}");
+/// This serves clients connecting through `transport` (see `transport.rs`)
+/// and communicates via length-prefixed MsgPack frames. With `reconnect`,
+/// the app outlives a client and waits for the next one.
+pub fn run_ipc_server(channels: IpcServerChannels, transport: Box<dyn Transport>, reconnect: bool) {
+    if let Err(e) = serve(channels, transport, reconnect) {
+        eprintln!("[IPC] Runtime bridge error: {e}");
     }
 }
 
-#[derive(Debug)]
-struct RuntimeErrorReport {
-    source: String,
-    message: String,
-    fatal: bool,
-}
-
-fn write_runtime_error(
-    stream: &mut impl std::io::Write,
-    source: impl Into<String>,
-    message: impl Into<String>,
-    fatal: bool,
-) -> std::io::Result<()> {
-    write_msgpack_frame(
-        stream,
-        &ServerMessage::RuntimeError {
-            source: source.into(),
-            message: message.into(),
-            fatal,
-        },
-    )
-}
-
-fn runtime_error_from_ui_event(event: UiEvent) -> ServerMessage {
+fn server_message_from_ui_event(event: UiEvent) -> ServerMessage {
     match event {
         UiEvent::RuntimeError {
             source,
-            message,
+            error,
             fatal,
         } => ServerMessage::RuntimeError {
             source,
-            message,
-            fatal,
+            fatal: fatal || strict_mode(),
+            error,
+        },
+        UiEvent::CommandResponse {
+            request_id,
+            result,
+            error,
+        } => ServerMessage::Response {
+            request_id,
+            result,
+            error,
         },
         other => ServerMessage::UiEvent { event: other },
     }
@@ -85,58 +83,215 @@ fn parse_widget_kind(kind: &str) -> WidgetKind {
     }
 }
 
-fn parse_padding_shorthand(raw: &str) -> Option<serde_json::Value> {
-    let values: Vec<f64> = raw
-        .split(',')
-        .map(str::trim)
-        .filter(|segment| !segment.is_empty())
-        .map(str::parse::<f64>)
-        .collect::<Result<_, _>>()
-        .ok()?;
-
-    match values.as_slice() {
-        [all] => Some(serde_json::json!(*all)),
-        [vertical, horizontal] => Some(serde_json::json!({
-            "top": *vertical,
-            "right": *horizontal,
-            "bottom": *vertical,
-            "left": *horizontal
-        })),
-        [top, horizontal, bottom] => Some(serde_json::json!({
-            "top": *top,
-            "right": *horizontal,
-            "bottom": *bottom,
-            "left": *horizontal
-        })),
-        [top, right, bottom, left] => Some(serde_json::json!({
-            "top": *top,
-            "right": *right,
-            "bottom": *bottom,
-            "left": *left
-        })),
-        _ => None,
+/// Move whole-value `var(--name)` references out of the style object into
+/// `varRefs`, so they are resolved on the UI thread instead of failing to parse.
+fn extract_var_refs(obj: &mut serde_json::Map<String, serde_json::Value>) {
+    let refs: Vec<(String, serde_json::Value)> = obj
+        .iter()
+        .filter(|(key, value)| {
+            key.as_str() != "varRefs" && value.as_str().is_some_and(is_var_reference)
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if refs.is_empty() {
+        return;
+    }
+
+    for (key, _) in &refs {
+        obj.remove(key);
+    }
+    let var_refs = obj
+        .entry("varRefs")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if let Some(var_refs) = var_refs.as_object_mut() {
+        var_refs.extend(refs);
+    }
+}
+
+/// A style property dropped during normalization, as `(property, raw value)`.
+type DroppedProperty = (String, String);
+
+fn normalize_style_value(value: &mut serde_json::Value, dropped: &mut Vec<DroppedProperty>) {
+    if let Some(obj) = value.as_object_mut() {
+        extract_var_refs(obj);
+        drop_invalid_shorthands(obj, dropped);
+    }
+}
+
+/// Drop unparseable `padding` / `transform` / `transformOrigin` strings so
+/// they don't invalidate the rest of the style.
+fn drop_invalid_shorthands(
+    obj: &mut serde_json::Map<String, serde_json::Value>,
+    dropped: &mut Vec<DroppedProperty>,
+) {
+    let mut drop_if = |property: &str, invalid: fn(&str) -> bool| {
+        if let Some(raw) = obj.get(property).and_then(|v| v.as_str())
+            && invalid(raw)
+        {
+            dropped.push((property.to_string(), raw.to_string()));
+            obj.remove(property);
+        }
+    };
+    drop_if("padding", |raw| PaddingValue::parse(raw).is_none());
+    drop_if("transform", |css| TransformValue::parse(css).is_none());
+    drop_if("transformOrigin", |css| {
+        TransformOrigin::parse(css).is_none()
+    });
+}
+
+/// Parse a style object, dropping invalid shorthands (recorded in `dropped`)
+/// rather than rejecting the whole style.
+fn parse_box_style_lossy(
+    style_json: &str,
+    dropped: &mut Vec<DroppedProperty>,
+) -> Result<BoxStyle, String> {
+    let mut value = serde_json::from_str::<serde_json::Value>(style_json)
+        .map_err(|e| format!("invalid style JSON: {e}"))?;
+    normalize_style_value(&mut value, dropped);
+    serde_json::from_value::<BoxStyle>(value).map_err(|e| format!("invalid style: {e}"))
+}
+
+/// Report each dropped property as a non-fatal `InvalidStyle` warning.
+fn report_dropped(
+    dropped: Vec<DroppedProperty>,
+    widget_id: Option<&str>,
+    command: &str,
+    warnings: &mut Vec<ErrorReport>,
+) {
+    warnings.extend(dropped.into_iter().map(|(property, value)| {
+        ErrorReport::new(
+            VellumError::InvalidStyle {
+                widget_id: widget_id.map(str::to_string),
+                property,
+                value,
+                reason: "unrecognized value, property ignored".to_string(),
+            },
+            Some(command),
+        )
+    }));
+}
+
+/// Unwrap a native style, reporting the properties decoding set aside.
+fn take_native_style(
+    native: NativeStyle,
+    widget_id: Option<&str>,
+    command: &str,
+    warnings: &mut Vec<ErrorReport>,
+) -> BoxStyle {
+    report_dropped(native.dropped, widget_id, command, warnings);
+    native.style
+}
+
+/// Parse the style of a command. Invalid parts are reported as warnings and
+/// left out, so the command still applies what could be parsed.
+fn parse_command_style(
+    style_json: &str,
+    widget_id: Option<&str>,
+    command: &str,
+    warnings: &mut Vec<ErrorReport>,
+) -> Option<BoxStyle> {
+    let mut dropped = Vec::new();
+    let style = parse_box_style_lossy(style_json, &mut dropped);
+    report_dropped(dropped, widget_id, command, warnings);
+    match style {
+        Ok(style) => Some(style),
+        Err(reason) => {
+            warnings.push(ErrorReport::new(
+                VellumError::DecodeFailed {
+                    widget_id: widget_id.map(str::to_string),
+                    what: "style".to_string(),
+                    reason,
+                },
+                Some(command),
+            ));
+            None
+        }
+    }
+}
+
+/// Normalize a map of per-kind widget styles: style shorthands are expanded
+/// and kind aliases (`label`, `text_input`, ...) are keyed by `WidgetKind::style_key`.
+fn normalize_theme_widgets(widgets: &mut serde_json::Value, dropped: &mut Vec<DroppedProperty>) {
+    let Some(obj) = widgets.as_object_mut() else {
+        return;
+    };
+    let entries = std::mem::take(obj);
+    for (kind, mut style) in entries {
+        normalize_style_value(&mut style, dropped);
+        let key = parse_widget_kind(&kind).style_key().to_string();
+        obj.insert(key, style);
     }
 }
 
-fn parse_box_style_lossy(style_json: &str) -> Option<BoxStyle> {
-    let mut value = serde_json::from_str::<serde_json::Value>(style_json).ok()?;
+fn parse_theme(
+    theme_json: &str,
+    dropped: &mut Vec<DroppedProperty>,
+) -> Result<ThemeTokens, String> {
+    let mut value = serde_json::from_str::<serde_json::Value>(theme_json)
+        .map_err(|e| format!("invalid theme JSON: {e}"))?;
 
-    if let Some(obj) = value.as_object_mut()
-        && let Some(padding_value) = obj.get("padding").and_then(|v| v.as_str())
-    {
-        if let Some(parsed_padding) = parse_padding_shorthand(padding_value) {
-            obj.insert("padding".to_string(), parsed_padding);
-        } else {
-            obj.remove("padding");
+    if let Some(widgets) = value.get_mut("widgets") {
+        normalize_theme_widgets(widgets, dropped);
+    }
+    if let Some(variants) = value.get_mut("variants").and_then(|v| v.as_object_mut()) {
+        for variant in variants.values_mut() {
+            if let Some(widgets) = variant.get_mut("widgets") {
+                normalize_theme_widgets(widgets, dropped);
+            }
         }
     }
 
-    serde_json::from_value::<BoxStyle>(value).ok()
+    serde_json::from_value::<ThemeTokens>(value).map_err(|e| format!("invalid theme tokens: {e}"))
+}
+
+/// Parse `[{ offset?, ...style }]` keyframes; style shorthands and variable
+/// references are normalized like any other style object.
+fn parse_keyframes(
+    keyframes_json: &str,
+    dropped: &mut Vec<DroppedProperty>,
+) -> Result<Vec<Keyframe>, String> {
+    let value = serde_json::from_str::<serde_json::Value>(keyframes_json)
+        .map_err(|e| format!("invalid keyframes JSON: {e}"))?;
+    let serde_json::Value::Array(frames) = value else {
+        return Err("keyframes must be an array".to_string());
+    };
+
+    frames
+        .into_iter()
+        .map(|mut frame| {
+            let offset = match frame.as_object_mut().and_then(|obj| obj.remove("offset")) {
+                Some(serde_json::Value::Number(n)) => n.as_f64(),
+                Some(other) => return Err(format!("invalid keyframe offset: {other}")),
+                None => None,
+            };
+            normalize_style_value(&mut frame, dropped);
+            let style = serde_json::from_value::<BoxStyle>(frame)
+                .map_err(|e| format!("invalid keyframe style: {e}"))?;
+            Ok(Keyframe { offset, style })
+        })
+        .collect()
 }
 
-fn handle_client_message(message: ClientMessage) -> Option<ClientCommand> {
-    match message {
-        ClientMessage::SetTitle { title } => Some(ClientCommand::SetTitle(title)),
+/// Map a protocol message to a command, or report why it was rejected.
+/// Problems that don't reject the command (e.g. dropped style properties)
+/// are added to `warnings`. Messages handled here on the IPC thread (shared
+/// buffer registration) yield no command.
+fn handle_client_message(
+    message: ClientMessage,
+    buffers: &mut SharedBuffers,
+    warnings: &mut Vec<ErrorReport>,
+) -> Result<Option<ClientCommand>, ErrorReport> {
+    Ok(Some(match message {
+        ClientMessage::Hello { .. } => {
+            return Err(ErrorReport::new(
+                VellumError::InvalidArgument {
+                    widget_id: None,
+                    reason: "Hello is only valid as the first message".to_string(),
+                },
+                Some("Hello"),
+            ));
+        }
+        ClientMessage::SetTitle { title } => ClientCommand::SetTitle(title),
         ClientMessage::CreateWidget {
             id,
             kind,
@@ -144,151 +299,391 @@ fn handle_client_message(message: ClientMessage) -> Option<ClientCommand> {
             text,
             style_json,
             widget_params_json,
+            style: native_style,
+            params: native_params,
             data,
+            buffer_id,
+            classes,
         } => {
             let parsed_kind = parse_widget_kind(&kind);
-            let widget_data = build_widget_data(
-                &parsed_kind,
-                style_json.as_deref(),
-                widget_params_json.as_deref(),
-                data,
-            );
-            Some(ClientCommand::CreateWidget {
+            let data = buffers
+                .resolve(buffer_id.as_deref(), data)
+                .map_err(|e| ErrorReport::new(e, Some("CreateWidget")))?;
+            let params = match native_params {
+                Some(params) => params,
+                None => parse_json_params(
+                    widget_params_json.as_deref(),
+                    style_json.as_deref(),
+                    &id,
+                    warnings,
+                ),
+            };
+            let style = match native_style {
+                Some(native) => Some(take_native_style(
+                    native,
+                    Some(&id),
+                    "CreateWidget",
+                    warnings,
+                )),
+                None => style_json.as_deref().and_then(|style_json| {
+                    parse_command_style(style_json, Some(&id), "CreateWidget", warnings)
+                }),
+            };
+            let widget_data = build_widget_data(&parsed_kind, params, data);
+            ClientCommand::CreateWidget {
                 id,
                 kind: parsed_kind,
                 parent_id,
                 text,
-                style: style_json.as_deref().and_then(parse_box_style_lossy),
+                style,
                 data: widget_data,
-            })
+                classes: classes.unwrap_or_default(),
+            }
         }
-        ClientMessage::RemoveWidget { id } => Some(ClientCommand::RemoveWidget { id }),
-        ClientMessage::SetWidgetText { id, text } => {
-            Some(ClientCommand::SetWidgetText { id, text })
+        ClientMessage::DefineStyleClass {
+            name,
+            style_json,
+            style,
+        } => ClientCommand::DefineStyleClass {
+            name,
+            style: match style {
+                Some(native) => take_native_style(native, None, "DefineStyleClass", warnings),
+                None => style_json
+                    .as_deref()
+                    .and_then(|style_json| {
+                        parse_command_style(style_json, None, "DefineStyleClass", warnings)
+                    })
+                    .unwrap_or_default(),
+            },
+        },
+        ClientMessage::SetWidgetClasses { id, classes } => {
+            ClientCommand::SetWidgetClasses { id, classes }
         }
+        ClientMessage::RemoveWidget { id } => ClientCommand::RemoveWidget { id },
+        ClientMessage::SetWidgetText { id, text } => ClientCommand::SetWidgetText { id, text },
         ClientMessage::SetWidgetVisible { id, visible } => {
-            Some(ClientCommand::SetWidgetVisible { id, visible })
+            ClientCommand::SetWidgetVisible { id, visible }
         }
-        ClientMessage::SetWidgetStyle { id, style_json } => Some(ClientCommand::SetWidgetStyle {
+        ClientMessage::SetWidgetStyle {
             id,
-            style: parse_box_style_lossy(&style_json).unwrap_or_default(),
-        }),
+            style_json,
+            style,
+        } => {
+            let style = match style {
+                Some(native) => take_native_style(native, Some(&id), "SetWidgetStyle", warnings),
+                None => style_json
+                    .as_deref()
+                    .and_then(|style_json| {
+                        parse_command_style(style_json, Some(&id), "SetWidgetStyle", warnings)
+                    })
+                    .unwrap_or_default(),
+            };
+            ClientCommand::SetWidgetStyle { id, style }
+        }
+        ClientMessage::Animate {
+            id,
+            keyframes_json,
+            duration_ms,
+            easing,
+            iterations,
+            direction,
+        } => {
+            let mut dropped = Vec::new();
+            let keyframes = parse_keyframes(&keyframes_json, &mut dropped);
+            report_dropped(dropped, Some(&id), "Animate", warnings);
+            let keyframes = keyframes.map_err(|reason| {
+                ErrorReport::new(
+                    VellumError::DecodeFailed {
+                        widget_id: Some(id.clone()),
+                        what: "keyframes".to_string(),
+                        reason,
+                    },
+                    Some("Animate"),
+                )
+            })?;
+            let easing = easing
+                .as_deref()
+                .map(|raw| {
+                    Easing::parse(raw).unwrap_or_else(|| {
+                        warnings.push(ErrorReport::new(
+                            VellumError::InvalidArgument {
+                                widget_id: Some(id.clone()),
+                                reason: format!("unknown easing '{raw}', using 'ease'"),
+                            },
+                            Some("Animate"),
+                        ));
+                        Easing::default()
+                    })
+                })
+                .unwrap_or_default();
+            let direction = direction
+                .as_deref()
+                .map(|raw| {
+                    AnimationDirection::parse(raw).unwrap_or_else(|| {
+                        warnings.push(ErrorReport::new(
+                            VellumError::InvalidArgument {
+                                widget_id: Some(id.clone()),
+                                reason: format!("unknown direction '{raw}', using 'normal'"),
+                            },
+                            Some("Animate"),
+                        ));
+                        AnimationDirection::default()
+                    })
+                })
+                .unwrap_or_default();
+            let invalid = |reason: String| {
+                ErrorReport::new(
+                    VellumError::InvalidArgument {
+                        widget_id: Some(id.clone()),
+                        reason,
+                    },
+                    Some("Animate"),
+                )
+            };
+            if !(duration_ms.is_finite() && duration_ms >= 0.0) {
+                return Err(invalid(format!(
+                    "duration_ms must be a non-negative number, got {duration_ms}"
+                )));
+            }
+            // Infinity repeats forever
+            let iterations = iterations.unwrap_or(1.0);
+            if iterations.is_nan() || iterations < 0.0 {
+                return Err(invalid(format!(
+                    "iterations must be a non-negative number or Infinity, got {iterations}"
+                )));
+            }
+            ClientCommand::Animate {
+                id,
+                keyframes,
+                duration_ms,
+                easing,
+                iterations,
+                direction,
+            }
+        }
+        ClientMessage::CancelAnimation { id } => ClientCommand::CancelAnimation { id },
         ClientMessage::SetStyleProperty {
             id,
             property,
             value,
-        } => Some(ClientCommand::SetStyleProperty {
+        } => ClientCommand::SetStyleProperty {
             id,
             property,
             value,
-        }),
-        ClientMessage::SetWidgetValue { id, value } => {
-            Some(ClientCommand::SetWidgetValue { id, value })
-        }
+        },
+        ClientMessage::SetWidgetValue { id, value } => ClientCommand::SetWidgetValue { id, value },
         ClientMessage::SetWidgetChecked { id, checked } => {
-            Some(ClientCommand::SetWidgetChecked { id, checked })
+            ClientCommand::SetWidgetChecked { id, checked }
+        }
+        ClientMessage::SetTheme { theme_json } => {
+            let mut dropped = Vec::new();
+            let theme = parse_theme(&theme_json, &mut dropped);
+            report_dropped(dropped, None, "SetTheme", warnings);
+            ClientCommand::SetTheme {
+                theme: theme.map_err(|reason| {
+                    ErrorReport::new(
+                        VellumError::DecodeFailed {
+                            widget_id: None,
+                            what: "theme".to_string(),
+                            reason,
+                        },
+                        Some("SetTheme"),
+                    )
+                })?,
+            }
+        }
+        ClientMessage::SetThemeVariant { variant } => ClientCommand::SetThemeVariant { variant },
+        ClientMessage::SetStyleVariables { variables } => {
+            ClientCommand::SetStyleVariables { variables }
         }
         ClientMessage::ResizeWindow { width, height } => {
-            Some(ClientCommand::ResizeWindow { width, height })
+            ClientCommand::ResizeWindow { width, height }
+        }
+        ClientMessage::CloseWindow => ClientCommand::CloseWindow,
+        ClientMessage::SetWindowMinimized { minimized } => {
+            ClientCommand::SetWindowMinimized(minimized)
+        }
+        ClientMessage::SetWindowMaximized { maximized } => {
+            ClientCommand::SetWindowMaximized(maximized)
+        }
+        ClientMessage::SetWindowFullscreen { fullscreen } => {
+            ClientCommand::SetWindowFullscreen(fullscreen)
+        }
+        ClientMessage::SetWindowPosition { x, y } => {
+            if !(x.is_finite() && y.is_finite()) {
+                return Err(ErrorReport::new(
+                    VellumError::InvalidArgument {
+                        widget_id: None,
+                        reason: format!("window position must be finite, got ({x}, {y})"),
+                    },
+                    Some("SetWindowPosition"),
+                ));
+            }
+            ClientCommand::SetWindowPosition { x, y }
+        }
+        ClientMessage::SetWindowMinSize { width, height } => ClientCommand::SetWindowMinSize(
+            window_size_limit(width, height)
+                .map_err(|e| ErrorReport::new(e, Some("SetWindowMinSize")))?,
+        ),
+        ClientMessage::SetWindowMaxSize { width, height } => ClientCommand::SetWindowMaxSize(
+            window_size_limit(width, height)
+                .map_err(|e| ErrorReport::new(e, Some("SetWindowMaxSize")))?,
+        ),
+        ClientMessage::SetWindowAlwaysOnTop { always_on_top } => {
+            ClientCommand::SetWindowAlwaysOnTop(always_on_top)
+        }
+        ClientMessage::SetWindowDecorations { decorations } => {
+            ClientCommand::SetWindowDecorations(decorations)
+        }
+        ClientMessage::SetWindowIcon { data } => ClientCommand::SetWindowIcon(data),
+        ClientMessage::RequestWindowAttention { critical } => {
+            ClientCommand::RequestWindowAttention { critical }
+        }
+        ClientMessage::GetWindowState => ClientCommand::GetWindowState,
+        ClientMessage::SetCursor { cursor } => ClientCommand::SetCursor(cursor),
+        ClientMessage::ExitApp => ClientCommand::ExitApp,
+        ClientMessage::SetImageData {
+            id,
+            data,
+            buffer_id,
+        } => {
+            let data = buffers
+                .resolve(buffer_id.as_deref(), data)
+                .and_then(|data| {
+                    data.ok_or_else(|| VellumError::InvalidArgument {
+                        widget_id: Some(id.clone()),
+                        reason: "SetImageData needs data or a buffer_id".to_string(),
+                    })
+                })
+                .map_err(|e| ErrorReport::new(e, Some("SetImageData")))?;
+            ClientCommand::SetImageData { id, data }
+        }
+        ClientMessage::RegisterBuffer {
+            buffer_id,
+            path,
+            len,
+        } => {
+            buffers
+                .register(buffer_id, &path, len)
+                .map_err(|e| ErrorReport::new(e, Some("RegisterBuffer")))?;
+            return Ok(None);
         }
-        ClientMessage::CloseWindow => Some(ClientCommand::CloseWindow),
-        ClientMessage::ExitApp => Some(ClientCommand::ExitApp),
-        ClientMessage::SetImageData { id, data } => Some(ClientCommand::SetImageData { id, data }),
-        ClientMessage::PlayVideo { id } => Some(ClientCommand::PlayVideo { id }),
-        ClientMessage::PauseVideo { id } => Some(ClientCommand::PauseVideo { id }),
-        ClientMessage::SeekVideo { id, time_secs } => {
-            Some(ClientCommand::SeekVideo { id, time_secs })
+        ClientMessage::ReleaseBuffer { buffer_id } => {
+            if !buffers.release(&buffer_id) {
+                return Err(ErrorReport::new(
+                    VellumError::InvalidArgument {
+                        widget_id: None,
+                        reason: format!("unknown shared buffer '{buffer_id}'"),
+                    },
+                    Some("ReleaseBuffer"),
+                ));
+            }
+            return Ok(None);
+        }
+        ClientMessage::PlayVideo { id } => ClientCommand::PlayVideo { id },
+        ClientMessage::PauseVideo { id } => ClientCommand::PauseVideo { id },
+        ClientMessage::SeekVideo { id, time_secs } => ClientCommand::SeekVideo { id, time_secs },
+        ClientMessage::GetWidgetRect { id } => ClientCommand::GetWidgetRect { id },
+        ClientMessage::GetWindowSize => ClientCommand::GetWindowSize,
+        ClientMessage::HitTest { x, y } => ClientCommand::HitTest { x, y },
+        ClientMessage::ObserveResize { id } => ClientCommand::ObserveResize { id },
+        ClientMessage::UnobserveResize { id } => ClientCommand::UnobserveResize { id },
+        ClientMessage::ObserveIntersection {
+            id,
+            root_id,
+            threshold,
+        } => {
+            let threshold = threshold.unwrap_or(0.0);
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(ErrorReport::new(
+                    VellumError::InvalidArgument {
+                        widget_id: Some(id),
+                        reason: format!("threshold {threshold} is outside 0..1"),
+                    },
+                    Some("ObserveIntersection"),
+                ));
+            }
+            ClientCommand::ObserveIntersection {
+                id,
+                root_id,
+                threshold,
+            }
+        }
+        ClientMessage::UnobserveIntersection { id } => ClientCommand::UnobserveIntersection { id },
+    }))
+}
+
+/// Widget params of a JSON-encoded `CreateWidget`. Keys missing from the
+/// params are also looked up in the style, where older clients put them.
+fn parse_json_params(
+    params_json: Option<&str>,
+    style_json: Option<&str>,
+    widget_id: &str,
+    warnings: &mut Vec<ErrorReport>,
+) -> WidgetParams {
+    let params = match params_json.map(serde_json::from_str::<WidgetParams>) {
+        Some(Ok(params)) => params,
+        Some(Err(e)) => {
+            warnings.push(ErrorReport::new(
+                VellumError::DecodeFailed {
+                    widget_id: Some(widget_id.to_string()),
+                    what: "widget params".to_string(),
+                    reason: e.to_string(),
+                },
+                Some("CreateWidget"),
+            ));
+            WidgetParams::default()
+        }
+        None => WidgetParams::default(),
+    };
+    let from_style = style_json
+        .and_then(|json| serde_json::from_str::<WidgetParams>(json).ok())
+        .unwrap_or_default();
+    params.or(from_style)
+}
+
+/// Size limit of `SetWindowMinSize` / `SetWindowMaxSize`: both sides, or
+/// neither to remove the limit.
+fn window_size_limit(
+    width: Option<f64>,
+    height: Option<f64>,
+) -> Result<Option<(f64, f64)>, VellumError> {
+    match (width, height) {
+        (None, None) => Ok(None),
+        (Some(width), Some(height))
+            if width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0 =>
+        {
+            Ok(Some((width, height)))
         }
+        _ => Err(VellumError::InvalidArgument {
+            widget_id: None,
+            reason: format!(
+                "window size limit needs a positive width and height, or neither (got {width:?} x {height:?})"
+            ),
+        }),
     }
 }
 
-/// Build widget-specific data from params JSON and binary data.
-///
-/// For backward compatibility, some widget-specific fields are also read from
-/// `style_json` when params are absent. Flex/container layout fields are no
-/// longer parsed here and are sourced entirely from `BoxStyle`.
+/// Build widget-specific data from params and binary data.
 fn build_widget_data(
     kind: &WidgetKind,
-    style_json: Option<&str>,
-    params_json: Option<&str>,
-    data: Option<Vec<u8>>,
+    params: WidgetParams,
+    data: Option<PayloadBytes>,
 ) -> Option<WidgetData> {
-    let params_value =
-        params_json.and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok());
-    let style_value =
-        style_json.and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok());
-
-    let get_string = |key: &str| -> Option<String> {
-        params_value
-            .as_ref()
-            .and_then(|v| v.get(key))
-            .and_then(|v| v.as_str())
-            .or_else(|| {
-                style_value
-                    .as_ref()
-                    .and_then(|v| v.get(key))
-                    .and_then(|v| v.as_str())
-            })
-            .map(String::from)
-    };
-    let get_bool = |key: &str| -> Option<bool> {
-        params_value
-            .as_ref()
-            .and_then(|v| v.get(key))
-            .and_then(|v| v.as_bool())
-            .or_else(|| {
-                style_value
-                    .as_ref()
-                    .and_then(|v| v.get(key))
-                    .and_then(|v| v.as_bool())
-            })
-    };
-    let get_f64 = |key: &str| -> Option<f64> {
-        params_value
-            .as_ref()
-            .and_then(|v| v.get(key))
-            .and_then(|v| v.as_f64())
-            .or_else(|| {
-                style_value
-                    .as_ref()
-                    .and_then(|v| v.get(key))
-                    .and_then(|v| v.as_f64())
-            })
-            // Sometimes it comes in as an integer
-            .or_else(|| {
-                params_value
-                    .as_ref()
-                    .and_then(|v| v.get(key))
-                    .and_then(|v| v.as_i64())
-                    .map(|i| i as f64)
-            })
-            .or_else(|| {
-                style_value
-                    .as_ref()
-                    .and_then(|v| v.get(key))
-                    .and_then(|v| v.as_i64())
-                    .map(|i| i as f64)
-            })
-    };
-
     match kind {
         WidgetKind::Label => Some(WidgetData::Label),
 
         WidgetKind::Button => None,
 
-        WidgetKind::Svg => {
-            let svg_data = get_string("svgData")
-                .or_else(|| get_string("svg_data"))
-                .or_else(|| get_string("svg"));
-            Some(WidgetData::Svg { svg_data })
-        }
+        WidgetKind::Svg => Some(WidgetData::Svg {
+            svg_data: params.svg_data,
+        }),
 
         WidgetKind::Image => {
             let image_data = data?;
-            let object_fit = get_string("object_fit").or_else(|| get_string("objectFit"));
             Some(WidgetData::Image {
                 data: image_data,
-                object_fit,
+                object_fit: params.object_fit,
             })
         }
 
@@ -296,167 +691,1009 @@ fn build_widget_data(
 
         WidgetKind::SizedBox => Some(WidgetData::SizedBox),
 
-        WidgetKind::Checkbox => {
-            let checked = get_bool("checked").unwrap_or(false);
-            Some(WidgetData::Checkbox { checked })
-        }
+        WidgetKind::Checkbox => Some(WidgetData::Checkbox {
+            checked: params.checked.unwrap_or(false),
+        }),
 
-        WidgetKind::TextInput => {
-            let placeholder = get_string("placeholder");
-            Some(WidgetData::TextInput { placeholder })
-        }
+        WidgetKind::TextInput => Some(WidgetData::TextInput {
+            placeholder: params.placeholder,
+        }),
 
         WidgetKind::TextArea => Some(WidgetData::TextArea),
         WidgetKind::Prose => Some(WidgetData::Prose),
 
-        WidgetKind::ProgressBar => {
-            let progress = get_f64("progress").or_else(|| get_f64("value"));
-            Some(WidgetData::ProgressBar { progress })
-        }
+        WidgetKind::ProgressBar => Some(WidgetData::ProgressBar {
+            progress: params.progress.or(params.value),
+        }),
 
         WidgetKind::Spinner => Some(WidgetData::Spinner),
 
-        WidgetKind::Slider => {
-            let min = get_f64("minValue")
-                .or_else(|| get_f64("min_value"))
-                .or_else(|| get_f64("min"))
-                .unwrap_or(0.0);
-            let max = get_f64("maxValue")
-                .or_else(|| get_f64("max_value"))
-                .or_else(|| get_f64("max"))
-                .unwrap_or(1.0);
-            let value = get_f64("value")
-                .or_else(|| get_f64("progress"))
-                .unwrap_or(0.5);
-            let step = get_f64("step");
-            Some(WidgetData::Slider {
-                min,
-                max,
-                value,
-                step,
-            })
-        }
+        WidgetKind::Slider => Some(WidgetData::Slider {
+            min: params.min_value.unwrap_or(0.0),
+            max: params.max_value.unwrap_or(1.0),
+            value: params.value.or(params.progress).unwrap_or(0.5),
+            step: params.step,
+        }),
 
         WidgetKind::ZStack => Some(WidgetData::ZStack),
         WidgetKind::Portal => Some(WidgetData::Portal),
         WidgetKind::Grid => Some(WidgetData::Grid),
         WidgetKind::Hoverable => Some(WidgetData::Hoverable),
 
-        WidgetKind::Video => {
-            let src = get_string("src").unwrap_or_default();
-            Some(WidgetData::Video { src })
-        }
+        WidgetKind::Video => Some(WidgetData::Video {
+            src: params.src.unwrap_or_default(),
+        }),
 
         WidgetKind::Custom(name) => Some(WidgetData::Custom(name.clone())),
     }
 }
 
-fn run_socket_server(
+/// Refuse a message that relies on a feature the client didn't negotiate.
+fn check_negotiated(message: &ClientMessage, session: &Session) -> Result<(), ErrorReport> {
+    match message.required_feature() {
+        Some((feature, command)) if !session.has_feature(feature) => Err(ErrorReport::new(
+            VellumError::InvalidArgument {
+                widget_id: None,
+                reason: format!(
+                    "{command} needs the '{feature}' feature, which was not negotiated"
+                ),
+            },
+            Some(command),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Report a message that never became a command: requests get the error in
+/// their response, fire-and-forget messages through the runtime error channel.
+fn rejection(request_id: Option<u64>, error: ErrorReport) -> ServerMessage {
+    match request_id {
+        Some(request_id) => ServerMessage::Response {
+            request_id,
+            result: None,
+            error: Some(error),
+        },
+        None => ServerMessage::RuntimeError {
+            source: "ipc".to_string(),
+            fatal: strict_mode(),
+            error,
+        },
+    }
+}
+
+/// Fatal report for a client that broke the framing; the connection is closed after it.
+fn protocol_violation(reason: String) -> ServerMessage {
+    ServerMessage::RuntimeError {
+        source: "socket-read".to_string(),
+        fatal: true,
+        error: ErrorReport::new(VellumError::ProtocolViolation { reason }, None),
+    }
+}
+
+/// How a connection's handshake ended, short of an I/O failure.
+enum Handshake {
+    /// With the window attributes the client asked for, if any
+    Accepted(Session, Option<WindowOptions>),
+    /// Not our client (no valid token, or it never said `Hello`): the
+    /// connection is dropped and the server keeps waiting.
+    Refused(String),
+    /// Our client, but one this server can't serve: the app exits.
+    Failed(String),
+}
+
+/// Write a fatal handshake error and `Shutdown` to the connecting client.
+fn reject_hello(stream: &mut impl Write, error: VellumError) -> std::io::Result<String> {
+    let report = ErrorReport::new(error, Some("Hello"));
+    let message = report.message.clone();
+    write_msgpack_frame(
+        stream,
+        &ServerMessage::RuntimeError {
+            source: "handshake".to_string(),
+            fatal: true,
+            error: report,
+        },
+    )?;
+    write_msgpack_frame(stream, &ServerMessage::Shutdown)?;
+    Ok(message)
+}
+
+/// Read the client's `Hello`, check its session token (when the transport
+/// requires one) and answer with `Welcome`, or with a fatal `unauthorized` /
+/// `incompatible_protocol` error when the client can't be served.
+fn perform_handshake(
+    connection: &mut Connection,
+    limits: &IpcLimits,
+    token: Option<&SessionToken>,
+) -> std::io::Result<Handshake> {
+    // A client that connects and says nothing must not hold the transport,
+    // since nobody else is accepted meanwhile
+    connection.set_read_timeout(Some(limits.handshake_timeout))?;
+    let hello = read_msgpack_frame::<_, ClientFrame>(&mut connection.reader, limits.max_frame_len);
+    connection.set_read_timeout(None)?;
+    let stream = &mut connection.writer;
+    let message = match hello {
+        Ok(frame) => frame.message,
+        Err(e) => return Ok(Handshake::Refused(format!("no Hello received: {e}"))),
+    };
+    let (protocol_version, client_name, mut capabilities, window) = match message {
+        ClientMessage::Hello {
+            token: presented,
+            protocol_version,
+            client_name,
+            capabilities,
+            window,
+        } if token.is_none_or(|token| presented.as_deref().is_some_and(|p| token.matches(p))) => {
+            (protocol_version, client_name, capabilities, window)
+        }
+        ClientMessage::Hello { token: Some(_), .. } => {
+            let reason = "the session token does not match".to_string();
+            return reject_hello(stream, VellumError::Unauthorized { reason })
+                .map(Handshake::Refused);
+        }
+        ClientMessage::Hello { .. } => {
+            let reason = "the Hello is missing the session token".to_string();
+            return reject_hello(stream, VellumError::Unauthorized { reason })
+                .map(Handshake::Refused);
+        }
+        _ if token.is_some() => {
+            let reason = "the first message must be a Hello with the session token".to_string();
+            return reject_hello(stream, VellumError::Unauthorized { reason })
+                .map(Handshake::Refused);
+        }
+        // A client that skips the handshake speaks some other protocol
+        _ => {
+            let error = VellumError::IncompatibleProtocol {
+                client_version: None,
+                min_version: handshake::MIN_PROTOCOL_VERSION,
+                max_version: handshake::PROTOCOL_VERSION,
+            };
+            return reject_hello(stream, error).map(Handshake::Refused);
+        }
+    };
+    if !connection.same_host {
+        capabilities.retain(|c| !handshake::SAME_HOST_FEATURES.contains(&c.as_str()));
+    }
+    match handshake::negotiate(protocol_version, client_name, &capabilities) {
+        Ok(session) => {
+            write_msgpack_frame(
+                stream,
+                &ServerMessage::Welcome {
+                    server_version: env!("CARGO_PKG_VERSION").to_string(),
+                    protocol_version: session.protocol_version,
+                    supported_widget_kinds: handshake::supported_widget_kinds(),
+                    features: session.features.clone(),
+                    max_frame_len: limits.max_frame_len,
+                },
+            )?;
+            // Bad window attributes aren't worth refusing the client over
+            let mut window = window.filter(|window| match window.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("[IPC] Ignoring the client's window options: {e}");
+                    false
+                }
+            });
+            // The icon is a path on this machine, which a network client has
+            // no business reading (it can use SetWindowIcon with image data)
+            if !connection.same_host
+                && let Some(icon) = window.as_mut().and_then(|window| window.icon.take())
+            {
+                eprintln!("[IPC] Ignoring the window icon path '{icon}' from a remote client");
+            }
+            Ok(Handshake::Accepted(session, window))
+        }
+        Err(error) => reject_hello(stream, error).map(Handshake::Failed),
+    }
+}
+
+/// Why a client session ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SessionEnd {
+    /// The client closed its side of the connection.
+    ClientLeft,
+    /// The UI went away, a write failed or a fatal error was sent.
+    Stopped,
+}
+
+/// Serve clients until the app should exit. With `reconnect`, a client that
+/// disconnects is followed by the next one (e.g. a restarted dev client),
+/// starting from an empty UI; otherwise its departure exits the app.
+fn serve(
     channels: IpcServerChannels,
+    mut transport: Box<dyn Transport>,
+    reconnect: bool,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let command_sender = channels.command_sender;
     let event_receiver = channels.event_receiver;
+    let mut startup_window = Some(channels.startup_window);
 
-    let socket_path = get_socket_path();
-    println!("[IPC] Binding socket to {}", socket_path);
+    let limits = IpcLimits::from_env();
+    eprintln!("[IPC] Waiting for a client on {}...", transport.describe());
 
-    let listener = bind_socket(&socket_path).map_err(|e| format!("Failed to bind socket: {e}"))?;
+    'serve: loop {
+        // Serve the first client that authenticates; anyone else is turned away
+        let (connection, session, window) = loop {
+            let mut connection = match transport.accept() {
+                Ok(connection) => connection,
+                Err(e) => {
+                    // No client will ever drive this window
+                    let _ = command_sender.send(ClientCommand::ExitApp);
+                    return Err(e.into());
+                }
+            };
+            // The window closed while we waited (`main` connects to wake us)
+            if ui_closed(&event_receiver) {
+                break 'serve;
+            }
+            match perform_handshake(&mut connection, &limits, transport.session_token()) {
+                Ok(Handshake::Accepted(session, window)) => break (connection, session, window),
+                Ok(Handshake::Refused(reason)) => {
+                    eprintln!("[IPC] Refused connection: {reason}");
+                }
+                Ok(Handshake::Failed(reason)) => {
+                    let _ = command_sender.send(ClientCommand::ExitApp);
+                    return Err(format!("Handshake failed: {reason}").into());
+                }
+                Err(e) => eprintln!("[IPC] Handshake I/O error: {e}"),
+            }
+        };
+        eprintln!(
+            "[IPC] Client '{}' speaks protocol v{} (features: {})",
+            session.client_name,
+            session.protocol_version,
+            session.features.join(", ")
+        );
+        // `main` holds the window back until the first client said how it
+        // should look (or a timeout passed)
+        match startup_window.take() {
+            Some(sender) => {
+                if sender.send(window.clone()).is_err() && window.is_some() {
+                    eprintln!("[IPC] Ignoring window options sent after the window was shown");
+                }
+            }
+            None if window.is_some() => {
+                eprintln!("[IPC] Ignoring window options from a reconnecting client");
+            }
+            None => {}
+        }
 
-    println!("[IPC] Waiting for client connection...");
+        // Events about the previous client's widgets mean nothing to this one
+        while event_receiver.try_recv().is_ok() {}
 
-    // We just wait for the first client for now
-    let (mut stream, _) = listener.accept()?;
-    let mut read_stream = stream.try_clone()?;
+        let end = serve_session(
+            connection,
+            session,
+            limits,
+            &command_sender,
+            &event_receiver,
+        )?;
+        eprintln!("[IPC] Client connection closed");
+        if end == SessionEnd::ClientLeft && reconnect && !ui_closed(&event_receiver) {
+            let _ = command_sender.send(ClientCommand::ResetUi);
+            eprintln!("[IPC] Waiting for the next client...");
+            continue;
+        }
+        break;
+    }
+    drop(transport);
+
+    // The client is gone, exit the UI thread cleanly
+    let _ = command_sender.send(ClientCommand::ExitApp);
 
-    println!("[IPC] Client connected");
+    Ok(())
+}
+
+/// Whether the UI thread has exited and dropped its event sender.
+fn ui_closed(event_receiver: &UiEventReceiver) -> bool {
+    loop {
+        match event_receiver.try_recv() {
+            Ok(_) => continue,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => return true,
+        }
+    }
+}
+
+/// Pump frames between one connected client and the UI until either side
+/// goes away.
+fn serve_session(
+    mut connection: Connection,
+    session: Session,
+    limits: IpcLimits,
+    command_sender: &ClientCommandSender,
+    event_receiver: &UiEventReceiver,
+) -> Result<SessionEnd, Box<dyn std::error::Error + Send + Sync>> {
+    let mut read_stream = connection.take_reader();
+    let stream = &mut connection.writer;
 
     let command_sender_clone = command_sender.clone();
-    let (error_tx, error_rx) = mpsc::channel::<RuntimeErrorReport>();
+    // Frames produced by the read thread itself: runtime errors and responses
+    // to requests that never reached the UI thread.
+    let (report_tx, report_rx) = mpsc::channel::<ServerMessage>();
     let read_thread = thread::Builder::new()
         .name("js-bridge-read".to_string())
         .spawn(move || {
+            let mut buffers = SharedBuffers::default();
+            let mut rate_limiter = limits.rate_limiter();
+            let mut corrupt_frames = 0;
             loop {
-                match read_msgpack_frame::<_, ClientMessage>(&mut read_stream) {
-                    Ok(message) => {
-                        if let Some(cmd) = handle_client_message(message)
-                            && let Err(send_err) = command_sender_clone.send(cmd)
-                        {
-                            let _ = error_tx.send(RuntimeErrorReport {
+                let payload = match read_frame_payload(&mut read_stream, limits.max_frame_len) {
+                    Ok(payload) => payload,
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                    Err(e) => {
+                        // An untrusted length or a failed read leaves no frame
+                        // boundary to resume from
+                        let _ = report_tx.send(protocol_violation(e.to_string()));
+                        break;
+                    }
+                };
+                if let Some(rate_limiter) = rate_limiter.as_mut() {
+                    rate_limiter.throttle();
+                }
+                match decode_msgpack::<ClientFrame>(&payload) {
+                    Ok(ClientFrame {
+                        request_id,
+                        message,
+                    }) => {
+                        corrupt_frames = 0;
+                        if let Err(e) = check_negotiated(&message, &session) {
+                            let _ = report_tx.send(rejection(request_id, e));
+                            continue;
+                        }
+                        let mut warnings = Vec::new();
+                        let handled = handle_client_message(message, &mut buffers, &mut warnings);
+                        for warning in warnings {
+                            let _ = report_tx.send(ServerMessage::RuntimeError {
+                                source: "ipc".to_string(),
+                                fatal: strict_mode(),
+                                error: warning,
+                            });
+                        }
+                        let cmd = match handled {
+                            Ok(Some(cmd)) => cmd,
+                            Ok(None) => {
+                                if let Some(request_id) = request_id {
+                                    let _ = report_tx.send(ServerMessage::Response {
+                                        request_id,
+                                        result: None,
+                                        error: None,
+                                    });
+                                }
+                                continue;
+                            }
+                            Err(e) => {
+                                let _ = report_tx.send(rejection(request_id, e));
+                                continue;
+                            }
+                        };
+                        let sent = match request_id {
+                            Some(request_id) => command_sender_clone.send_request(cmd, request_id),
+                            None => command_sender_clone.send(cmd),
+                        };
+                        if let Err(send_err) = sent {
+                            let _ = report_tx.send(ServerMessage::RuntimeError {
                                 source: "ui-thread".to_string(),
-                                message: format!(
-                                    "Failed to dispatch JS command to UI thread: {send_err}"
-                                ),
                                 fatal: true,
+                                error: ErrorReport::new(
+                                    VellumError::internal(format!(
+                                        "Failed to dispatch JS command to UI thread: {send_err}"
+                                    )),
+                                    None,
+                                ),
                             });
                             break;
                         }
                     }
-                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
-                        break;
-                    }
                     Err(e) => {
-                        let _ = error_tx.send(RuntimeErrorReport {
-                            source: "socket-read".to_string(),
-                            message: format!("Failed to decode MsgPack command from JS: {e}"),
-                            fatal: false,
-                        });
+                        // The length prefix kept the stream in sync, so a frame whose
+                        // message doesn't decode (e.g. a field of the wrong type) is
+                        // answered and skipped. Only frames that aren't even a typed
+                        // message count as corruption: a run of them means the client
+                        // is writing garbage rather than messages.
+                        let header = decode_msgpack::<FrameHeader>(&payload).ok();
+                        if header.is_some() {
+                            corrupt_frames = 0;
+                        } else {
+                            corrupt_frames += 1;
+                            if corrupt_frames >= MAX_CONSECUTIVE_CORRUPT_FRAMES {
+                                let _ = report_tx.send(protocol_violation(format!(
+                                    "{corrupt_frames} undecodable frames in a row, last: {e}"
+                                )));
+                                break;
+                            }
+                        }
+                        let command = header.as_ref().map(FrameHeader::command_name);
+                        let error = ErrorReport::new(
+                            VellumError::DecodeFailed {
+                                widget_id: None,
+                                what: "MsgPack command from JS".to_string(),
+                                reason: e.to_string(),
+                            },
+                            command.as_deref(),
+                        );
+                        let _ = report_tx.send(rejection(header.and_then(|h| h.request_id), error));
                     }
                 }
             }
         })?;
 
-    let mut should_stop = false;
+    let mut end = None;
 
-    while !should_stop {
+    while end.is_none() {
         loop {
-            match error_rx.try_recv() {
+            match report_rx.try_recv() {
                 Ok(report) => {
-                    if let Err(write_err) = write_runtime_error(
-                        &mut stream,
-                        report.source,
-                        report.message,
-                        report.fatal,
-                    ) {
-                        eprintln!("[IPC] Failed to send runtimeError frame to JS: {write_err}");
-                        should_stop = true;
+                    let fatal = matches!(report, ServerMessage::RuntimeError { fatal: true, .. });
+                    if let Err(write_err) = write_msgpack_frame(stream, &report) {
+                        eprintln!("[IPC] Failed to send frame to JS: {write_err}");
+                        end = Some(SessionEnd::Stopped);
                         break;
                     }
-                    if report.fatal {
-                        should_stop = true;
+                    if fatal {
+                        end = Some(SessionEnd::Stopped);
                         break;
                     }
                 }
                 Err(mpsc::TryRecvError::Empty) => break,
-                Err(mpsc::TryRecvError::Disconnected) => break,
+                // The read thread only exits once the client stopped sending
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    end = Some(SessionEnd::ClientLeft);
+                    break;
+                }
             }
         }
 
-        if should_stop {
+        if end.is_some() {
             break;
         }
 
         match event_receiver.recv_timeout(Duration::from_millis(50)) {
             Ok(event) => {
-                let frame = runtime_error_from_ui_event(event);
-                if let Err(e) = write_msgpack_frame(&mut stream, &frame) {
+                let frame = server_message_from_ui_event(event);
+                if let Err(e) = write_msgpack_frame(stream, &frame) {
                     eprintln!("[IPC] Socket bridge write failed: {e}");
-                    break;
+                    end = Some(SessionEnd::Stopped);
+                } else if matches!(frame, ServerMessage::RuntimeError { fatal: true, .. }) {
+                    end = Some(SessionEnd::Stopped);
                 }
             }
             Err(RecvTimeoutError::Timeout) => {}
-            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Disconnected) => end = Some(SessionEnd::Stopped),
         }
     }
 
-    let _ = write_msgpack_frame(&mut stream, &ServerMessage::Shutdown);
+    let _ = write_msgpack_frame(stream, &ServerMessage::Shutdown);
+
+    // Wait for the reader when the transport can stop it; otherwise the
+    // process exit takes it down
+    if connection.close_read() {
+        let _ = read_thread.join();
+    }
 
-    let _ = read_thread.join();
-    let _ = std::fs::remove_file(socket_path);
+    Ok(end.unwrap_or(SessionEnd::Stopped))
+}
 
-    println!("[IPC] Socket connection closed");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::CursorStyle;
 
-    // JS runtime disconnected, exit the UI thread cleanly
-    let _ = command_sender.send(ClientCommand::ExitApp);
+    #[test]
+    fn test_invalid_style_properties_are_reported() {
+        let style =
+            serde_json::json!({ "padding": "1px oops", "transform": "wobble(3)", "fontSize": 14 });
+        let json = ClientMessage::SetWidgetStyle {
+            id: "box".to_string(),
+            style_json: Some(style.to_string()),
+            style: None,
+        };
+        // The same style as a native map must be just as lenient
+        let native = serde_json::json!({ "type": "setWidgetStyle", "id": "box", "style": style });
+        let native = decode_msgpack::<ClientFrame>(&rmp_serde::to_vec_named(&native).unwrap())
+            .unwrap()
+            .message;
 
-    Ok(())
+        for message in [json, native] {
+            let mut warnings = Vec::new();
+            let cmd = handle_client_message(message, &mut SharedBuffers::default(), &mut warnings)
+                .unwrap();
+
+            let Some(ClientCommand::SetWidgetStyle { style, .. }) = cmd else {
+                panic!("expected SetWidgetStyle");
+            };
+            assert_eq!(style.font_size, Some(14.0));
+            assert!(style.padding.is_none());
+            assert!(style.transform.is_none());
+
+            let dropped: Vec<_> = warnings
+                .iter()
+                .map(|report| match &report.error {
+                    VellumError::InvalidStyle {
+                        widget_id,
+                        property,
+                        ..
+                    } => (widget_id.as_deref(), property.as_str()),
+                    other => panic!("unexpected warning {other:?}"),
+                })
+                .collect();
+            assert_eq!(
+                dropped,
+                [(Some("box"), "padding"), (Some("box"), "transform")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_undecodable_style_is_reported() {
+        let mut warnings = Vec::new();
+        handle_client_message(
+            ClientMessage::DefineStyleClass {
+                name: "card".to_string(),
+                style_json: Some("{not json".to_string()),
+                style: None,
+            },
+            &mut SharedBuffers::default(),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].error.code(), "decode_failed");
+        assert_eq!(warnings[0].command.as_deref(), Some("DefineStyleClass"));
+    }
+
+    #[test]
+    fn test_animate_rejects_bad_timing_and_reports_unknown_names() {
+        let animate = |duration_ms: f64, iterations: Option<f64>, direction: Option<&str>| {
+            let mut warnings = Vec::new();
+            let result = handle_client_message(
+                ClientMessage::Animate {
+                    id: "box".to_string(),
+                    keyframes_json: r#"[{"style":{"opacity":0}},{"style":{"opacity":1}}]"#
+                        .to_string(),
+                    duration_ms,
+                    easing: None,
+                    iterations,
+                    direction: direction.map(str::to_string),
+                },
+                &mut SharedBuffers::default(),
+                &mut warnings,
+            );
+            (result, warnings)
+        };
+
+        let (result, warnings) = animate(200.0, Some(f64::INFINITY), Some("sideways"));
+        let Ok(Some(ClientCommand::Animate {
+            iterations,
+            direction,
+            ..
+        })) = result
+        else {
+            panic!("expected Animate");
+        };
+        assert_eq!(iterations, f64::INFINITY);
+        assert_eq!(direction, AnimationDirection::Normal);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].error.code(), "invalid_argument");
+
+        for (duration_ms, iterations) in [
+            (f64::NAN, None),
+            (-1.0, None),
+            (200.0, Some(f64::NAN)),
+            (200.0, Some(-2.0)),
+        ] {
+            let error = animate(duration_ms, iterations, None).0.unwrap_err();
+            assert_eq!(error.error.code(), "invalid_argument");
+            assert_eq!(error.command.as_deref(), Some("Animate"));
+        }
+    }
+
+    #[test]
+    fn test_window_size_limits_need_both_sides() {
+        let decode = |json: serde_json::Value| {
+            let payload = rmp_serde::to_vec_named(&json).unwrap();
+            let frame = decode_msgpack::<ClientFrame>(&payload).unwrap();
+            handle_client_message(
+                frame.message,
+                &mut SharedBuffers::default(),
+                &mut Vec::new(),
+            )
+        };
+        let command = decode(serde_json::json!({
+            "type": "setWindowMinSize", "width": 320.0, "height": 240.0,
+        }))
+        .unwrap();
+        assert!(matches!(
+            command,
+            Some(ClientCommand::SetWindowMinSize(Some((320.0, 240.0))))
+        ));
+        let cleared = decode(serde_json::json!({ "type": "setWindowMaxSize" })).unwrap();
+        assert!(matches!(
+            cleared,
+            Some(ClientCommand::SetWindowMaxSize(None))
+        ));
+
+        let error =
+            decode(serde_json::json!({ "type": "setWindowMaxSize", "width": 800.0 })).unwrap_err();
+        assert_eq!(error.error.code(), "invalid_argument");
+        assert_eq!(error.command.as_deref(), Some("SetWindowMaxSize"));
+    }
+
+    #[test]
+    fn test_cursor_decodes_from_styles_and_set_cursor() {
+        let decode = |json: serde_json::Value| {
+            let payload = rmp_serde::to_vec_named(&json).unwrap();
+            let frame = decode_msgpack::<ClientFrame>(&payload).unwrap();
+            handle_client_message(
+                frame.message,
+                &mut SharedBuffers::default(),
+                &mut Vec::new(),
+            )
+            .unwrap()
+        };
+        let styled = decode(serde_json::json!({
+            "type": "setWidgetStyle", "id": "card", "style": { "cursor": "pointer" },
+        }));
+        let Some(ClientCommand::SetWidgetStyle { style, .. }) = styled else {
+            panic!("expected SetWidgetStyle");
+        };
+        assert_eq!(style.cursor, Some(CursorStyle::Pointer));
+
+        let grabbing = decode(serde_json::json!({ "type": "setCursor", "cursor": "ew-resize" }));
+        assert!(matches!(
+            grabbing,
+            Some(ClientCommand::SetCursor(Some(CursorStyle::EwResize)))
+        ));
+        let cleared = decode(serde_json::json!({ "type": "setCursor", "cursor": null }));
+        assert!(matches!(cleared, Some(ClientCommand::SetCursor(None))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_handshake_requires_session_token() {
+        let token = SessionToken::generate().unwrap();
+        let limits = IpcLimits::default();
+        let hello = |token: Option<&str>| ClientMessage::Hello {
+            protocol_version: handshake::PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+            capabilities: Vec::new(),
+            token: token.map(str::to_string),
+            window: None,
+        };
+        let close = || ClientMessage::CloseWindow;
+        let cases = [
+            (Some(&token), hello(Some(token.as_str())), "welcome", None),
+            (
+                Some(&token),
+                hello(Some("guess")),
+                "runtimeError",
+                Some("unauthorized"),
+            ),
+            (
+                Some(&token),
+                hello(None),
+                "runtimeError",
+                Some("unauthorized"),
+            ),
+            (Some(&token), close(), "runtimeError", Some("unauthorized")),
+            // Transports only the parent can reach (stdio) need no token
+            (None, hello(None), "welcome", None),
+            (None, close(), "runtimeError", Some("incompatible_protocol")),
+        ];
+        for (token, message, reply, code) in cases {
+            let (server, mut client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let mut connection = Connection::new(server.try_clone().unwrap(), server);
+            let frame = ClientFrame {
+                request_id: None,
+                message,
+            };
+            write_msgpack_frame(&mut client, &frame).unwrap();
+            let outcome = perform_handshake(&mut connection, &limits, token).unwrap();
+            assert_eq!(
+                matches!(outcome, Handshake::Accepted(..)),
+                reply == "welcome"
+            );
+            let answer: serde_json::Value =
+                read_msgpack_frame(&mut client, limits.max_frame_len).unwrap();
+            assert_eq!(answer["type"], reply);
+            if let Some(code) = code {
+                assert_eq!(answer["code"], code);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_clients_are_not_offered_same_host_features() {
+        let (server, mut client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let mut connection = Connection::new(server.try_clone().unwrap(), server).remote();
+        let hello = ClientFrame {
+            request_id: None,
+            message: ClientMessage::Hello {
+                protocol_version: handshake::PROTOCOL_VERSION,
+                client_name: "remote".to_string(),
+                capabilities: vec!["requests".to_string(), "shared_buffers".to_string()],
+                token: None,
+                window: None,
+            },
+        };
+        write_msgpack_frame(&mut client, &hello).unwrap();
+        let limits = IpcLimits::default();
+        let Handshake::Accepted(session, _) =
+            perform_handshake(&mut connection, &limits, None).unwrap()
+        else {
+            panic!("handshake was not accepted");
+        };
+        assert_eq!(session.features, ["requests"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_clients_cannot_name_a_window_icon_path() {
+        for remote in [false, true] {
+            let (server, mut client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let mut connection = Connection::new(server.try_clone().unwrap(), server);
+            if remote {
+                connection = connection.remote();
+            }
+            let hello = ClientFrame {
+                request_id: None,
+                message: ClientMessage::Hello {
+                    protocol_version: handshake::PROTOCOL_VERSION,
+                    client_name: "test".to_string(),
+                    capabilities: Vec::new(),
+                    token: None,
+                    window: Some(WindowOptions {
+                        title: Some("Notes".to_string()),
+                        icon: Some("/home/someone/.ssh/id_ed25519".to_string()),
+                        ..Default::default()
+                    }),
+                },
+            };
+            write_msgpack_frame(&mut client, &hello).unwrap();
+            let Handshake::Accepted(_, Some(window)) =
+                perform_handshake(&mut connection, &IpcLimits::default(), None).unwrap()
+            else {
+                panic!("handshake was not accepted with window options");
+            };
+            assert_eq!(window.title.as_deref(), Some("Notes"));
+            assert_eq!(window.icon.is_some(), !remote);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_silent_client_is_refused_after_handshake_timeout() {
+        let (server, _client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let timer = server.try_clone().unwrap();
+        let mut connection = Connection::new(server.try_clone().unwrap(), server)
+            .with_read_timeout(move |timeout| timer.set_read_timeout(timeout));
+        let limits = IpcLimits {
+            handshake_timeout: Duration::from_millis(50),
+            ..IpcLimits::default()
+        };
+        let outcome = perform_handshake(&mut connection, &limits, None).unwrap();
+        assert!(matches!(outcome, Handshake::Refused(_)));
+    }
+
+    #[test]
+    fn test_mutated_frames_never_panic() {
+        let corpus = [
+            serde_json::json!({
+                "type": "createWidget", "request_id": 1, "id": "a", "kind": "slider",
+                "parent_id": null, "text": null,
+                "style": { "padding": "4, 8", "transition": { "properties": "all", "duration": 100 } },
+                "params": { "minValue": 0, "maxValue": 5 },
+            }),
+            serde_json::json!({
+                "type": "createWidget", "id": "b", "kind": "label", "parent_id": "a",
+                "text": "hi", "style_json": "{\"background\":\"var(--bg)\",\"padding\":\"2\"}",
+                "widget_params_json": "{\"svgData\":\"<svg/>\"}",
+            }),
+            serde_json::json!({ "type": "setWidgetStyle", "id": "b", "style": { "color": "#fff" } }),
+            serde_json::json!({
+                "type": "animate", "id": "b", "duration_ms": 200.0, "easing": "ease-in",
+                "keyframes_json": "[{\"offset\":0,\"style\":{\"opacity\":0}},{\"offset\":1,\"style\":{\"opacity\":1}}]",
+            }),
+            serde_json::json!({ "type": "setTheme", "theme_json": "{\"palette\":{\"accent\":\"#f00\"}}" }),
+            serde_json::json!({ "type": "observeIntersection", "id": "b", "threshold": 0.5 }),
+            serde_json::json!({ "type": "setImageData", "id": "img", "data": [137, 80, 78, 71] }),
+            serde_json::json!({ "type": "hitTest", "x": 1.0, "y": 2.0 }),
+            serde_json::json!({ "type": "setWindowMinSize", "width": 300.0, "height": 200.0 }),
+            serde_json::json!({ "type": "setCursor", "cursor": "grabbing" }),
+        ];
+        let mut rng = crate::ipc::msgpack::TestRng::new(42);
+        let mut buffers = SharedBuffers::default();
+        for _ in 0..5_000 {
+            let mut payload = rmp_serde::to_vec_named(&corpus[rng.below(corpus.len())]).unwrap();
+            rng.mutate(&mut payload);
+            let Ok(frame) = decode_msgpack::<ClientFrame>(&payload) else {
+                let _ = decode_msgpack::<FrameHeader>(&payload).map(|h| h.command_name());
+                continue;
+            };
+            // Never let a mutation touch files
+            if matches!(frame.message, ClientMessage::RegisterBuffer { .. }) {
+                continue;
+            }
+            let _ = handle_client_message(frame.message, &mut buffers, &mut Vec::new());
+        }
+    }
+
+    /// Decode and map `CreateWidget` frames in both encodings. Run with
+    /// `cargo test --release bench_create_widget -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_create_widget_decoding() {
+        const WIDGETS: usize = 20_000;
+        let style = serde_json::json!({
+            "padding": "8, 16",
+            "background": "#2d2d2d",
+            "color": "#ffffff",
+            "fontSize": 14,
+            "cornerRadius": 6,
+            "borderWidth": 1,
+            "borderColor": "#444444",
+            "gap": 8,
+            "direction": "row",
+        });
+        let params = serde_json::json!({ "minValue": 0, "maxValue": 100, "value": 40 });
+        let frame = |i: usize, native: bool| {
+            let mut message = serde_json::json!({
+                "type": "createWidget",
+                "id": format!("w{i}"),
+                "kind": "slider",
+                "parent_id": null,
+                "text": null,
+            });
+            if native {
+                message["style"] = style.clone();
+                message["params"] = params.clone();
+            } else {
+                message["style_json"] = style.to_string().into();
+                message["widget_params_json"] = params.to_string().into();
+            }
+            rmp_serde::to_vec_named(&message).unwrap()
+        };
+
+        for (label, native) in [("json (v1)", false), ("native (v2)", true)] {
+            let frames: Vec<Vec<u8>> = (0..WIDGETS).map(|i| frame(i, native)).collect();
+            let mut buffers = SharedBuffers::default();
+            let start = std::time::Instant::now();
+            for payload in &frames {
+                let frame: ClientFrame = decode_msgpack(payload).unwrap();
+                let mut warnings = Vec::new();
+                let cmd =
+                    handle_client_message(frame.message, &mut buffers, &mut warnings).unwrap();
+                assert!(warnings.is_empty());
+                std::hint::black_box(cmd);
+            }
+            let elapsed = start.elapsed();
+            eprintln!(
+                "{label}: {WIDGETS} widgets in {elapsed:?} ({:.2} µs/widget)",
+                elapsed.as_secs_f64() * 1e6 / WIDGETS as f64
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_set_image_data_from_shared_buffer() {
+        let path = format!("/dev/shm/vellum-server-test-{}", std::process::id());
+        std::fs::write(&path, b"not really a png").unwrap();
+        let mut buffers = SharedBuffers::default();
+        let mut warnings = Vec::new();
+
+        let registered = handle_client_message(
+            ClientMessage::RegisterBuffer {
+                buffer_id: "photo".to_string(),
+                path,
+                len: 16,
+            },
+            &mut buffers,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(registered.is_none());
+
+        let cmd = handle_client_message(
+            ClientMessage::SetImageData {
+                id: "img".to_string(),
+                data: None,
+                buffer_id: Some("photo".to_string()),
+            },
+            &mut buffers,
+            &mut warnings,
+        )
+        .unwrap();
+        let Some(ClientCommand::SetImageData { data, .. }) = cmd else {
+            panic!("expected SetImageData");
+        };
+        assert_eq!(&data[..], b"not really a png");
+        assert!(matches!(data, PayloadBytes::Shared(_)));
+
+        let released = handle_client_message(
+            ClientMessage::ReleaseBuffer {
+                buffer_id: "photo".to_string(),
+            },
+            &mut buffers,
+            &mut warnings,
+        );
+        assert!(matches!(released, Ok(None)));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_shared_buffers_need_the_negotiated_feature() {
+        let session = |features: &[&str]| Session {
+            protocol_version: handshake::PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+        };
+        let register = ClientMessage::RegisterBuffer {
+            buffer_id: "b".to_string(),
+            path: "/dev/shm/vellum-b".to_string(),
+            len: 4,
+        };
+        let image = ClientMessage::SetImageData {
+            id: "img".to_string(),
+            data: None,
+            buffer_id: Some("b".to_string()),
+        };
+        let inline_image = ClientMessage::SetImageData {
+            id: "img".to_string(),
+            data: Some(vec![1, 2, 3]),
+            buffer_id: None,
+        };
+
+        let remote = session(&["batch"]);
+        let error = check_negotiated(&register, &remote).unwrap_err();
+        assert_eq!(error.error.code(), "invalid_argument");
+        assert_eq!(error.command.as_deref(), Some("RegisterBuffer"));
+        assert!(check_negotiated(&image, &remote).is_err());
+        assert!(check_negotiated(&inline_image, &remote).is_ok());
+
+        let local = session(&["batch", "shared_buffers"]);
+        assert!(check_negotiated(&register, &local).is_ok());
+        assert!(check_negotiated(&image, &local).is_ok());
+    }
+
+    #[test]
+    fn test_commands_need_their_negotiated_feature() {
+        let session = Session {
+            protocol_version: handshake::PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+            features: vec!["requests".to_string()],
+        };
+        let gated = [
+            (ClientMessage::SetCursor { cursor: None }, "cursor"),
+            (ClientMessage::GetWindowState, "window_control"),
+            (
+                ClientMessage::HitTest { x: 1.0, y: 2.0 },
+                "geometry_queries",
+            ),
+            (
+                ClientMessage::ObserveResize {
+                    id: "a".to_string(),
+                },
+                "observers",
+            ),
+            (
+                ClientMessage::SetWidgetStyle {
+                    id: "a".to_string(),
+                    style_json: None,
+                    style: Some(NativeStyle::default()),
+                },
+                "native_styles",
+            ),
+        ];
+        for (message, feature) in gated {
+            let error = check_negotiated(&message, &session).unwrap_err();
+            assert!(error.message.contains(feature), "{}", error.message);
+        }
+        let json_style = ClientMessage::SetWidgetStyle {
+            id: "a".to_string(),
+            style_json: Some("{}".to_string()),
+            style: None,
+        };
+        assert!(check_negotiated(&json_style, &session).is_ok());
+    }
 }
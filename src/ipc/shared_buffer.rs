@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(target_os = "linux")]
+use std::io;
+use std::ops::Deref;
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::MetadataExt;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::VellumError;
+
+/// Directory shared buffers must live in: tmpfs, so the client's file never
+/// touches the disk.
+#[cfg(target_os = "linux")]
+const SHARED_BUFFER_DIR: &str = "/dev/shm";
+/// File name prefix of shared buffers, so other programs' files in
+/// `/dev/shm` can't be registered (and unlinked).
+#[cfg(target_os = "linux")]
+const SHARED_BUFFER_PREFIX: &str = "vellum-";
+
+/// Bytes of a payload (e.g. an image file), either received inline in a frame
+/// or read from a shared buffer the client registered.
+#[derive(Clone)]
+pub enum PayloadBytes {
+    Inline(Vec<u8>),
+    /// Shared by every command that references the buffer.
+    #[cfg(target_os = "linux")]
+    Shared(Arc<[u8]>),
+}
+
+impl PayloadBytes {
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            PayloadBytes::Inline(bytes) => bytes,
+            #[cfg(target_os = "linux")]
+            PayloadBytes::Shared(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for PayloadBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl From<Vec<u8>> for PayloadBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        PayloadBytes::Inline(bytes)
+    }
+}
+
+impl fmt::Debug for PayloadBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            PayloadBytes::Inline(_) => "inline",
+            #[cfg(target_os = "linux")]
+            PayloadBytes::Shared(_) => "shared",
+        };
+        write!(f, "PayloadBytes({} bytes, {source})", self.len())
+    }
+}
+
+impl Serialize for PayloadBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PayloadBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_bytes::ByteBuf::deserialize(deserializer).map(|buf| buf.into_vec().into())
+    }
+}
+
+/// Shared buffers registered by the client, by id. Owned by the IPC read
+/// thread, which swaps buffer references in commands for the buffer's bytes.
+#[derive(Default)]
+pub struct SharedBuffers {
+    buffers: HashMap<String, PayloadBytes>,
+}
+
+impl SharedBuffers {
+    /// Copy the client's `vellum-*` file at `path` (in `/dev/shm`) and unlink
+    /// it. A file that isn't a usable buffer is left alone.
+    ///
+    /// The bytes are read rather than mapped: the client keeps its own handle
+    /// to the file and could truncate it under a mapping, which would crash
+    /// this process with SIGBUS. Read into memory, a file changed meanwhile
+    /// can at most produce a wrong image or a length mismatch. (Other processes
+    /// of the same user can write the file until it is unlinked, but they
+    /// could as well connect and send commands themselves.)
+    #[cfg(target_os = "linux")]
+    pub fn register(&mut self, buffer_id: String, path: &str, len: u64) -> Result<(), VellumError> {
+        let invalid = |reason: String| VellumError::InvalidArgument {
+            widget_id: None,
+            reason,
+        };
+        let path = Path::new(path);
+        let named_buffer = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(SHARED_BUFFER_PREFIX));
+        if path.parent() != Some(Path::new(SHARED_BUFFER_DIR)) || !named_buffer {
+            return Err(invalid(format!(
+                "shared buffer '{buffer_id}' must be a {SHARED_BUFFER_PREFIX}* file in {SHARED_BUFFER_DIR}"
+            )));
+        }
+        // Don't follow a link out of /dev/shm
+        let linked = std::fs::symlink_metadata(path)
+            .ok()
+            .filter(|metadata| metadata.is_file());
+        let Some(linked) = linked else {
+            return Err(invalid(format!(
+                "shared buffer '{buffer_id}' is not a regular file"
+            )));
+        };
+        let file = std::fs::File::open(path)
+            .map_err(|e| invalid(format!("cannot open shared buffer '{buffer_id}': {e}")))?;
+        let opened = file.metadata().map_err(|e| {
+            VellumError::internal(format!("cannot read shared buffer '{buffer_id}': {e}"))
+        })?;
+        // The path may have been swapped for a link since it was checked
+        if (opened.dev(), opened.ino()) != (linked.dev(), linked.ino()) {
+            return Err(invalid(format!(
+                "shared buffer '{buffer_id}' changed while it was opened"
+            )));
+        }
+        if opened.len() != len {
+            return Err(invalid(format!(
+                "shared buffer '{buffer_id}' holds {} bytes, expected {len}",
+                opened.len()
+            )));
+        }
+        // Read at most one byte more than announced, so a file that grew
+        // since is caught without reading all of it
+        let mut bytes = Vec::with_capacity(opened.len() as usize);
+        io::Read::read_to_end(
+            &mut io::Read::take(&file, len.saturating_add(1)),
+            &mut bytes,
+        )
+        .map_err(|e| {
+            VellumError::internal(format!("cannot read shared buffer '{buffer_id}': {e}"))
+        })?;
+        if bytes.len() as u64 != len {
+            return Err(invalid(format!(
+                "shared buffer '{buffer_id}' holds {} bytes, expected {len}",
+                bytes.len()
+            )));
+        }
+        let _ = std::fs::remove_file(path);
+        self.buffers
+            .insert(buffer_id, PayloadBytes::Shared(bytes.into()));
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn register(
+        &mut self,
+        buffer_id: String,
+        _path: &str,
+        _len: u64,
+    ) -> Result<(), VellumError> {
+        Err(VellumError::InvalidArgument {
+            widget_id: None,
+            reason: format!(
+                "cannot register '{buffer_id}': shared buffers are only supported on Linux"
+            ),
+        })
+    }
+
+    /// Forget the buffer. Commands already holding its bytes keep them alive.
+    pub fn release(&mut self, buffer_id: &str) -> bool {
+        self.buffers.remove(buffer_id).is_some()
+    }
+
+    pub fn get(&self, buffer_id: &str) -> Result<PayloadBytes, VellumError> {
+        self.buffers
+            .get(buffer_id)
+            .cloned()
+            .ok_or_else(|| VellumError::InvalidArgument {
+                widget_id: None,
+                reason: format!("unknown shared buffer '{buffer_id}'"),
+            })
+    }
+
+    /// Bytes of a command: the referenced shared buffer, else the inline data.
+    pub fn resolve(
+        &self,
+        buffer_id: Option<&str>,
+        inline: Option<Vec<u8>>,
+    ) -> Result<Option<PayloadBytes>, VellumError> {
+        match buffer_id {
+            Some(buffer_id) => self.get(buffer_id).map(Some),
+            None => Ok(inline.map(PayloadBytes::from)),
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_copies_and_unlinks_file() {
+        let path = format!("{SHARED_BUFFER_DIR}/vellum-test-{}", std::process::id());
+        std::fs::write(&path, [1_u8, 2, 3, 4]).unwrap();
+
+        let mut buffers = SharedBuffers::default();
+        buffers.register("img".to_string(), &path, 4).unwrap();
+        assert!(!Path::new(&path).exists());
+
+        let bytes = buffers.get("img").unwrap();
+        assert!(buffers.release("img"));
+        // The command's reference outlives the registration
+        assert_eq!(&bytes[..], &[1, 2, 3, 4]);
+        assert!(buffers.get("img").is_err());
+    }
+
+    #[test]
+    fn test_registered_bytes_survive_client_truncating_the_file() {
+        let path = format!("{SHARED_BUFFER_DIR}/vellum-truncate-{}", std::process::id());
+        std::fs::write(&path, [5_u8; 64]).unwrap();
+        // The client's own handle outlives the unlink
+        let client = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+
+        let mut buffers = SharedBuffers::default();
+        buffers.register("img".to_string(), &path, 64).unwrap();
+        client.set_len(0).unwrap();
+        assert_eq!(&buffers.get("img").unwrap()[..], &[5_u8; 64]);
+    }
+
+    #[test]
+    fn test_register_rejects_paths_outside_shm() {
+        let mut buffers = SharedBuffers::default();
+        let error = buffers
+            .register("etc".to_string(), "/etc/hostname", 0)
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_argument");
+    }
+
+    #[test]
+    fn test_rejected_buffers_are_not_unlinked() {
+        let mut buffers = SharedBuffers::default();
+        // Not a Vellum buffer
+        let foreign = format!("{SHARED_BUFFER_DIR}/other-app-{}", std::process::id());
+        std::fs::write(&foreign, [1_u8]).unwrap();
+        assert!(buffers.register("a".to_string(), &foreign, 1).is_err());
+        assert!(Path::new(&foreign).exists());
+        let _ = std::fs::remove_file(&foreign);
+
+        // Wrong length
+        let short = format!("{SHARED_BUFFER_DIR}/vellum-short-{}", std::process::id());
+        std::fs::write(&short, [1_u8, 2]).unwrap();
+        assert!(buffers.register("b".to_string(), &short, 8).is_err());
+        assert!(Path::new(&short).exists());
+        let _ = std::fs::remove_file(&short);
+    }
+}
@@ -0,0 +1,87 @@
+/// A `var(--name, fallback)` reference used as a style value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarReference<'a> {
+    /// Variable name without the leading `--`
+    pub name: &'a str,
+    /// Raw fallback expression (may itself be a `var(...)` reference)
+    pub fallback: Option<&'a str>,
+}
+
+/// Returns true if the whole value is a `var(...)` reference.
+pub fn is_var_reference(value: &str) -> bool {
+    parse_var_reference(value).is_some()
+}
+
+/// Parse a style value of the form `var(--name)` or `var(--name, fallback)`.
+/// References embedded in a larger value (e.g. `"var(--x), 8"`) are not supported.
+pub fn parse_var_reference(value: &str) -> Option<VarReference<'_>> {
+    let inner = value.trim().strip_prefix("var(")?.strip_suffix(')')?.trim();
+
+    let (name, fallback) = match inner.split_once(',') {
+        Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+        None => (inner, None),
+    };
+
+    let name = name.strip_prefix("--")?;
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(VarReference { name, fallback })
+}
+
+/// Normalize a variable name as sent by clients (`--accent` or `accent`).
+pub fn normalize_var_name(name: &str) -> &str {
+    name.trim().strip_prefix("--").unwrap_or(name.trim())
+}
+
+/// Names of every variable an expression depends on, including those in fallbacks.
+pub fn referenced_var_names(value: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut current = Some(value);
+    while let Some(expr) = current {
+        match parse_var_reference(expr) {
+            Some(reference) => {
+                names.push(reference.name);
+                current = reference.fallback;
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_var_reference() {
+        assert_eq!(
+            parse_var_reference("var(--accent)"),
+            Some(VarReference {
+                name: "accent",
+                fallback: None
+            })
+        );
+        assert_eq!(
+            parse_var_reference(" var(--space-md, 12) "),
+            Some(VarReference {
+                name: "space-md",
+                fallback: Some("12")
+            })
+        );
+        assert_eq!(parse_var_reference("#ff0000"), None);
+        assert_eq!(parse_var_reference("var(accent)"), None);
+        assert_eq!(parse_var_reference("var(--)"), None);
+    }
+
+    #[test]
+    fn test_referenced_var_names_follows_fallbacks() {
+        assert_eq!(
+            referenced_var_names("var(--primary, var(--accent, #fff))"),
+            vec!["primary", "accent"]
+        );
+        assert!(referenced_var_names("12").is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::color::ColorValue;
+use super::commands::{BoxStyle, WidgetKind};
+
+/// Design tokens applied as the default styling of every widget.
+///
+/// Colors inside `widgets` (and inside variant overrides) may name a palette
+/// entry instead of a literal color, e.g. `"color": "text"`; these are
+/// resolved against the active palette when a widget is styled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThemeTokens {
+    /// Named colors (`background`, `surface`, `text`, `accent`, ...)
+    pub palette: HashMap<String, ColorValue>,
+    /// Default text styling for text-bearing widgets
+    pub typography: TypographyScale,
+    /// Named spacing steps (`sm`, `md`, `lg`, ...)
+    pub spacing: HashMap<String, f64>,
+    /// Named corner radii (`sm`, `md`, `full`, ...)
+    pub radii: HashMap<String, f64>,
+    /// Default style per widget kind, keyed by `WidgetKind::style_key`
+    pub widgets: HashMap<String, BoxStyle>,
+    /// Named variants (e.g. `light` / `dark`) layered over the base tokens
+    pub variants: HashMap<String, ThemeVariant>,
+    /// Currently active variant, if any
+    pub variant: Option<String>,
+}
+
+/// Typography tokens shared by all text widgets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TypographyScale {
+    pub font_family: Option<String>,
+    pub base_size: Option<f32>,
+    pub line_height: Option<f32>,
+    /// Named font sizes (`caption`, `body`, `title`, ...)
+    pub sizes: HashMap<String, f32>,
+}
+
+/// Palette and per-widget overrides for a theme variant.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThemeVariant {
+    pub palette: HashMap<String, ColorValue>,
+    pub widgets: HashMap<String, BoxStyle>,
+}
+
+impl Default for ThemeTokens {
+    /// Built-in dark theme matching Vellum's historical defaults: white text,
+    /// 30px labels and 16px custom widgets. Text without a font family is
+    /// set in the generic sans-serif family (see `build_text_styles`).
+    fn default() -> Self {
+        let mut palette = HashMap::new();
+        palette.insert("text".to_string(), rgb(255, 255, 255));
+
+        let mut widgets = HashMap::new();
+        widgets.insert(
+            "Label".to_string(),
+            BoxStyle {
+                color: Some(ColorValue::Named("text".to_string())),
+                font_size: Some(30.0),
+                ..Default::default()
+            },
+        );
+        widgets.insert(
+            "Custom".to_string(),
+            BoxStyle {
+                color: Some(ColorValue::Named("text".to_string())),
+                font_size: Some(16.0),
+                ..Default::default()
+            },
+        );
+
+        Self {
+            palette,
+            typography: TypographyScale::default(),
+            spacing: HashMap::new(),
+            radii: HashMap::new(),
+            widgets,
+            variants: HashMap::new(),
+            variant: None,
+        }
+    }
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> ColorValue {
+    ColorValue::Rgba { r, g, b, a: 255 }
+}
+
+fn lookup_palette<'a>(
+    palette: &'a HashMap<String, ColorValue>,
+    name: &str,
+) -> Option<&'a ColorValue> {
+    palette.get(name).or_else(|| {
+        palette
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, color)| color)
+    })
+}
+
+impl ThemeTokens {
+    fn active_variant(&self) -> Option<&ThemeVariant> {
+        self.variant
+            .as_deref()
+            .and_then(|name| self.variants.get(name))
+    }
+
+    /// Look up a palette color, preferring the active variant's palette.
+    /// Names are matched case-insensitively since color strings are lowercased when parsed.
+    pub fn palette_color(&self, name: &str) -> Option<&ColorValue> {
+        self.active_variant()
+            .and_then(|variant| lookup_palette(&variant.palette, name))
+            .or_else(|| lookup_palette(&self.palette, name))
+    }
+
+    /// Replace palette references (named colors) with the palette value.
+    /// Unknown names are left untouched.
+    pub fn resolve_palette(&self, style: &mut BoxStyle) {
+        for slot in [
+            &mut style.color,
+            &mut style.background,
+            &mut style.border_color,
+            &mut style.hover_border_color,
+        ] {
+            let resolved = match slot {
+                Some(ColorValue::Named(name)) => self.palette_color(name).cloned(),
+                _ => None,
+            };
+            if let Some(color) = resolved {
+                *slot = Some(color);
+            }
+        }
+    }
+
+    /// Default style for a widget kind: typography, then the base widget
+    /// entry, then the active variant's widget entry, with palette colors resolved.
+    pub fn widget_style(&self, kind: &WidgetKind) -> BoxStyle {
+        let mut style = BoxStyle::default();
+
+        if kind.is_text() {
+            style.font_family = self.typography.font_family.clone();
+            style.font_size = self.typography.base_size;
+            style.line_height = self.typography.line_height;
+        }
+
+        let key = kind.style_key();
+        if let Some(base) = self.widgets.get(key) {
+            style.merge_from(base);
+        }
+        if let Some(variant_style) = self
+            .active_variant()
+            .and_then(|variant| variant.widgets.get(key))
+        {
+            style.merge_from(variant_style);
+        }
+
+        self.resolve_palette(&mut style);
+        style
+    }
+
+    /// Value of a built-in token variable: `--color-<palette>`, `--spacing-<step>`,
+    /// `--radius-<step>` or `--font-size-<step>`.
+    pub fn token_variable(&self, name: &str) -> Option<String> {
+        if let Some(color) = name.strip_prefix("color-") {
+            return self.palette_color(color).map(ColorValue::to_css_string);
+        }
+        if let Some(step) = name.strip_prefix("spacing-") {
+            return self.spacing.get(step).map(f64::to_string);
+        }
+        if let Some(step) = name.strip_prefix("radius-") {
+            return self.radii.get(step).map(f64::to_string);
+        }
+        if let Some(step) = name.strip_prefix("font-size-") {
+            return self.typography.sizes.get(step).map(f32::to_string);
+        }
+        None
+    }
+
+    /// Style applied to the root container (the window "body").
+    pub fn root_style(&self) -> BoxStyle {
+        BoxStyle {
+            background: self.palette_color("background").cloned(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widget_style_resolves_palette() {
+        let theme = ThemeTokens::default();
+        let style = theme.widget_style(&WidgetKind::Label);
+        assert!(matches!(
+            style.color,
+            Some(ColorValue::Rgba {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 255
+            })
+        ));
+        assert_eq!(style.font_size, Some(30.0));
+    }
+
+    #[test]
+    fn test_variant_overrides_palette_and_widgets() {
+        let json = r##"{
+            "palette": { "text": "#ffffff", "background": "#000000" },
+            "typography": { "fontFamily": "Inter", "baseSize": 14 },
+            "widgets": { "Label": { "color": "text" } },
+            "variants": {
+                "light": {
+                    "palette": { "text": "#000000" },
+                    "widgets": { "Label": { "fontWeight": 600 } }
+                }
+            },
+            "variant": "light"
+        }"##;
+        let theme: ThemeTokens = serde_json::from_str(json).unwrap();
+        let style = theme.widget_style(&WidgetKind::Label);
+
+        assert!(matches!(
+            style.color,
+            Some(ColorValue::Rgba {
+                r: 0,
+                g: 0,
+                b: 0,
+                ..
+            })
+        ));
+        assert_eq!(style.font_weight, Some(600.0));
+        assert_eq!(style.font_family.as_deref(), Some("Inter"));
+        assert_eq!(style.font_size, Some(14.0));
+
+        // Typography only applies to text-bearing widgets
+        let flex_style = theme.widget_style(&WidgetKind::Flex);
+        assert!(flex_style.font_family.is_none());
+    }
+}
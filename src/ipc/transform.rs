@@ -0,0 +1,351 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A 2D transform, composed as translate → rotate → skew → scale around the
+/// transform origin. CSS strings such as `"translate(10px, 0) rotate(45deg)"`
+/// are folded into these components, so the order of functions in the string
+/// does not matter. Keeping a fixed shape makes transforms interpolable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformValue {
+    pub translate_x: f64,
+    pub translate_y: f64,
+    /// Rotation in degrees, clockwise
+    pub rotate: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    /// Skew angles in degrees
+    pub skew_x: f64,
+    pub skew_y: f64,
+}
+
+impl Default for TransformValue {
+    fn default() -> Self {
+        Self {
+            translate_x: 0.0,
+            translate_y: 0.0,
+            rotate: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            skew_x: 0.0,
+            skew_y: 0.0,
+        }
+    }
+}
+
+/// Object form accepted on the wire, with optional components.
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TransformParts {
+    translate_x: f64,
+    translate_y: f64,
+    rotate: f64,
+    scale: Option<f64>,
+    scale_x: Option<f64>,
+    scale_y: Option<f64>,
+    skew_x: f64,
+    skew_y: f64,
+}
+
+impl Default for TransformParts {
+    fn default() -> Self {
+        Self {
+            translate_x: 0.0,
+            translate_y: 0.0,
+            rotate: 0.0,
+            scale: None,
+            scale_x: None,
+            scale_y: None,
+            skew_x: 0.0,
+            skew_y: 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTransform {
+    Css(String),
+    Parts(TransformParts),
+}
+
+impl<'de> Deserialize<'de> for TransformValue {
+    fn deserialize<D>(deserializer: D) -> Result<TransformValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match RawTransform::deserialize(deserializer)? {
+            RawTransform::Css(css) => TransformValue::parse(&css)
+                .ok_or_else(|| serde::de::Error::custom(format!("Invalid transform: {css}"))),
+            RawTransform::Parts(parts) => Ok(TransformValue {
+                translate_x: parts.translate_x,
+                translate_y: parts.translate_y,
+                rotate: parts.rotate,
+                scale_x: parts.scale_x.or(parts.scale).unwrap_or(1.0),
+                scale_y: parts.scale_y.or(parts.scale).unwrap_or(1.0),
+                skew_x: parts.skew_x,
+                skew_y: parts.skew_y,
+            }),
+        }
+    }
+}
+
+/// Parse a length in px (unit optional).
+fn parse_px(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value
+        .strip_suffix("px")
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Parse an angle; degrees by default, `rad` and `turn` units are converted.
+fn parse_angle(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Some(rad) = value.strip_suffix("rad") {
+        return rad.trim().parse::<f64>().ok().map(f64::to_degrees);
+    }
+    if let Some(turns) = value.strip_suffix("turn") {
+        return turns.trim().parse::<f64>().ok().map(|t| t * 360.0);
+    }
+    value
+        .strip_suffix("deg")
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .ok()
+}
+
+impl TransformValue {
+    /// Whether the transform leaves the widget unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == TransformValue::default()
+    }
+
+    /// Parse CSS transform functions: `translate`, `translateX/Y`, `scale`,
+    /// `scaleX/Y`, `rotate`, `skew`, `skewX/Y`, or `none`.
+    pub fn parse(css: &str) -> Option<Self> {
+        let mut transform = TransformValue::default();
+        let mut rest = css.trim();
+        if rest.eq_ignore_ascii_case("none") {
+            return Some(transform);
+        }
+
+        while !rest.is_empty() {
+            let open = rest.find('(')?;
+            let close = rest.find(')')?;
+            if close < open {
+                return None;
+            }
+            let name = rest[..open].trim().to_ascii_lowercase();
+            let args: Vec<&str> = rest[open + 1..close].split(',').map(str::trim).collect();
+            rest = rest[close + 1..].trim_start();
+
+            match (name.as_str(), args.as_slice()) {
+                ("translate", [x]) => transform.translate_x += parse_px(x)?,
+                ("translate", [x, y]) => {
+                    transform.translate_x += parse_px(x)?;
+                    transform.translate_y += parse_px(y)?;
+                }
+                ("translatex", [x]) => transform.translate_x += parse_px(x)?,
+                ("translatey", [y]) => transform.translate_y += parse_px(y)?,
+                ("scale", [s]) => {
+                    let s: f64 = s.parse().ok()?;
+                    transform.scale_x *= s;
+                    transform.scale_y *= s;
+                }
+                ("scale", [x, y]) => {
+                    transform.scale_x *= x.parse::<f64>().ok()?;
+                    transform.scale_y *= y.parse::<f64>().ok()?;
+                }
+                ("scalex", [x]) => transform.scale_x *= x.parse::<f64>().ok()?,
+                ("scaley", [y]) => transform.scale_y *= y.parse::<f64>().ok()?,
+                ("rotate", [angle]) => transform.rotate += parse_angle(angle)?,
+                ("skew", [x]) => transform.skew_x += parse_angle(x)?,
+                ("skew", [x, y]) => {
+                    transform.skew_x += parse_angle(x)?;
+                    transform.skew_y += parse_angle(y)?;
+                }
+                ("skewx", [x]) => transform.skew_x += parse_angle(x)?,
+                ("skewy", [y]) => transform.skew_y += parse_angle(y)?,
+                _ => return None,
+            }
+        }
+        Some(transform)
+    }
+}
+
+/// One coordinate of a transform origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OriginLength {
+    /// Fraction of the widget size (`50%` is 0.5)
+    Fraction(f64),
+    /// Absolute offset from the top-left corner
+    Px(f64),
+}
+
+impl OriginLength {
+    /// Resolve against the widget's extent along this axis.
+    pub fn resolve(&self, extent: f64) -> f64 {
+        match *self {
+            OriginLength::Fraction(f) => f * extent,
+            OriginLength::Px(px) => px,
+        }
+    }
+
+    fn to_css_string(self) -> String {
+        match self {
+            OriginLength::Fraction(f) => format!("{}%", f * 100.0),
+            OriginLength::Px(px) => format!("{px}px"),
+        }
+    }
+}
+
+/// Point the transform is applied around; defaults to the center, as in CSS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformOrigin {
+    pub x: OriginLength,
+    pub y: OriginLength,
+}
+
+impl Default for TransformOrigin {
+    fn default() -> Self {
+        Self {
+            x: OriginLength::Fraction(0.5),
+            y: OriginLength::Fraction(0.5),
+        }
+    }
+}
+
+impl TransformOrigin {
+    /// Parse `"center"`, `"top left"`, `"50% 100%"`, `"10px 20px"` and mixes thereof.
+    pub fn parse(css: &str) -> Option<Self> {
+        let parts: Vec<String> = css
+            .split_whitespace()
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let keyword = |part: &str| match part {
+            "left" | "top" => Some(OriginLength::Fraction(0.0)),
+            "center" => Some(OriginLength::Fraction(0.5)),
+            "right" | "bottom" => Some(OriginLength::Fraction(1.0)),
+            _ => None,
+        };
+        let length = |part: &str| {
+            keyword(part).or_else(|| match part.strip_suffix('%') {
+                Some(percent) => percent
+                    .parse::<f64>()
+                    .ok()
+                    .map(|p| OriginLength::Fraction(p / 100.0)),
+                None => parse_px(part).map(OriginLength::Px),
+            })
+        };
+
+        match parts.as_slice() {
+            [single] => {
+                let value = length(single)?;
+                // A lone vertical keyword positions y and centers x
+                Some(if matches!(single.as_str(), "top" | "bottom") {
+                    TransformOrigin {
+                        x: OriginLength::Fraction(0.5),
+                        y: value,
+                    }
+                } else {
+                    TransformOrigin {
+                        x: value,
+                        y: OriginLength::Fraction(0.5),
+                    }
+                })
+            }
+            [first, second] => {
+                // Keyword pairs may be written vertical-first ("top left")
+                let (x, y) = if matches!(first.as_str(), "top" | "bottom")
+                    || matches!(second.as_str(), "left" | "right")
+                {
+                    (second, first)
+                } else {
+                    (first, second)
+                };
+                Some(TransformOrigin {
+                    x: length(x)?,
+                    y: length(y)?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for TransformOrigin {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let css = format!("{} {}", self.x.to_css_string(), self.y.to_css_string());
+        serializer.serialize_str(&css)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransformOrigin {
+    fn deserialize<D>(deserializer: D) -> Result<TransformOrigin, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        TransformOrigin::parse(&s)
+            .ok_or_else(|| serde::de::Error::custom(format!("Invalid transform origin: {s}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_css_transform() {
+        let t = TransformValue::parse("translate(10px, -4) rotate(0.25turn) scale(2)").unwrap();
+        assert_eq!(t.translate_x, 10.0);
+        assert_eq!(t.translate_y, -4.0);
+        assert_eq!(t.rotate, 90.0);
+        assert_eq!((t.scale_x, t.scale_y), (2.0, 2.0));
+        assert!(TransformValue::parse("none").unwrap().is_identity());
+        assert!(TransformValue::parse("perspective(10px)").is_none());
+    }
+
+    #[test]
+    fn test_deserialize_object_transform() {
+        let t: TransformValue = serde_json::from_str(r#"{ "scale": 1.5, "skewX": 10 }"#).unwrap();
+        assert_eq!((t.scale_x, t.scale_y), (1.5, 1.5));
+        assert_eq!(t.skew_x, 10.0);
+    }
+
+    #[test]
+    fn test_parse_transform_origin() {
+        assert_eq!(
+            TransformOrigin::parse("top left"),
+            Some(TransformOrigin {
+                x: OriginLength::Fraction(0.0),
+                y: OriginLength::Fraction(0.0)
+            })
+        );
+        assert_eq!(
+            TransformOrigin::parse("25% 8px"),
+            Some(TransformOrigin {
+                x: OriginLength::Fraction(0.25),
+                y: OriginLength::Px(8.0)
+            })
+        );
+        assert_eq!(
+            TransformOrigin::parse("bottom").unwrap().x,
+            OriginLength::Fraction(0.5)
+        );
+        assert!(TransformOrigin::parse("sideways").is_none());
+
+        let origin = TransformOrigin::parse("right 4px").unwrap();
+        let json = serde_json::to_string(&origin).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TransformOrigin>(&json).unwrap(),
+            origin
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::handshake::SessionToken;
+use super::websocket;
+use crate::socket::{UnixListener, bind_socket, get_socket_path, runtime_dir};
+
+/// Address the `tcp` and `websocket` transports listen on unless
+/// `VELLUM_LISTEN` says otherwise; port 0 picks a free port.
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:0";
+/// How long a WebSocket client may take to send its upgrade request.
+const UPGRADE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connected client, as independent read and write halves of the same
+/// byte stream. Both carry length-prefixed MsgPack frames.
+pub struct Connection {
+    pub reader: Box<dyn Read + Send>,
+    pub writer: Box<dyn Write + Send>,
+    /// Whether the client shares our filesystem, which `shared_buffers`
+    /// relies on. Network clients may live in a container or on another host.
+    pub same_host: bool,
+    close_read: Option<Box<dyn FnOnce() + Send>>,
+    read_timeout: Option<Box<dyn Fn(Option<Duration>) -> io::Result<()> + Send>>,
+}
+
+impl Connection {
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) -> Self {
+        Self {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            same_host: true,
+            close_read: None,
+            read_timeout: None,
+        }
+    }
+
+    /// Mark the client as possibly living elsewhere.
+    pub fn remote(mut self) -> Self {
+        self.same_host = false;
+        self
+    }
+
+    /// Let [`Connection::close_read`] end a blocked read through `close`.
+    pub fn with_close_read(mut self, close: impl FnOnce() + Send + 'static) -> Self {
+        self.close_read = Some(Box::new(close));
+        self
+    }
+
+    /// Let [`Connection::set_read_timeout`] bound blocking reads through `set`.
+    pub fn with_read_timeout(
+        mut self,
+        set: impl Fn(Option<Duration>) -> io::Result<()> + Send + 'static,
+    ) -> Self {
+        self.read_timeout = Some(Box::new(set));
+        self
+    }
+
+    /// Make reads fail after `timeout` without data (`None`: block forever).
+    /// A no-op for transports that can't time out, like stdio.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match &self.read_timeout {
+            Some(set) => set(timeout),
+            None => Ok(()),
+        }
+    }
+
+    /// Move the read half out, e.g. to a reader thread.
+    pub fn take_reader(&mut self) -> Box<dyn Read + Send> {
+        std::mem::replace(&mut self.reader, Box::new(io::empty()))
+    }
+
+    /// Stop reading, so a thread blocked on `reader` sees end-of-stream.
+    /// Returns `false` when the transport can't interrupt reads, in which
+    /// case the reader only ends once the client closes its side.
+    pub fn close_read(&mut self) -> bool {
+        match self.close_read.take() {
+            Some(close) => {
+                close();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Where clients come from. Implementations only move bytes; framing,
+/// the handshake and dispatch are the same for all of them.
+pub trait Transport: Send {
+    /// The endpoint, for logs.
+    fn describe(&self) -> String;
+
+    /// Block until the next client connects.
+    fn accept(&mut self) -> io::Result<Connection>;
+
+    /// Token a client must present in `Hello`, or `None` when only the
+    /// process that started us can reach the transport.
+    fn session_token(&self) -> Option<&SessionToken>;
+}
+
+/// The transport named by `VELLUM_TRANSPORT`: `socket` (the default),
+/// `stdio`, `tcp` or `websocket`.
+pub fn transport_from_env() -> io::Result<Box<dyn Transport>> {
+    let name = std::env::var("VELLUM_TRANSPORT").unwrap_or_default();
+    match name.trim() {
+        "" | "socket" => Ok(Box::new(SocketTransport::bind()?)),
+        "stdio" => Ok(Box::new(StdioTransport::default())),
+        "tcp" => Ok(Box::new(TcpTransport::bind(false)?)),
+        "websocket" => Ok(Box::new(TcpTransport::bind(true)?)),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown VELLUM_TRANSPORT '{other}' (expected socket, stdio, tcp or websocket)"
+            ),
+        )),
+    }
+}
+
+/// The session token for a listening transport: handed down by whoever
+/// spawned us in `VELLUM_SESSION_TOKEN`, or generated and written to a file
+/// only the current user can read, for clients started by hand. The file is
+/// removed on drop.
+struct IssuedToken {
+    token: SessionToken,
+    path: Option<PathBuf>,
+}
+
+impl IssuedToken {
+    fn issue(path: PathBuf) -> io::Result<Self> {
+        if let Some(token) = SessionToken::from_env() {
+            return Ok(Self { token, path: None });
+        }
+        let token = SessionToken::generate()?;
+        token.write_to(&path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Failed to write {}: {e}", path.display()))
+        })?;
+        eprintln!("[IPC] Session token written to {}", path.display());
+        Ok(Self {
+            token,
+            path: Some(path),
+        })
+    }
+}
+
+impl Drop for IssuedToken {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A Unix domain socket (see `crate::socket` for its location), removed on
+/// drop.
+pub struct SocketTransport {
+    listener: UnixListener,
+    path: PathBuf,
+    token: IssuedToken,
+}
+
+impl SocketTransport {
+    /// Bind the socket; a generated token goes next to it (`<socket>.token`).
+    pub fn bind() -> io::Result<Self> {
+        let path = get_socket_path()
+            .map_err(|e| io::Error::new(e.kind(), format!("No socket location: {e}")))?;
+        Self::bind_at(path, None)
+    }
+
+    /// Bind the socket at `path`. A `token` we hand to the client ourselves
+    /// (as `vellum run` does) isn't written anywhere.
+    pub fn bind_at(path: PathBuf, token: Option<SessionToken>) -> io::Result<Self> {
+        let listener = bind_socket(&path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Failed to bind {}: {e}", path.display()))
+        })?;
+        let token = match token {
+            Some(token) => Ok(IssuedToken { token, path: None }),
+            None => IssuedToken::issue(path.with_extension("token")),
+        };
+        let token = match token {
+            Ok(token) => token,
+            Err(e) => {
+                let _ = std::fs::remove_file(&path);
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            listener,
+            path,
+            token,
+        })
+    }
+}
+
+impl Transport for SocketTransport {
+    fn describe(&self) -> String {
+        format!("socket {}", self.path.display())
+    }
+
+    fn accept(&mut self) -> io::Result<Connection> {
+        let (stream, _) = self.listener.accept()?;
+        let reader = stream.try_clone()?;
+        let closer = stream.try_clone()?;
+        let timer = stream.try_clone()?;
+        Ok(Connection::new(reader, stream)
+            .with_close_read(move || {
+                let _ = closer.shutdown(Shutdown::Read);
+            })
+            .with_read_timeout(move |timeout| timer.set_read_timeout(timeout)))
+    }
+
+    fn session_token(&self) -> Option<&SessionToken> {
+        Some(&self.token.token)
+    }
+}
+
+impl Drop for SocketTransport {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A TCP port (`VELLUM_LISTEN`, loopback by default) for clients in a
+/// container or another runtime, speaking either raw frames or frames inside
+/// binary WebSocket messages (for browser-based tools). A generated token is
+/// written to `vellum-<port>.token` in the runtime dir.
+pub struct TcpTransport {
+    listener: TcpListener,
+    websocket: bool,
+    token: IssuedToken,
+}
+
+impl TcpTransport {
+    /// Bind `VELLUM_LISTEN`, which must be a loopback address: anything else
+    /// would expose the window (and the token, in clear text) to the network.
+    pub fn bind(websocket: bool) -> io::Result<Self> {
+        let addr = std::env::var("VELLUM_LISTEN")
+            .ok()
+            .filter(|addr| !addr.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_LISTEN_ADDR.to_string());
+        let listener = TcpListener::bind(addr.trim())
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to listen on {addr}: {e}")))?;
+        let local = listener.local_addr()?;
+        if !local.ip().is_loopback() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Refusing to listen on non-loopback {local}"),
+            ));
+        }
+        let token =
+            IssuedToken::issue(runtime_dir()?.join(format!("vellum-{}.token", local.port())))?;
+        Ok(Self {
+            listener,
+            websocket,
+            token,
+        })
+    }
+
+    fn connection(stream: TcpStream, websocket: bool) -> io::Result<Connection> {
+        stream.set_nodelay(true)?;
+        let closer = stream.try_clone()?;
+        let timer = stream.try_clone()?;
+        let connection = if websocket {
+            let (reader, writer) = websocket::split(stream)?;
+            Connection::new(reader, writer)
+        } else {
+            Connection::new(stream.try_clone()?, stream)
+        };
+        Ok(connection
+            .remote()
+            .with_close_read(move || {
+                let _ = closer.shutdown(Shutdown::Read);
+            })
+            .with_read_timeout(move |timeout| timer.set_read_timeout(timeout)))
+    }
+}
+
+impl Transport for TcpTransport {
+    fn describe(&self) -> String {
+        match (self.listener.local_addr(), self.websocket) {
+            (Ok(addr), true) => format!("ws://{addr}"),
+            (Ok(addr), false) => format!("tcp {addr}"),
+            (Err(_), _) => "tcp".to_string(),
+        }
+    }
+
+    fn accept(&mut self) -> io::Result<Connection> {
+        loop {
+            let (mut stream, peer) = self.listener.accept()?;
+            if !self.websocket {
+                return Self::connection(stream, false);
+            }
+            // A bad upgrade request only costs that connection
+            stream.set_read_timeout(Some(UPGRADE_TIMEOUT))?;
+            match websocket::accept(&mut stream) {
+                Ok(()) => {
+                    stream.set_read_timeout(None)?;
+                    return Self::connection(stream, true);
+                }
+                Err(e) => eprintln!("[IPC] WebSocket upgrade from {peer} failed: {e}"),
+            }
+        }
+    }
+
+    fn session_token(&self) -> Option<&SessionToken> {
+        Some(&self.token.token)
+    }
+}
+
+/// The process's own stdin and stdout, for hosts that spawn Vellum and talk
+/// to it over pipes. There is exactly one client, and stdout carries nothing
+/// but frames (all logging goes to stderr).
+#[derive(Default)]
+pub struct StdioTransport {
+    connected: bool,
+}
+
+impl Transport for StdioTransport {
+    fn describe(&self) -> String {
+        "stdio".to_string()
+    }
+
+    fn accept(&mut self) -> io::Result<Connection> {
+        if std::mem::replace(&mut self.connected, true) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "stdio serves a single client",
+            ));
+        }
+        Ok(Connection::new(io::stdin(), io::stdout()))
+    }
+
+    fn session_token(&self) -> Option<&SessionToken> {
+        None
+    }
+}
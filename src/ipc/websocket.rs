@@ -0,0 +1,351 @@
+// Just enough of the server side of RFC 6455 to carry the frame stream over
+// a WebSocket: binary messages are concatenated into one byte stream, which
+// holds the same length-prefixed MsgPack frames as the other transports.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use sha1::{Digest, Sha1};
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Longest HTTP upgrade request we read before giving up.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Answer the HTTP upgrade request that opens a WebSocket on `stream`.
+pub fn accept(stream: &mut TcpStream) -> io::Result<()> {
+    let head = read_request_head(stream)?;
+    let key = match upgrade_key(&head) {
+        Ok(key) => key,
+        Err(reason) => {
+            let _ = stream.write_all(
+                b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            );
+            return Err(io::Error::new(io::ErrorKind::InvalidData, reason));
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    )?;
+    stream.flush()
+}
+
+/// Split an upgraded stream into a reader of the concatenated binary
+/// payloads and a writer that sends one binary message per `flush`.
+pub fn split(stream: TcpStream) -> io::Result<(WsReader, WsWriter)> {
+    let out = Arc::new(Mutex::new(stream.try_clone()?));
+    let reader = WsReader {
+        stream,
+        out: Arc::clone(&out),
+        remaining: 0,
+        mask: [0; 4],
+        offset: 0,
+        closed: false,
+    };
+    let writer = WsWriter {
+        out,
+        pending: Vec::new(),
+    };
+    Ok((reader, writer))
+}
+
+/// Reads the request line and headers, byte by byte so nothing past the
+/// blank line is consumed.
+fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_REQUEST_HEAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "HTTP request head too long",
+            ));
+        }
+        stream.read_exact(&mut byte)?;
+        head.push(byte[0]);
+    }
+    String::from_utf8(head)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "HTTP request is not UTF-8"))
+}
+
+/// The `Sec-WebSocket-Key` of a valid upgrade request.
+fn upgrade_key(head: &str) -> Result<String, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    if !request_line.starts_with("GET ") {
+        return Err(format!(
+            "expected a GET upgrade request, got '{request_line}'"
+        ));
+    }
+    let header = |name: &str| {
+        head.split("\r\n").skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let has_token = |name: &str, token: &str| {
+        header(name).is_some_and(|value| {
+            value
+                .split(',')
+                .any(|item| item.trim().eq_ignore_ascii_case(token))
+        })
+    };
+    if !has_token("Upgrade", "websocket") || !has_token("Connection", "upgrade") {
+        return Err("not a WebSocket upgrade request".to_string());
+    }
+    if header("Sec-WebSocket-Version").as_deref() != Some("13") {
+        return Err("unsupported WebSocket version (expected 13)".to_string());
+    }
+    header("Sec-WebSocket-Key").ok_or_else(|| "missing Sec-WebSocket-Key".to_string())
+}
+
+fn accept_key(key: &str) -> String {
+    let digest = Sha1::digest(format!("{key}{ACCEPT_GUID}").as_bytes());
+    BASE64_STANDARD.encode(digest)
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Write one unmasked, final frame. The lock keeps control replies from the
+/// reader from interleaving with data frames.
+fn send_frame(out: &Mutex<TcpStream>, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    let mut stream = out.lock().unwrap_or_else(PoisonError::into_inner);
+    stream.write_all(&frame)?;
+    stream.flush()
+}
+
+/// Binary payloads from the client as one stream. Pings are answered and a
+/// close frame (or the connection closing) ends the stream.
+pub struct WsReader {
+    stream: TcpStream,
+    out: Arc<Mutex<TcpStream>>,
+    /// Payload bytes left in the current data frame
+    remaining: u64,
+    mask: [u8; 4],
+    /// Position in the current frame, for unmasking
+    offset: usize,
+    closed: bool,
+}
+
+impl WsReader {
+    /// Read frame headers until a data frame with payload starts.
+    fn next_frame(&mut self) -> io::Result<()> {
+        let mut header = [0; 2];
+        match self.stream.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.closed = true;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+        if header[0] & 0x70 != 0 {
+            return Err(invalid("WebSocket extensions are not supported"));
+        }
+        if header[1] & 0x80 == 0 {
+            return Err(invalid("client WebSocket frames must be masked"));
+        }
+        let opcode = header[0] & 0x0F;
+        let len = match header[1] & 0x7F {
+            126 => {
+                let mut len = [0; 2];
+                self.stream.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                self.stream.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        self.stream.read_exact(&mut self.mask)?;
+        self.offset = 0;
+
+        match opcode {
+            OP_BINARY | OP_CONTINUATION => {
+                self.remaining = len;
+                Ok(())
+            }
+            OP_TEXT => Err(invalid(
+                "send frames as binary WebSocket messages, not text",
+            )),
+            OP_CLOSE | OP_PING | OP_PONG => {
+                if len > 125 {
+                    return Err(invalid("WebSocket control frame too long"));
+                }
+                let mut payload = vec![0; len as usize];
+                self.stream.read_exact(&mut payload)?;
+                for (i, byte) in payload.iter_mut().enumerate() {
+                    *byte ^= self.mask[i % 4];
+                }
+                match opcode {
+                    OP_PING => send_frame(&self.out, OP_PONG, &payload),
+                    OP_CLOSE => {
+                        // Echo the status code, then treat it as end of stream
+                        self.closed = true;
+                        let _ = send_frame(&self.out, OP_CLOSE, &payload[..payload.len().min(2)]);
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            other => Err(invalid(&format!("unknown WebSocket opcode {other:#x}"))),
+        }
+    }
+}
+
+impl Read for WsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.remaining == 0 {
+            if self.closed {
+                return Ok(0);
+            }
+            self.next_frame()?;
+        }
+        let want = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.stream.read(&mut buf[..want])?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        for (i, byte) in buf[..read].iter_mut().enumerate() {
+            *byte ^= self.mask[(self.offset + i) % 4];
+        }
+        self.offset += read;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Buffers writes and sends them as one binary message on `flush`, so each
+/// `write_msgpack_frame` becomes one WebSocket message.
+pub struct WsWriter {
+    out: Arc<Mutex<TcpStream>>,
+    pending: Vec<u8>,
+}
+
+impl Write for WsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let result = send_frame(&self.out, OP_BINARY, &self.pending);
+        self.pending.clear();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_accept_key_matches_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGj7EoCuSIB+4="
+        );
+    }
+
+    /// A masked client frame.
+    fn client_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn test_binary_messages_form_one_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        client
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\n\
+                  Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .unwrap();
+        accept(&mut server).unwrap();
+        let (mut reader, mut writer) = split(server).unwrap();
+
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            client.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("s3pPLMBiTxaQ9kYGj7EoCuSIB+4="));
+
+        // A frame split over two messages, with a ping in between
+        client
+            .write_all(&client_frame(OP_BINARY, b"hello "))
+            .unwrap();
+        client.write_all(&client_frame(OP_PING, b"p")).unwrap();
+        client
+            .write_all(&client_frame(OP_CONTINUATION, b"world"))
+            .unwrap();
+        let mut received = [0; 11];
+        reader.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"hello world");
+
+        let mut pong = [0; 3];
+        client.read_exact(&mut pong).unwrap();
+        assert_eq!(pong, [0x80 | OP_PONG, 1, b'p']);
+
+        writer.write_all(b"ok").unwrap();
+        writer.flush().unwrap();
+        let mut message = [0; 4];
+        client.read_exact(&mut message).unwrap();
+        assert_eq!(message, [0x80 | OP_BINARY, 2, b'o', b'k']);
+
+        client
+            .write_all(&client_frame(OP_CLOSE, &[0x03, 0xE8]))
+            .unwrap();
+        assert_eq!(reader.read(&mut received).unwrap(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+/// Window attributes requested before the window is created, by the command
+/// line, the app manifest or the client's `Hello`. Unset fields keep their
+/// defaults; sizes and positions are in logical pixels.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WindowOptions {
+    pub title: Option<String>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub min_width: Option<f64>,
+    pub min_height: Option<f64>,
+    pub max_width: Option<f64>,
+    pub max_height: Option<f64>,
+    /// Position of the window's outer top-left corner on the desktop
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub resizable: Option<bool>,
+    pub decorations: Option<bool>,
+    /// Show the desktop through wherever nothing is painted
+    pub transparent: Option<bool>,
+    /// Image file for the window icon, on the machine running Vellum
+    pub icon: Option<String>,
+}
+
+/// What the window looks like right now, in logical pixels. Sent with
+/// `WindowStateChanged` and as the result of `GetWindowState`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub focused: bool,
+    pub decorations: bool,
+    pub always_on_top: bool,
+    /// Outer position; None where the platform doesn't tell (Wayland)
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    /// Inner size
+    pub width: f64,
+    pub height: f64,
+}
+
+impl WindowOptions {
+    /// Fill fields that are unset in `self` from `other`.
+    pub fn or(self, other: WindowOptions) -> WindowOptions {
+        WindowOptions {
+            title: self.title.or(other.title),
+            width: self.width.or(other.width),
+            height: self.height.or(other.height),
+            min_width: self.min_width.or(other.min_width),
+            min_height: self.min_height.or(other.min_height),
+            max_width: self.max_width.or(other.max_width),
+            max_height: self.max_height.or(other.max_height),
+            x: self.x.or(other.x),
+            y: self.y.or(other.y),
+            resizable: self.resizable.or(other.resizable),
+            decorations: self.decorations.or(other.decorations),
+            transparent: self.transparent.or(other.transparent),
+            icon: self.icon.or(other.icon),
+        }
+    }
+
+    /// Reject sizes that aren't positive, positions that aren't finite, and
+    /// minimum sizes above the requested or maximum size.
+    pub fn validate(&self) -> Result<(), String> {
+        let sizes = [
+            ("width", self.width),
+            ("height", self.height),
+            ("minWidth", self.min_width),
+            ("minHeight", self.min_height),
+            ("maxWidth", self.max_width),
+            ("maxHeight", self.max_height),
+        ];
+        for (name, value) in sizes {
+            if let Some(value) = value
+                && !(value.is_finite() && value > 0.0)
+            {
+                return Err(format!("{name} must be a positive number, got {value}"));
+            }
+        }
+        for (name, value) in [("x", self.x), ("y", self.y)] {
+            if let Some(value) = value
+                && !value.is_finite()
+            {
+                return Err(format!("{name} must be a finite number, got {value}"));
+            }
+        }
+        let bounds = [
+            ("minWidth", self.min_width, "width", self.width),
+            ("minHeight", self.min_height, "height", self.height),
+            ("minWidth", self.min_width, "maxWidth", self.max_width),
+            ("minHeight", self.min_height, "maxHeight", self.max_height),
+        ];
+        for (min_name, min, name, value) in bounds {
+            if let (Some(min), Some(value)) = (min, value)
+                && min > value
+            {
+                return Err(format!(
+                    "{min_name} ({min}) is larger than {name} ({value})"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_options_layering_and_validation() {
+        let cli = WindowOptions {
+            title: Some("From CLI".to_string()),
+            ..Default::default()
+        };
+        let manifest: WindowOptions = serde_json::from_str(
+            r#"{"title": "From manifest", "minWidth": 320, "resizable": false}"#,
+        )
+        .unwrap();
+        let merged = cli.or(manifest);
+        assert_eq!(merged.title.as_deref(), Some("From CLI"));
+        assert_eq!(merged.min_width, Some(320.0));
+        assert_eq!(merged.resizable, Some(false));
+        assert!(merged.validate().is_ok());
+
+        let bad = WindowOptions {
+            width: Some(0.0),
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+        let too_small = WindowOptions {
+            width: Some(300.0),
+            min_width: Some(400.0),
+            ..Default::default()
+        };
+        assert!(too_small.validate().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+// Client Launcher Module
+// Spawns the JS client for `vellum run`, forwards its output and supervises it
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cli::RunOptions;
+use crate::ipc::handshake::{SESSION_TOKEN_ENV, SessionToken};
+use crate::ipc::{ClientCommand, ClientCommandSender};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often dev mode looks for changed files.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a client may take to exit on its own after the window closed.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+/// Directories dev mode doesn't watch.
+const IGNORED_DIRS: &[&str] = &["node_modules", "target", "dist", "build"];
+/// Upper bound on files scanned per check, so a huge tree can't stall the watcher.
+const MAX_WATCHED_FILES: usize = 20_000;
+
+/// The running client and the thread supervising it.
+pub struct Supervisor {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<i32>,
+}
+
+impl Supervisor {
+    /// Start the client described by `options`, pointed at `socket` with
+    /// `token`. When it exits the app exits too, unless `options.dev` is set,
+    /// in which case it is restarted once its files change.
+    pub fn start(
+        options: RunOptions,
+        socket: PathBuf,
+        token: SessionToken,
+        commands: ClientCommandSender,
+    ) -> io::Result<Self> {
+        let launch = Launch {
+            options,
+            socket,
+            token,
+        };
+        // Fail fast (before the window shows) if the runtime can't be started
+        let child = launch.spawn()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name("client-supervisor".to_string())
+                .spawn(move || launch.supervise(child, &stop, &commands))?
+        };
+        Ok(Self { stop, thread })
+    }
+
+    /// Stop supervising (the window closed) and return the client's exit
+    /// code, killing it if it doesn't exit in time.
+    pub fn finish(self) -> i32 {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.join().unwrap_or(1)
+    }
+}
+
+struct Launch {
+    options: RunOptions,
+    socket: PathBuf,
+    token: SessionToken,
+}
+
+impl Launch {
+    fn spawn(&self) -> io::Result<Child> {
+        let entry = self.options.entry.to_string_lossy();
+        let (program, mut args) = self.options.runtime.command_line(&entry);
+        args.extend(self.options.args.iter().cloned());
+        let mut child = Command::new(program)
+            .args(&args)
+            .env("VELLUM_ATTACH", &self.socket)
+            .env(SESSION_TOKEN_ENV, self.token.as_str())
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to start {program}: {e}")))?;
+        if let Some(stdout) = child.stdout.take() {
+            forward(stdout, io::stdout());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward(stderr, io::stderr());
+        }
+        Ok(child)
+    }
+
+    fn supervise(
+        &self,
+        mut child: Child,
+        stop: &AtomicBool,
+        commands: &ClientCommandSender,
+    ) -> i32 {
+        let watch_root = watch_root(&self.options.entry);
+        let mut snapshot = self.options.dev.then(|| snapshot(&watch_root));
+        loop {
+            let status = match self.wait(&mut child, stop, snapshot.as_mut(), &watch_root) {
+                Waited::Exited(status) => status,
+                Waited::Stopped(code) => return code,
+                Waited::Changed => {
+                    eprintln!("[Dev] Files changed, restarting the client");
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    match self.restart(commands) {
+                        Some(restarted) => {
+                            child = restarted;
+                            continue;
+                        }
+                        None => return 1,
+                    }
+                }
+            };
+            let code = exit_code(status);
+            let Some(snapshot) = snapshot.as_mut() else {
+                eprintln!("[Launcher] Client exited with code {code}");
+                let _ = commands.send(ClientCommand::ExitApp);
+                return code;
+            };
+
+            // Dev mode: keep the window and wait for a fix
+            eprintln!("[Dev] Client exited with code {code}; waiting for file changes...");
+            loop {
+                if stop.load(Ordering::SeqCst) {
+                    return code;
+                }
+                thread::sleep(WATCH_INTERVAL);
+                let current = self::snapshot(&watch_root);
+                if current != *snapshot {
+                    *snapshot = current;
+                    break;
+                }
+            }
+            eprintln!("[Dev] Files changed, restarting the client");
+            match self.restart(commands) {
+                Some(restarted) => child = restarted,
+                None => return 1,
+            }
+        }
+    }
+
+    fn restart(&self, commands: &ClientCommandSender) -> Option<Child> {
+        match self.spawn() {
+            Ok(child) => Some(child),
+            Err(e) => {
+                eprintln!("[Launcher] {e}");
+                let _ = commands.send(ClientCommand::ExitApp);
+                None
+            }
+        }
+    }
+
+    /// Wait until the client exits, the app stops or (in dev mode) the
+    /// watched files change.
+    fn wait(
+        &self,
+        child: &mut Child,
+        stop: &AtomicBool,
+        mut snapshot: Option<&mut Snapshot>,
+        watch_root: &Path,
+    ) -> Waited {
+        let mut last_check = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return Waited::Exited(status),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("[Launcher] Failed to poll the client: {e}");
+                    return Waited::Stopped(1);
+                }
+            }
+            if stop.load(Ordering::SeqCst) {
+                return Waited::Stopped(stop_child(child));
+            }
+            if let Some(snapshot) = snapshot.as_deref_mut()
+                && last_check.elapsed() >= WATCH_INTERVAL
+            {
+                last_check = Instant::now();
+                let current = self::snapshot(watch_root);
+                if current != *snapshot {
+                    *snapshot = current;
+                    return Waited::Changed;
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+enum Waited {
+    Exited(ExitStatus),
+    Stopped(i32),
+    Changed,
+}
+
+/// Give the client (which was sent `Shutdown`) a moment to exit, then kill it.
+fn stop_child(child: &mut Child) -> i32 {
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(status)) = child.try_wait() {
+            return exit_code(status);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let _ = child.kill();
+    child.wait().map(exit_code).unwrap_or(1)
+}
+
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+/// Copy a child's output stream to ours on a background thread.
+fn forward(mut from: impl io::Read + Send + 'static, mut to: impl io::Write + Send + 'static) {
+    let _ = thread::Builder::new()
+        .name("client-output".to_string())
+        .spawn(move || {
+            let _ = io::copy(&mut from, &mut to);
+        });
+}
+
+/// The directory holding the entry script, watched in dev mode.
+fn watch_root(entry: &Path) -> PathBuf {
+    match entry.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Newest modification time and file count under the watched directory;
+/// any edit, addition or removal changes it.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    newest: Option<SystemTime>,
+    files: usize,
+}
+
+fn snapshot(root: &Path) -> Snapshot {
+    let mut snapshot = Snapshot {
+        newest: None,
+        files: 0,
+    };
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if !IGNORED_DIRS.contains(&name.as_ref()) {
+                    pending.push(entry.path());
+                }
+                continue;
+            }
+            snapshot.files += 1;
+            if let Ok(modified) = metadata.modified() {
+                snapshot.newest = snapshot.newest.max(Some(modified));
+            }
+            if snapshot.files >= MAX_WATCHED_FILES {
+                return snapshot;
+            }
+        }
+    }
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_notices_new_and_changed_files() {
+        let root = std::env::temp_dir().join(format!("vellum-watch-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("node_modules")).unwrap();
+        std::fs::write(root.join("app.ts"), "one").unwrap();
+        let before = snapshot(&root);
+        assert_eq!(before.files, 1);
+
+        // Ignored directories don't count
+        std::fs::write(root.join("node_modules").join("dep.js"), "").unwrap();
+        assert_eq!(snapshot(&root), before);
+
+        std::fs::write(root.join("util.ts"), "two").unwrap();
+        assert_ne!(snapshot(&root), before);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_watch_root_of_bare_file_is_cwd() {
+        assert_eq!(watch_root(Path::new("app.ts")), PathBuf::from("."));
+        assert_eq!(watch_root(Path::new("src/app.ts")), PathBuf::from("src"));
+    }
+}
@@ -5,23 +5,87 @@
 // - Background Thread (JS): Runs a Bun subprocess bridge
 //
 // Communication between threads uses EventLoopProxy (JS→UI, zero polling)
-// and MsgPack over Bun stdio (UI→JS, for UI events).
+// and MsgPack over a socket or stdio (UI→JS, for UI events; see ipc/transport.rs).
+//
+// `vellum run <entry>` also starts the JS client itself (see launcher.rs);
+// plain `vellum` waits for one to connect.
 
 // On Windows platform, don't show a console when opening the app.
 // #![windows_subsystem = "windows"]
 
+mod cli;
 mod ipc;
+mod launcher;
+mod manifest;
 mod socket;
 mod ui;
 
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::thread;
+use std::time::Duration;
 
-use ipc::IpcChannels;
+use cli::{Command, RunOptions, WindowArgs};
+use ipc::handshake::{SESSION_TOKEN_ENV, SessionToken};
 use ipc::server::run_ipc_server;
-use ui::{prepare_ui, run_ui_blocking};
+use ipc::transport::{SocketTransport, Transport, transport_from_env};
+use ipc::{IpcChannels, WindowOptions};
+use launcher::Supervisor;
+use manifest::Manifest;
+use ui::{WindowConfig, prepare_ui, run_ui_blocking};
+
+/// How long the window waits for the `Hello` of an expected client (see
+/// `expects_client`), which may say how it should look, before it is shown
+/// as configured so far.
+const STARTUP_WINDOW_WAIT: Duration = Duration::from_secs(3);
 
 fn main() {
-    println!("Vellum Starting...");
+    let command = match cli::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("vellum: {e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let (run_options, window_args) = match command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Command::Version => {
+            println!("vellum {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Command::Serve(window_args) => (None, window_args),
+        Command::Run(options) => {
+            let window_args = options.window.clone();
+            (Some(options), window_args)
+        }
+    };
+    let manifest_dir = match &run_options {
+        Some(options) => options
+            .entry
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf(),
+        None => PathBuf::new(),
+    };
+    let manifest_window = match load_manifest(&window_args, &manifest_dir) {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("vellum: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // Flags and manifest must agree before anything is started
+    let local_window = window_args.overrides.clone().or(manifest_window);
+    if let Err(e) = window_config(&local_window) {
+        eprintln!("vellum: {e}");
+        std::process::exit(1);
+    }
+
+    eprintln!("Vellum Starting...");
 
     let rust_log = std::env::var("RUST_LOG").ok();
     let should_override_log = match rust_log.as_deref() {
@@ -32,10 +96,10 @@ fn main() {
         unsafe {
             std::env::set_var("RUST_LOG", "warn");
         }
-        println!("[Main] RUST_LOG set to info");
+        eprintln!("[Main] RUST_LOG set to info");
     }
 
-    println!("[Main] Operating in Client-Server Socket IPC Mode");
+    eprintln!("[Main] Operating in Client-Server IPC Mode");
 
     // Phase 1: Build the EventLoop and extract EventLoopProxy (non-blocking).
     // This must happen before spawning the JS thread so the proxy can be shared.
@@ -49,26 +113,166 @@ fn main() {
     let ui_channels = channels.ui;
     let js_channels = channels.ipc_server;
 
-    // Phase 3: Spawn the IPC server thread with EventLoopProxy-based command sender.
+    // Phase 3: Open the transport and, for `vellum run`, start the client on it.
+    let (transport, supervisor, socket_path) = match &run_options {
+        Some(options) => match launch_client(options, &js_channels) {
+            Ok((transport, supervisor, path)) => (transport, Some(supervisor), Some(path)),
+            Err(e) => {
+                eprintln!("[Main] {e}");
+                socket::remove_runtime_dir();
+                std::process::exit(1);
+            }
+        },
+        None => match transport_from_env() {
+            Ok(transport) => (transport, None, None),
+            Err(e) => {
+                eprintln!("[Main] {e}");
+                socket::remove_runtime_dir();
+                std::process::exit(1);
+            }
+        },
+    };
+    // A dev client may restart, so keep the window for the next one
+    let reconnect = run_options.as_ref().is_some_and(|options| options.dev);
+
+    // Phase 4: Spawn the IPC server thread with EventLoopProxy-based command sender.
     let ipc_server_handle = thread::Builder::new()
         .name("ipc-server".to_string())
         .spawn(move || {
-            println!("[Main] IPC server thread started");
-            run_ipc_server(js_channels);
-            println!("[Main] IPC server thread finished");
+            eprintln!("[Main] IPC server thread started");
+            run_ipc_server(js_channels, transport, reconnect);
+            eprintln!("[Main] IPC server thread finished");
         })
         .unwrap_or_else(|e| panic!("Fatal: failed to spawn IPC server thread: {e}"));
 
-    // Phase 4: Run the UI event loop on the main thread (blocks forever).
+    // Phase 5: Hold the window back until the expected client said how it
+    // should look, so it doesn't flash with the defaults. Without one there
+    // may be nobody to wait for. Flags win over the client, and the client
+    // over the manifest.
+    let client_window = if expects_client(supervisor.is_some(), |name| std::env::var_os(name)) {
+        ui_channels
+            .startup_window
+            .recv_timeout(STARTUP_WINDOW_WAIT)
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    drop(ui_channels.startup_window);
+    let window = client_window
+        .and_then(|client_window| {
+            let options = window_args
+                .overrides
+                .clone()
+                .or(client_window)
+                .or(local_window.clone());
+            window_config(&options)
+                .inspect_err(|e| eprintln!("[Main] Ignoring the client's window options: {e}"))
+                .ok()
+        })
+        .or_else(|| window_config(&local_window).ok())
+        .unwrap_or_default();
+
+    // Phase 6: Run the UI event loop on the main thread (blocks forever).
     // The main thread MUST run the UI due to platform requirements (macOS, etc.).
-    println!("[Main] Starting UI on main thread");
-    run_ui_blocking(event_loop, ui_setup.window_id, ui_channels.event_sender);
+    eprintln!("[Main] Starting UI on main thread");
+    run_ui_blocking(
+        event_loop,
+        ui_setup.window_id,
+        ui_channels.event_sender,
+        &window,
+    );
+
+    // Stop the client we started; its exit code becomes ours
+    let exit_code = supervisor.map(Supervisor::finish);
+    if let Some(path) = socket_path {
+        // Wake the server if it is waiting for a (restarted) client
+        let _ = socket::UnixStream::connect(path);
+    }
 
     // Wait for the IPC server thread to finish after the UI closes
-    println!("[Main] UI closed, waiting for IPC server thread to finish...");
+    eprintln!("[Main] UI closed, waiting for IPC server thread to finish...");
     if let Err(e) = ipc_server_handle.join() {
         eprintln!("[Main] IPC server thread panicked: {:?}", e);
     }
+    // The socket and token files went with the transport
+    socket::remove_runtime_dir();
+
+    eprintln!("[Main] Vellum shutdown complete");
+    if let Some(code) = exit_code {
+        std::process::exit(code);
+    }
+}
+
+/// Whether a client is on its way: one `vellum run` launched, or the one
+/// that spawned this process and passed its socket or session token (as the
+/// JS bridge does). `env` looks up an environment variable.
+fn expects_client(launched: bool, env: impl Fn(&str) -> Option<OsString>) -> bool {
+    launched
+        || ["VELLUM_SOCKET", SESSION_TOKEN_ENV]
+            .iter()
+            .any(|name| env(name).is_some_and(|value| !value.is_empty()))
+}
+
+/// The window described by `options` on top of the defaults.
+fn window_config(options: &WindowOptions) -> Result<WindowConfig, String> {
+    let mut window = WindowConfig::default();
+    window.apply(options)?;
+    Ok(window)
+}
 
-    println!("[Main] Vellum shutdown complete");
+/// Window attributes from `--manifest`, or from the `vellum.json` in `dir`
+/// when there is one.
+fn load_manifest(args: &WindowArgs, dir: &Path) -> Result<WindowOptions, String> {
+    let path = match &args.manifest {
+        Some(path) => path.clone(),
+        None => match Manifest::find(dir) {
+            Some(path) => path,
+            None => return Ok(WindowOptions::default()),
+        },
+    };
+    eprintln!("[Main] Reading {}", path.display());
+    Manifest::load(&path).map(|manifest| manifest.window)
+}
+
+/// Bind a socket for `vellum run` and start the client on it.
+fn launch_client(
+    options: &RunOptions,
+    channels: &ipc::IpcServerChannels,
+) -> std::io::Result<(Box<dyn Transport>, Supervisor, PathBuf)> {
+    let path = match &options.socket {
+        // The client tells a path from `host:port` by its leading slash
+        Some(path) => std::path::absolute(path)?,
+        None => socket::runtime_dir()?.join(format!("vellum-{}.sock", std::process::id())),
+    };
+    let token = SessionToken::generate()?;
+    let transport = SocketTransport::bind_at(path.clone(), Some(token.clone()))?;
+    eprintln!("[Main] Starting {}", options.entry.display());
+    let supervisor = Supervisor::start(
+        options.clone(),
+        path.clone(),
+        token,
+        channels.command_sender.clone(),
+    )?;
+    Ok((Box::new(transport), supervisor, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_waits_for_a_client_that_spawned_vellum() {
+        // The JS bridge starts plain `vellum` with its socket and token
+        let bridge = |name: &str| match name {
+            "VELLUM_SOCKET" => Some(OsString::from("/run/user/1000/vellum/app.sock")),
+            SESSION_TOKEN_ENV => Some(OsString::from("0123abcd")),
+            _ => None,
+        };
+        assert!(expects_client(false, bridge));
+        assert!(expects_client(true, |_| None));
+        // Started by hand, nobody may ever connect
+        assert!(!expects_client(false, |_| None));
+        assert!(!expects_client(false, |_| Some(OsString::new())));
+    }
 }
@@ -0,0 +1,73 @@
+// App Manifest Module
+// Reads `vellum.json`, the app's own defaults for how Vellum starts it
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::ipc::WindowOptions;
+
+/// Looked up next to the entry script (`vellum run`) or in the working directory.
+pub const MANIFEST_FILE: &str = "vellum.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// Initial window attributes, e.g. `{ "title": "Notes", "width": 1024 }`
+    pub window: WindowOptions,
+}
+
+impl Manifest {
+    /// `vellum.json` in `dir`, if there is one.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        let path = dir.join(MANIFEST_FILE);
+        path.is_file().then_some(path)
+    }
+
+    /// Read and check the manifest at `path`. A relative icon path is taken
+    /// relative to the manifest.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let mut manifest: Manifest =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid {}: {e}", path.display()))?;
+        manifest
+            .window
+            .validate()
+            .map_err(|e| format!("Invalid window in {}: {e}", path.display()))?;
+        if let (Some(icon), Some(dir)) = (&manifest.window.icon, path.parent()) {
+            manifest.window.icon = Some(dir.join(icon).to_string_lossy().into_owned());
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_resolves_icon_next_to_manifest() {
+        let dir = std::env::temp_dir().join(format!("vellum-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(MANIFEST_FILE);
+        std::fs::write(
+            &path,
+            r#"{ "name": "notes", "window": { "title": "Notes", "icon": "icon.png" } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(Manifest::find(&dir), Some(path.clone()));
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(manifest.window.title.as_deref(), Some("Notes"));
+        assert_eq!(
+            manifest.window.icon.map(PathBuf::from),
+            Some(dir.join("icon.png"))
+        );
+
+        std::fs::write(&path, r#"{ "window": { "width": -1 } }"#).unwrap();
+        assert!(Manifest::load(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
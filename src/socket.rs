@@ -1,36 +1,155 @@
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[cfg(unix)]
-pub use std::os::unix::net::UnixListener;
+pub use std::os::unix::net::{UnixListener, UnixStream};
 
 #[cfg(windows)]
-pub use uds_windows::UnixListener;
+pub use uds_windows::{UnixListener, UnixStream};
 
-/// Returns the platform-specific socket path
-pub fn get_socket_path() -> String {
-    if let Ok(path) = std::env::var("Vellum_SOCKET") {
-        return path;
+/// Returns the platform-specific socket path: `VELLUM_SOCKET` when set,
+/// otherwise a socket in a directory only the current user can enter.
+pub fn get_socket_path() -> io::Result<PathBuf> {
+    if let Some(path) = std::env::var_os("VELLUM_SOCKET").filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
     }
+    Ok(runtime_dir()?.join("vellum.sock"))
+}
 
+/// Directory for files only the current user may see (sockets, tokens).
+pub fn runtime_dir() -> io::Result<PathBuf> {
     #[cfg(windows)]
     {
-        // On Windows, a file path is fine, but it typically lives in the temp directory
-        let mut path = std::env::temp_dir();
-        path.push("Vellum.sock");
-        path.to_string_lossy().to_string()
+        // The temp directory is per-user on Windows
+        Ok(std::env::temp_dir())
+    }
+    #[cfg(not(windows))]
+    {
+        private_runtime_dir()
+    }
+}
+
+/// The directory `private_runtime_dir` made under the temp dir, if any.
+#[cfg(not(windows))]
+static TEMP_RUNTIME_DIR: std::sync::Mutex<Option<PathBuf>> = std::sync::Mutex::new(None);
+
+/// `$XDG_RUNTIME_DIR/vellum`, or a fresh directory under the temp dir when
+/// there is no runtime dir. Either way it is restricted to mode 0700. The
+/// temp directory is made once per process; see `remove_runtime_dir`.
+#[cfg(not(windows))]
+fn private_runtime_dir() -> io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.mode(0o700);
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime_dir) => {
+            // The runtime dir itself is private to the user, so an existing
+            // `vellum` directory in it is ours
+            let dir = PathBuf::from(runtime_dir).join("vellum");
+            builder.recursive(true).create(&dir)?;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+            Ok(dir)
+        }
+        None => {
+            let mut temp_dir = TEMP_RUNTIME_DIR
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(dir) = temp_dir.as_ref() {
+                return Ok(dir.clone());
+            }
+            // `/tmp` is shared, so never reuse a directory someone else made
+            let suffix: String = crate::ipc::handshake::random_bytes()?[..6]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            let dir = std::env::temp_dir().join(format!("vellum-{}-{suffix}", std::process::id()));
+            builder.create(&dir)?;
+            *temp_dir = Some(dir.clone());
+            Ok(dir)
+        }
     }
+}
+
+/// Delete the temp directory `runtime_dir` made, once nothing in it is used
+/// any more. `$XDG_RUNTIME_DIR/vellum` is shared between runs and stays.
+pub fn remove_runtime_dir() {
     #[cfg(not(windows))]
     {
-        // On Unix, /tmp is standard
-        "/tmp/Vellum.sock".to_string()
+        let dir = TEMP_RUNTIME_DIR
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some(dir) = dir
+            && let Err(e) = std::fs::remove_dir_all(&dir)
+        {
+            eprintln!("[Main] Failed to remove {}: {e}", dir.display());
+        }
     }
 }
 
+/// Bind the listener, replacing a stale socket file but never a socket that
+/// another process still listens on (or something that isn't a socket).
 pub fn bind_socket<P: AsRef<Path>>(path: P) -> io::Result<UnixListener> {
     let path = path.as_ref();
-    if path.exists() {
-        let _ = std::fs::remove_file(path);
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is in use by another process", path.display()),
+            ));
+        }
+        #[cfg(unix)]
+        if !std::os::unix::fs::FileTypeExt::is_socket(&metadata.file_type()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        #[cfg(not(unix))]
+        let _ = metadata;
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(listener)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_refuses_live_socket_and_replaces_stale_one() {
+        let dir = std::env::temp_dir().join(format!("vellum-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bind.sock");
+
+        let listener = bind_socket(&path).unwrap();
+        let error = bind_socket(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+
+        // Once nobody listens, the leftover file is replaced
+        drop(listener);
+        assert!(path.exists());
+        let _listener = bind_socket(&path).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_temp_runtime_dir_is_made_once_and_removed() {
+        if std::env::var_os("XDG_RUNTIME_DIR").is_some_and(|dir| !dir.is_empty()) {
+            return;
+        }
+        let dir = runtime_dir().unwrap();
+        assert_eq!(runtime_dir().unwrap(), dir);
+
+        remove_runtime_dir();
+        assert!(!dir.exists());
     }
-    UnixListener::bind(path)
 }
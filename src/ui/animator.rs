@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+
+use crate::ipc::{
+    AnimationDirection, BoxStyle, ColorValue, Easing, Keyframe, PaddingValue, TransformValue,
+    TransitionSpec,
+};
+
+/// Invokes `$callback!` with every `BoxStyle` field that can be interpolated.
+macro_rules! with_animatable_fields {
+    ($callback:ident) => {
+        $callback!(
+            font_size,
+            font_weight,
+            letter_spacing,
+            line_height,
+            word_spacing,
+            color,
+            background,
+            border_color,
+            hover_border_color,
+            border_width,
+            corner_radius,
+            opacity,
+            padding,
+            width,
+            height,
+            icon_size,
+            transform,
+            flex,
+            gap,
+        )
+    };
+}
+
+/// Values that can be interpolated between two keyframes.
+trait Lerp: Clone {
+    fn lerp(&self, to: &Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        (*self as f64).lerp(&(*to as f64), t) as f32
+    }
+}
+
+impl Lerp for ColorValue {
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        match (self, to) {
+            (
+                ColorValue::Rgba { r, g, b, a },
+                ColorValue::Rgba {
+                    r: r2,
+                    g: g2,
+                    b: b2,
+                    a: a2,
+                },
+            ) => {
+                let channel = |from: u8, to: u8| (from as f64).lerp(&(to as f64), t).round() as u8;
+                ColorValue::Rgba {
+                    r: channel(*r, *r2),
+                    g: channel(*g, *g2),
+                    b: channel(*b, *b2),
+                    a: channel(*a, *a2),
+                }
+            }
+            // Unresolved named colors cannot be blended; switch halfway.
+            _ if t < 0.5 => self.clone(),
+            _ => to.clone(),
+        }
+    }
+}
+
+impl Lerp for PaddingValue {
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        if let (PaddingValue::Uniform(from), PaddingValue::Uniform(to)) = (self, to) {
+            return PaddingValue::Uniform(from.lerp(to, t));
+        }
+        let sides = |padding: &PaddingValue| match *padding {
+            PaddingValue::Uniform(v) => [v, v, v, v],
+            PaddingValue::Sides {
+                top,
+                right,
+                bottom,
+                left,
+            } => [top, right, bottom, left],
+        };
+        let (from, to) = (sides(self), sides(to));
+        PaddingValue::Sides {
+            top: from[0].lerp(&to[0], t),
+            right: from[1].lerp(&to[1], t),
+            bottom: from[2].lerp(&to[2], t),
+            left: from[3].lerp(&to[3], t),
+        }
+    }
+}
+
+impl Lerp for TransformValue {
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        TransformValue {
+            translate_x: self.translate_x.lerp(&to.translate_x, t),
+            translate_y: self.translate_y.lerp(&to.translate_y, t),
+            rotate: self.rotate.lerp(&to.rotate, t),
+            scale_x: self.scale_x.lerp(&to.scale_x, t),
+            scale_y: self.scale_y.lerp(&to.scale_y, t),
+            skew_x: self.skew_x.lerp(&to.skew_x, t),
+            skew_y: self.skew_y.lerp(&to.skew_y, t),
+        }
+    }
+}
+
+/// Copy of `style` whose missing transform is the identity, so a transform
+/// can be tweened in from (or out to) an untransformed widget.
+fn with_identity_transform(style: &BoxStyle) -> BoxStyle {
+    BoxStyle {
+        transform: Some(style.transform.unwrap_or_default()),
+        ..style.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ResolvedKeyframe {
+    offset: f64,
+    style: BoxStyle,
+}
+
+/// Value of one property at iteration progress `t`, interpolating between
+/// the surrounding keyframes that set it.
+fn sample_property<T: Lerp>(
+    keyframes: &[ResolvedKeyframe],
+    t: f64,
+    get: impl Fn(&BoxStyle) -> Option<&T>,
+) -> Option<T> {
+    let mut previous: Option<(f64, &T)> = None;
+    for keyframe in keyframes {
+        let Some(value) = get(&keyframe.style) else {
+            continue;
+        };
+        if keyframe.offset >= t {
+            return Some(match previous {
+                Some((offset, from)) if keyframe.offset > offset => {
+                    from.lerp(value, (t - offset) / (keyframe.offset - offset))
+                }
+                _ => value.clone(),
+            });
+        }
+        previous = Some((keyframe.offset, value));
+    }
+    previous.map(|(_, value)| value.clone())
+}
+
+fn sample_keyframes(keyframes: &[ResolvedKeyframe], t: f64) -> BoxStyle {
+    macro_rules! sample {
+        ($($field:ident),* $(,)?) => {
+            BoxStyle {
+                $($field: sample_property(keyframes, t, |s| s.$field.as_ref()),)*
+                ..Default::default()
+            }
+        };
+    }
+    with_animatable_fields!(sample)
+}
+
+/// Keyframe at `offset` holding the `base` value of every animated property
+/// that no keyframe sets at that offset (CSS implicit `from` / `to` frames).
+fn implicit_keyframe(keyframes: &[ResolvedKeyframe], base: &BoxStyle, offset: f64) -> BoxStyle {
+    let mut style = BoxStyle::default();
+    macro_rules! fill {
+        ($($field:ident),* $(,)?) => {
+            $(
+                let animated = keyframes.iter().any(|k| k.style.$field.is_some());
+                let explicit = keyframes
+                    .iter()
+                    .any(|k| k.offset == offset && k.style.$field.is_some());
+                if animated && !explicit {
+                    style.$field = base.$field.clone();
+                }
+            )*
+        };
+    }
+    with_animatable_fields!(fill);
+    style
+}
+
+/// Assign offsets to keyframes that omit them: the first and last default to
+/// 0 and 1, and runs of missing offsets are spread evenly between neighbours.
+fn resolve_offsets(keyframes: Vec<Keyframe>) -> Vec<ResolvedKeyframe> {
+    let count = keyframes.len();
+    let mut offsets: Vec<Option<f64>> = keyframes
+        .iter()
+        .map(|k| k.offset.map(|o| o.clamp(0.0, 1.0)))
+        .collect();
+    if count > 0 {
+        offsets[0].get_or_insert(0.0);
+        offsets[count - 1].get_or_insert(if count == 1 { 0.0 } else { 1.0 });
+    }
+
+    let known: Vec<usize> = (0..count).filter(|&i| offsets[i].is_some()).collect();
+    for pair in known.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let start_offset = offsets[start].unwrap_or(0.0);
+        let end_offset = offsets[end].unwrap_or(1.0);
+        let steps = (end - start) as f64;
+        for (step, slot) in offsets[start + 1..end].iter_mut().enumerate() {
+            *slot = Some(start_offset + (end_offset - start_offset) * (step + 1) as f64 / steps);
+        }
+    }
+
+    let mut resolved: Vec<ResolvedKeyframe> = keyframes
+        .into_iter()
+        .zip(offsets)
+        .map(|(keyframe, offset)| ResolvedKeyframe {
+            offset: offset.unwrap_or(0.0),
+            style: keyframe.style,
+        })
+        .collect();
+    resolved.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    resolved
+}
+
+/// A keyframe animation or transition running on one widget.
+#[derive(Debug, Clone)]
+struct Animation {
+    keyframes: Vec<ResolvedKeyframe>,
+    duration_ms: f64,
+    easing: Easing,
+    iterations: f64,
+    direction: AnimationDirection,
+    /// The first keyframe is held until the delay has elapsed.
+    delay_ms: f64,
+    elapsed_ms: f64,
+}
+
+impl Animation {
+    /// Animated style at the current time, and whether the animation is over.
+    fn current_frame(&self) -> (BoxStyle, bool) {
+        let elapsed_ms = (self.elapsed_ms - self.delay_ms).max(0.0);
+        let total_ms = self.duration_ms * self.iterations;
+        let finished = elapsed_ms >= total_ms;
+        if self.duration_ms <= 0.0 {
+            return (sample_keyframes(&self.keyframes, 1.0), true);
+        }
+
+        let progress = elapsed_ms.min(total_ms) / self.duration_ms;
+        let mut iteration = progress.floor();
+        let mut local = progress - iteration;
+        // Ending exactly on an iteration boundary shows that iteration's last frame
+        if finished && local == 0.0 && iteration > 0.0 {
+            iteration -= 1.0;
+            local = 1.0;
+        }
+        if self.direction.is_reversed(iteration as u64) {
+            local = 1.0 - local;
+        }
+
+        let t = self.easing.sample(local);
+        (sample_keyframes(&self.keyframes, t), finished)
+    }
+}
+
+/// Result of advancing all running animations by one frame.
+#[derive(Debug, Default)]
+pub struct AnimationStep {
+    /// Animated overlay of every widget still animating, keyed by JS widget id.
+    pub frames: Vec<(String, BoxStyle)>,
+    /// Widgets whose explicit animation completed during this step.
+    pub finished: Vec<String>,
+    /// Widgets with nothing left animating; their overlay should be cleared.
+    pub settled: Vec<String>,
+}
+
+/// Keyframe animations (one per widget) and style transitions (one per
+/// widget property) running on the UI thread.
+pub struct Animator {
+    animations: HashMap<String, Animation>,
+    transitions: HashMap<String, HashMap<&'static str, Animation>>,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Self {
+            animations: HashMap::new(),
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Whether anything is running, i.e. animation frames are needed.
+    pub fn is_active(&self) -> bool {
+        !self.animations.is_empty() || !self.transitions.is_empty()
+    }
+
+    /// Start (or restart) an animation on a widget. Keyframes must already be
+    /// resolved (no variables or palette names). `base` is the widget's style
+    /// without animation, used for properties missing from the first or last keyframe.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        &mut self,
+        id: String,
+        keyframes: Vec<Keyframe>,
+        base: &BoxStyle,
+        duration_ms: f64,
+        easing: Easing,
+        iterations: f64,
+        direction: AnimationDirection,
+    ) {
+        let mut keyframes = resolve_offsets(keyframes);
+        let base = &with_identity_transform(base);
+        let from = implicit_keyframe(&keyframes, base, 0.0);
+        let to = implicit_keyframe(&keyframes, base, 1.0);
+        keyframes.insert(
+            0,
+            ResolvedKeyframe {
+                offset: 0.0,
+                style: from,
+            },
+        );
+        keyframes.push(ResolvedKeyframe {
+            offset: 1.0,
+            style: to,
+        });
+
+        self.animations.insert(
+            id,
+            Animation {
+                keyframes,
+                duration_ms: duration_ms.max(0.0),
+                easing,
+                iterations: if iterations.is_nan() {
+                    1.0
+                } else {
+                    iterations.max(0.0)
+                },
+                direction,
+                delay_ms: 0.0,
+                elapsed_ms: 0.0,
+            },
+        );
+    }
+
+    /// Start a transition for every property covered by `spec` whose value
+    /// differs between `before` (currently displayed) and `target`. A property
+    /// already transitioning restarts from its current value. Returns whether
+    /// any transition was started.
+    pub fn start_transitions(
+        &mut self,
+        id: &str,
+        before: &BoxStyle,
+        target: &BoxStyle,
+        spec: &TransitionSpec,
+    ) -> bool {
+        if spec.duration <= 0.0 {
+            return false;
+        }
+        let with_transforms;
+        let (before, target) = if before.transform.is_some() || target.transform.is_some() {
+            with_transforms = (
+                with_identity_transform(before),
+                with_identity_transform(target),
+            );
+            (&with_transforms.0, &with_transforms.1)
+        } else {
+            (before, target)
+        };
+        let easing = spec.easing();
+        let mut started = false;
+        macro_rules! transition {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if spec.covers(stringify!($field))
+                        && let (Some(from), Some(to)) = (&before.$field, &target.$field)
+                        && from != to
+                    {
+                        let from_style = BoxStyle {
+                            $field: Some(from.clone()),
+                            ..Default::default()
+                        };
+                        let to_style = BoxStyle {
+                            $field: Some(to.clone()),
+                            ..Default::default()
+                        };
+                        self.transitions.entry(id.to_string()).or_default().insert(
+                            stringify!($field),
+                            Animation {
+                                keyframes: vec![
+                                    ResolvedKeyframe { offset: 0.0, style: from_style },
+                                    ResolvedKeyframe { offset: 1.0, style: to_style },
+                                ],
+                                duration_ms: spec.duration,
+                                easing,
+                                iterations: 1.0,
+                                direction: AnimationDirection::Normal,
+                                delay_ms: spec.delay.max(0.0),
+                                elapsed_ms: 0.0,
+                            },
+                        );
+                        started = true;
+                    }
+                )*
+            };
+        }
+        with_animatable_fields!(transition);
+        started
+    }
+
+    /// Stop the explicit animation on a widget. Returns false if none was running.
+    pub fn cancel(&mut self, id: &str) -> bool {
+        self.animations.remove(id).is_some()
+    }
+
+    /// Drop everything running on a removed widget.
+    pub fn forget(&mut self, id: &str) {
+        self.animations.remove(id);
+        self.transitions.remove(id);
+    }
+
+    /// Current overlay of a widget without advancing time: transitions first,
+    /// then the explicit animation, which wins for properties both animate.
+    pub fn overlay(&self, id: &str) -> Option<BoxStyle> {
+        let transitions = self.transitions.get(id);
+        let animation = self.animations.get(id);
+        if transitions.is_none() && animation.is_none() {
+            return None;
+        }
+
+        let mut style = BoxStyle::default();
+        for transition in transitions.into_iter().flat_map(|t| t.values()) {
+            style.merge_from(&transition.current_frame().0);
+        }
+        if let Some(animation) = animation {
+            style.merge_from(&animation.current_frame().0);
+        }
+        Some(style)
+    }
+
+    /// Advance everything by `delta_ms` and sample the resulting overlays.
+    pub fn advance(&mut self, delta_ms: f64) -> AnimationStep {
+        let mut step = AnimationStep::default();
+        let mut touched: Vec<String> = Vec::new();
+
+        for (id, animation) in &mut self.animations {
+            animation.elapsed_ms += delta_ms;
+            if animation.current_frame().1 {
+                step.finished.push(id.clone());
+            }
+            touched.push(id.clone());
+        }
+        for id in &step.finished {
+            self.animations.remove(id);
+        }
+
+        for (id, transitions) in &mut self.transitions {
+            for transition in transitions.values_mut() {
+                transition.elapsed_ms += delta_ms;
+            }
+            transitions.retain(|_, transition| !transition.current_frame().1);
+            if !touched.contains(id) {
+                touched.push(id.clone());
+            }
+        }
+        self.transitions
+            .retain(|_, transitions| !transitions.is_empty());
+
+        for id in touched {
+            match self.overlay(&id) {
+                Some(style) => step.frames.push((id, style)),
+                None => step.settled.push(id),
+            }
+        }
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(offset: Option<f64>, style: BoxStyle) -> Keyframe {
+        Keyframe { offset, style }
+    }
+
+    #[test]
+    fn test_resolve_offsets_spreads_missing() {
+        let frames = resolve_offsets(vec![
+            keyframe(None, BoxStyle::default()),
+            keyframe(None, BoxStyle::default()),
+            keyframe(None, BoxStyle::default()),
+            keyframe(Some(0.9), BoxStyle::default()),
+            keyframe(None, BoxStyle::default()),
+        ]);
+        let expected = [0.0, 0.3, 0.6, 0.9, 1.0];
+        assert_eq!(frames.len(), expected.len());
+        for (frame, offset) in frames.iter().zip(expected) {
+            assert!((frame.offset - offset).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_interpolates_numbers_and_colors() {
+        let mut animator = Animator::new();
+        animator.start(
+            "box".to_string(),
+            vec![
+                keyframe(
+                    None,
+                    BoxStyle {
+                        width: Some(0.0),
+                        background: ColorValue::parse("#000000"),
+                        ..Default::default()
+                    },
+                ),
+                keyframe(
+                    None,
+                    BoxStyle {
+                        width: Some(100.0),
+                        background: ColorValue::parse("#ff0000"),
+                        ..Default::default()
+                    },
+                ),
+            ],
+            &BoxStyle::default(),
+            1000.0,
+            Easing::Linear,
+            1.0,
+            AnimationDirection::Normal,
+        );
+
+        let step = animator.advance(250.0);
+        let (_, style) = &step.frames[0];
+        assert_eq!(style.width, Some(25.0));
+        assert!(matches!(
+            style.background,
+            Some(ColorValue::Rgba { r: 64, g: 0, .. })
+        ));
+
+        let step = animator.advance(750.0);
+        assert_eq!(step.finished, vec!["box".to_string()]);
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_missing_end_frame_uses_base_style() {
+        let mut animator = Animator::new();
+        animator.start(
+            "fade".to_string(),
+            vec![keyframe(
+                Some(0.0),
+                BoxStyle {
+                    opacity: Some(0.0),
+                    ..Default::default()
+                },
+            )],
+            &BoxStyle {
+                opacity: Some(1.0),
+                ..Default::default()
+            },
+            100.0,
+            Easing::Linear,
+            f64::INFINITY,
+            AnimationDirection::Alternate,
+        );
+
+        let step = animator.advance(50.0);
+        assert_eq!(step.frames[0].1.opacity, Some(0.5));
+        // Second iteration plays backwards
+        let step = animator.advance(75.0);
+        assert_eq!(step.frames[0].1.opacity, Some(0.75));
+        assert!(animator.cancel("fade"));
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_transitions_tween_changed_properties() {
+        let mut animator = Animator::new();
+        let spec = TransitionSpec {
+            properties: vec!["cornerRadius".to_string()],
+            duration: 100.0,
+            easing: Some("linear".to_string()),
+            delay: 50.0,
+        };
+        let before = BoxStyle {
+            corner_radius: Some(0.0),
+            width: Some(10.0),
+            ..Default::default()
+        };
+        let target = BoxStyle {
+            corner_radius: Some(20.0),
+            width: Some(30.0),
+            ..Default::default()
+        };
+        assert!(animator.start_transitions("card", &before, &target, &spec));
+
+        // The start value is held during the delay; width is not listed
+        let overlay = animator.overlay("card").unwrap();
+        assert_eq!(overlay.corner_radius, Some(0.0));
+        assert!(overlay.width.is_none());
+
+        let step = animator.advance(100.0);
+        assert_eq!(step.frames[0].1.corner_radius, Some(10.0));
+
+        let step = animator.advance(50.0);
+        assert!(step.frames.is_empty());
+        assert_eq!(step.settled, vec!["card".to_string()]);
+        assert!(step.finished.is_empty());
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_transform_transitions_from_identity() {
+        let mut animator = Animator::new();
+        let spec = TransitionSpec {
+            duration: 100.0,
+            easing: Some("linear".to_string()),
+            ..Default::default()
+        };
+        let target = BoxStyle {
+            transform: TransformValue::parse("scale(0.9) rotate(180deg)"),
+            ..Default::default()
+        };
+        assert!(animator.start_transitions("chevron", &BoxStyle::default(), &target, &spec));
+
+        let step = animator.advance(50.0);
+        let transform = step.frames[0].1.transform.unwrap();
+        assert!((transform.scale_x - 0.95).abs() < 1e-9);
+        assert!((transform.rotate - 90.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,100 @@
+use masonry::core::DefaultProperties;
+use masonry_winit::app::{EventLoop, MasonryState, MasonryUserEvent, NewWindow};
+use masonry_winit::winit::application::ApplicationHandler;
+use masonry_winit::winit::error::EventLoopError;
+use masonry_winit::winit::event::{DeviceEvent, DeviceId, StartCause, WindowEvent};
+use masonry_winit::winit::event_loop::ActiveEventLoop;
+use masonry_winit::winit::window::WindowId;
+
+use super::driver::VellumDriver;
+use super::file_drop::FileDropRouter;
+use super::global_state::get_event_loop_proxy;
+
+/// Event loop handler: masonry's own, plus the file drag events masonry
+/// ignores, which are posted to the driver as [`FileDrag`] actions.
+///
+/// [`FileDrag`]: super::file_drop::FileDrag
+struct VellumApp<'a> {
+    masonry: MasonryState<'a>,
+    driver: VellumDriver,
+    file_drops: FileDropRouter,
+}
+
+/// Run the event loop until the app exits. Equivalent to
+/// `masonry_winit::app::run_with`, with file drag-and-drop.
+pub fn run(
+    event_loop: EventLoop,
+    windows: Vec<NewWindow>,
+    driver: VellumDriver,
+    default_properties: DefaultProperties,
+) -> Result<(), EventLoopError> {
+    let mut app = VellumApp {
+        masonry: MasonryState::new(event_loop.create_proxy(), windows, default_properties),
+        driver,
+        file_drops: FileDropRouter::default(),
+    };
+    event_loop.run_app(&mut app)
+}
+
+impl ApplicationHandler<MasonryUserEvent> for VellumApp<'_> {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        self.masonry.handle_new_events(event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.masonry.handle_resumed(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: MasonryUserEvent) {
+        self.masonry
+            .handle_user_event(event_loop, event, &mut self.driver);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if self.file_drops.window_event(&event) {
+            return;
+        }
+        self.masonry
+            .handle_window_event(event_loop, window_id, event, &mut self.driver);
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        self.masonry
+            .handle_device_event(event_loop, device_id, event, &mut self.driver);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // All files of a drag arrive in one batch, so they are complete here
+        let drags = self.file_drops.take();
+        if !drags.is_empty()
+            && let Some((proxy, window_id)) = get_event_loop_proxy()
+        {
+            for drag in drags {
+                let _ = proxy.send_event(MasonryUserEvent::AsyncAction(window_id, Box::new(drag)));
+            }
+        }
+        self.masonry.handle_about_to_wait(event_loop);
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.masonry.handle_suspended(event_loop);
+    }
+
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        self.masonry.handle_exiting(event_loop);
+    }
+
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        self.masonry.handle_memory_warning(event_loop);
+    }
+}
@@ -1,9 +1,12 @@
 use masonry::app::RenderRoot;
 
+use super::handler::{update_cursor, update_transform};
 use super::widget_manager::WidgetManager;
 use super::widgets;
-use crate::ipc::{BoxStyle, WidgetData, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetData, WidgetKind};
 
+/// Create a widget and insert it under its parent. On failure nothing is
+/// registered and the widget's style entries are dropped again.
 #[allow(clippy::too_many_arguments)]
 pub fn create_and_add_widget(
     render_root: &mut RenderRoot,
@@ -14,8 +17,9 @@ pub fn create_and_add_widget(
     text: Option<String>,
     style: Option<BoxStyle>,
     data: Option<WidgetData>,
-) {
-    println!(
+    classes: Vec<String>,
+) -> Result<(), VellumError> {
+    eprintln!(
         "[UI] Creating widget: id={}, kind={:?}, parent={:?}",
         id, kind, parent_id
     );
@@ -23,213 +27,210 @@ pub fn create_and_add_widget(
     let parent_key = parent_id.as_deref().unwrap_or("__root__").to_string();
     let child_index = widget_manager.next_child_index(&parent_key);
 
-    match kind {
-        WidgetKind::Label => {
-            widgets::label::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Button => {
-            widgets::button::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Svg => {
-            widgets::svg::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::Flex | WidgetKind::Container => {
-            widgets::flex::create(
-                render_root,
-                widget_manager,
-                id,
-                kind,
-                parent_id,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::SizedBox => {
-            widgets::sized_box::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Checkbox => {
-            widgets::checkbox::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::TextInput => {
-            widgets::text_input::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::TextArea => {
-            widgets::text_area::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Prose => {
-            widgets::prose::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                text,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::ProgressBar => {
-            widgets::progress_bar::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::Spinner => {
-            widgets::spinner::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Slider => {
-            widgets::slider::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::ZStack => {
-            widgets::zstack::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Portal => {
-            widgets::portal::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Grid => {
-            widgets::grid::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Hoverable => {
-            widgets::hoverable_create::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Custom(_) => {
-            widgets::custom::create(
-                render_root,
-                widget_manager,
-                id,
-                kind,
-                parent_id,
-                text,
-                style,
-                child_index,
-            );
-        }
-        WidgetKind::Image => {
-            widgets::image::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                data,
-                child_index,
-            );
-        }
-        WidgetKind::Video => {
-            widgets::video::create(
-                render_root,
-                widget_manager,
-                id,
-                parent_id,
-                style,
-                data,
-                child_index,
-            );
-        }
+    // Widgets are built from their effective style (theme, inherited text, classes, inline style).
+    widget_manager.styles.set_classes(&id, classes);
+    if let Some(ref inline) = style {
+        widget_manager.styles.merge_inline(&id, inline);
     }
+    let inherited = widget_manager.inherited_style(parent_id.as_deref());
+    let style = Some(
+        widget_manager
+            .styles
+            .computed_style(&id, &kind, &inherited)
+            .with_opacity_applied(),
+    );
+    let created_id = id.clone();
+    // Transforms are set on the inserted widget, once it has a masonry id;
+    // cursors are tracked once the widget exists
+    let tracked_style = style.as_ref().map(|style| BoxStyle {
+        transform: style.transform,
+        transform_origin: style.transform_origin,
+        cursor: style.cursor,
+        ..Default::default()
+    });
+
+    let created = match kind {
+        WidgetKind::Label => widgets::label::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            child_index,
+        ),
+        WidgetKind::Button => widgets::button::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Svg => widgets::svg::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::Flex | WidgetKind::Container => widgets::flex::create(
+            render_root,
+            widget_manager,
+            id,
+            kind,
+            parent_id,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::SizedBox => widgets::sized_box::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Checkbox => widgets::checkbox::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::TextInput => widgets::text_input::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::TextArea => widgets::text_area::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            child_index,
+        ),
+        WidgetKind::Prose => widgets::prose::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            text,
+            style,
+            child_index,
+        ),
+        WidgetKind::ProgressBar => widgets::progress_bar::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::Spinner => widgets::spinner::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Slider => widgets::slider::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::ZStack => widgets::zstack::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Portal => widgets::portal::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Grid => widgets::grid::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Hoverable => widgets::hoverable_create::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            child_index,
+        ),
+        WidgetKind::Custom(_) => widgets::custom::create(
+            render_root,
+            widget_manager,
+            id,
+            kind,
+            parent_id,
+            text,
+            style,
+            child_index,
+        ),
+        WidgetKind::Image => widgets::image::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            data,
+            child_index,
+        ),
+        WidgetKind::Video => widgets::video::create(
+            render_root,
+            widget_manager,
+            id,
+            parent_id,
+            style,
+            data,
+            child_index,
+        ),
+    };
+
+    if let Err(error) = created {
+        widget_manager.styles.forget(&created_id);
+        return Err(error);
+    }
+    if let Some(tracked_style) = tracked_style
+        && let Some(info) = widget_manager.widgets.get(&created_id)
+    {
+        let widget_id = info.widget_id;
+        update_transform(render_root, widget_manager, widget_id, &tracked_style);
+        update_cursor(render_root, widget_manager, &created_id, &tracked_style);
+    }
+    Ok(())
 }
@@ -0,0 +1,83 @@
+use masonry::app::RenderRoot;
+use masonry::kurbo::Point;
+use masonry_winit::winit::window::{CursorIcon, Window};
+
+use super::geometry::hit_test;
+use super::widget_manager::WidgetManager;
+use crate::ipc::CursorStyle;
+
+/// What the window should show: a cursor over a JS widget, or masonry's own.
+pub type CursorTarget = Option<(CursorStyle, Option<String>)>;
+
+/// Shows `cursor` styles and the `SetCursor` override.
+///
+/// Masonry takes the cursor from the innermost hovered widget only, so a
+/// styled container can't show its cursor over its children. Instead the
+/// driver resolves the cursor on every [`PointerMoved`] and sets it on the
+/// winit window, after masonry set its own.
+///
+/// [`PointerMoved`]: super::widgets::root_host::PointerMoved
+#[derive(Debug, Default)]
+pub struct CursorController {
+    /// Last reported pointer position; None outside the window
+    position: Option<Point>,
+    /// Cursor last set on the window, with the JS widget it was set over
+    shown: CursorTarget,
+}
+
+impl CursorController {
+    pub fn pointer_moved(&mut self, position: Option<Point>) {
+        self.position = position;
+    }
+
+    /// The cursor to show at the last pointer position.
+    pub fn resolve(
+        &self,
+        render_root: &RenderRoot,
+        widget_manager: &WidgetManager,
+    ) -> CursorTarget {
+        let hit = match (self.position, widget_manager.cursors.is_empty()) {
+            (Some(position), false) => hit_test(render_root, widget_manager, position),
+            _ => None,
+        };
+        let cursor = widget_manager
+            .cursor_override
+            .or_else(|| widget_manager.cursor_at(hit.as_deref()?))?;
+        Some((cursor, hit))
+    }
+
+    /// Set `target` on the window. Masonry only changes the cursor when the
+    /// hovered widget changes, so the same cursor over the same widget is not
+    /// set again.
+    pub fn show(&mut self, window: &Window, target: CursorTarget) {
+        if self.shown == target {
+            return;
+        }
+        match &target {
+            Some((cursor, _)) => window.set_cursor(cursor_icon(*cursor)),
+            // Masonry sets its own again once the hovered widget changes
+            None => window.set_cursor(CursorIcon::Default),
+        }
+        self.shown = target;
+    }
+}
+
+fn cursor_icon(cursor: CursorStyle) -> CursorIcon {
+    match cursor {
+        CursorStyle::Default => CursorIcon::Default,
+        CursorStyle::Pointer => CursorIcon::Pointer,
+        CursorStyle::Text => CursorIcon::Text,
+        CursorStyle::Grab => CursorIcon::Grab,
+        CursorStyle::Grabbing => CursorIcon::Grabbing,
+        CursorStyle::Move => CursorIcon::Move,
+        CursorStyle::EwResize => CursorIcon::EwResize,
+        CursorStyle::NsResize => CursorIcon::NsResize,
+        CursorStyle::NeswResize => CursorIcon::NeswResize,
+        CursorStyle::NwseResize => CursorIcon::NwseResize,
+        CursorStyle::ColResize => CursorIcon::ColResize,
+        CursorStyle::RowResize => CursorIcon::RowResize,
+        CursorStyle::NotAllowed => CursorIcon::NotAllowed,
+        CursorStyle::Crosshair => CursorIcon::Crosshair,
+        CursorStyle::Wait => CursorIcon::Wait,
+    }
+}
@@ -2,13 +2,26 @@ use masonry::core::{ErasedAction, WidgetId};
 use masonry::widgets::{ButtonPress, Checkbox, CheckboxToggled};
 use masonry_winit::app::{AppDriver, DriverCtx, WindowId};
 
-use crate::ipc::{ClientCommandAction, UiEvent, UiEventSender, WidgetActionKind};
+use crate::ipc::{
+    ClientCommand, ClientCommandAction, ClientRequestAction, CommandResult, ErrorReport, UiEvent,
+    UiEventSender, VellumError, WidgetActionKind,
+};
 
+use super::cursor::CursorController;
+use super::file_drop::FileDrag;
+use super::geometry::hit_test;
 use super::global_state::set_global_wgpu;
-use super::handler::handle_client_command;
+use super::handler::{
+    advance_animations, handle_client_command, refresh_transforms, report_intersections,
+    report_resized_widgets,
+};
 use super::widget_manager::{WidgetInfo, WidgetManager};
 use super::widgets::hoverable::HoverAction;
+use super::widgets::root_host::{
+    AnimationTick, LayoutPass, PointerMoved, RootHost, ViewportChange,
+};
 use super::widgets::video_widget_impl::{VideoAction, VideoWidget};
+use super::window::WindowController;
 use masonry_winit::app::WgpuContext;
 
 /// Application driver that bridges JS runtime commands with the masonry UI.
@@ -20,6 +33,10 @@ pub struct VellumDriver {
     pub event_sender: UiEventSender,
     /// Manages JS widget ID → masonry WidgetId mapping
     pub widget_manager: WidgetManager,
+    /// Runs window control commands and reports window state changes
+    pub window: WindowController,
+    /// Shows widget cursors and the `SetCursor` override
+    pub cursor: CursorController,
 }
 
 impl VellumDriver {
@@ -27,9 +44,50 @@ impl VellumDriver {
         Self {
             event_sender,
             widget_manager: WidgetManager::new(),
+            window: WindowController::default(),
+            cursor: CursorController::default(),
         }
     }
 
+    /// Run a client command: window controls against the winit window, the
+    /// rest against the widget tree.
+    fn run_command(
+        &mut self,
+        window_id: WindowId,
+        ctx: &mut DriverCtx<'_, '_>,
+        cmd: ClientCommand,
+    ) -> CommandResult {
+        let window = ctx.window_handle(window_id);
+        if let Some(outcome) = self.window.handle(window, &cmd) {
+            self.window.report_changes(window, &self.event_sender);
+            return outcome;
+        }
+        if matches!(cmd, ClientCommand::ResetUi) {
+            // The next client hears the current state on the next layout pass
+            self.window.forget_reported();
+        }
+        // Changed `cursor` styles show once the pointer moves
+        let cursor_changed = matches!(cmd, ClientCommand::SetCursor(_) | ClientCommand::ResetUi);
+        let outcome = handle_client_command(
+            cmd,
+            window_id,
+            ctx.render_root(window_id),
+            &mut self.widget_manager,
+            &self.event_sender,
+        );
+        if cursor_changed {
+            self.update_cursor(window_id, ctx);
+        }
+        outcome
+    }
+
+    fn update_cursor(&mut self, window_id: WindowId, ctx: &mut DriverCtx<'_, '_>) {
+        let target = self
+            .cursor
+            .resolve(ctx.render_root(window_id), &self.widget_manager);
+        self.cursor.show(ctx.window_handle(window_id), target);
+    }
+
     /// Look up JS widget ID by masonry WidgetId
     fn find_client_id(&self, widget_id: WidgetId) -> Option<String> {
         self.widget_manager
@@ -39,10 +97,10 @@ impl VellumDriver {
             .map(|(id, _): (&String, &WidgetInfo)| id.clone())
     }
 
-    fn report_runtime_error(&self, source: &str, message: String, fatal: bool) {
+    fn report_runtime_error(&self, source: &str, error: ErrorReport, fatal: bool) {
         if let Err(send_err) = self.event_sender.send(UiEvent::RuntimeError {
             source: source.to_string(),
-            message,
+            error,
             fatal,
         }) {
             eprintln!("[UI] Failed to report runtime error to JS thread: {send_err}");
@@ -79,6 +137,24 @@ impl AppDriver for VellumDriver {
             return;
         }
 
+        if let Some(tick) = action.downcast_ref::<AnimationTick>() {
+            // Submitted by the RootHost on each animation frame while animations run
+            let render_root = ctx.render_root(window_id);
+            let active = advance_animations(
+                render_root,
+                &mut self.widget_manager,
+                &self.event_sender,
+                tick.interval_ns,
+            );
+            if !active {
+                render_root.edit_widget(widget_id, |mut w| {
+                    let mut host = w.downcast::<RootHost>();
+                    RootHost::set_animating(&mut host, false);
+                });
+            }
+            return;
+        }
+
         if let Some(hover_action) = action.downcast_ref::<HoverAction>() {
             // The action is submitted by the Hoverable widget itself,
             // so widget_id is the Hoverable's masonry WidgetId.
@@ -120,13 +196,18 @@ impl AppDriver for VellumDriver {
         }
 
         // Unknown action
-        println!(
+        eprintln!(
             "[UI] Unhandled widget action on {:?}: {}",
             widget_id, type_name
         );
         self.report_runtime_error(
             "ui-driver",
-            format!("Unhandled widget action on {widget_id:?}: {type_name}"),
+            ErrorReport::new(
+                VellumError::internal(format!(
+                    "Unhandled widget action on {widget_id:?}: {type_name}"
+                )),
+                None,
+            ),
             false,
         );
     }
@@ -140,11 +221,68 @@ impl AppDriver for VellumDriver {
         // Check if this action is a ClientCommandAction sent via EventLoopProxy
         if let Some(client_action) = action.downcast_ref::<ClientCommandAction>() {
             let cmd = client_action.0.clone();
+            let command = cmd.name();
+            if let Err(error) = self.run_command(window_id, ctx, cmd) {
+                self.report_runtime_error(
+                    "ui-handler",
+                    ErrorReport::new(error, Some(command)),
+                    false,
+                );
+            }
+            return;
+        }
+
+        // Requests are answered with their outcome instead of a runtime error
+        if let Some(request) = action.downcast_ref::<ClientRequestAction>() {
+            let command = request.command.name();
+            let outcome = self.run_command(window_id, ctx, request.command.clone());
+            let (result, error) = match outcome {
+                Ok(result) => (result, None),
+                Err(error) => (None, Some(ErrorReport::new(error, Some(command)))),
+            };
+            if let Err(send_err) = self.event_sender.send(UiEvent::CommandResponse {
+                request_id: request.request_id,
+                result,
+                error,
+            }) {
+                eprintln!("[UI] Failed to send command response to JS thread: {send_err}");
+            }
+            return;
+        }
+
+        if action.is::<LayoutPass>() {
+            // Widget sizes may have changed, which moves size-relative transform origins
             let render_root = ctx.render_root(window_id);
-            handle_client_command(
-                cmd,
-                window_id,
-                render_root,
+            refresh_transforms(render_root, &self.widget_manager);
+            report_resized_widgets(render_root, &mut self.widget_manager, &self.event_sender);
+            report_intersections(render_root, &mut self.widget_manager, &self.event_sender);
+            // Resizes, maximizing and fullscreen changes by the user end up here
+            self.window
+                .report_changes(ctx.window_handle(window_id), &self.event_sender);
+            return;
+        }
+
+        if let Some(PointerMoved(position)) = action.downcast_ref::<PointerMoved>() {
+            self.cursor.pointer_moved(*position);
+            self.update_cursor(window_id, ctx);
+            return;
+        }
+
+        if let Some(drag) = action.downcast_ref::<FileDrag>() {
+            let scale_factor = ctx.window_handle(window_id).scale_factor();
+            let render_root = ctx.render_root(window_id);
+            let event = drag.clone().into_event(scale_factor, |point| {
+                hit_test(render_root, &self.widget_manager, point)
+            });
+            if let Err(send_err) = self.event_sender.send(event) {
+                eprintln!("[UI] Failed to forward file drag to JS thread: {send_err}");
+            }
+            return;
+        }
+
+        if action.is::<ViewportChange>() {
+            report_intersections(
+                ctx.render_root(window_id),
                 &mut self.widget_manager,
                 &self.event_sender,
             );
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+
+use masonry::dpi::PhysicalPosition;
+use masonry::kurbo::Point;
+use masonry_winit::winit::event::WindowEvent;
+
+use crate::ipc::UiEvent;
+
+/// A file drag from the OS, posted to the driver after each event batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileDrag {
+    /// Files were dragged into the window
+    Enter {
+        paths: Vec<PathBuf>,
+        position: Option<PhysicalPosition<f64>>,
+    },
+    /// The drag left the window or was cancelled
+    Leave,
+    /// Files were dropped on the window
+    Drop {
+        paths: Vec<PathBuf>,
+        position: Option<PhysicalPosition<f64>>,
+    },
+}
+
+/// Paths, window position (logical pixels) and target of an enter or drop.
+type Located = (Vec<String>, Option<f64>, Option<f64>, Option<String>);
+
+impl FileDrag {
+    /// The event sent to the client. `hit_test` finds the JS widget at a
+    /// window point given in logical pixels.
+    pub fn into_event(
+        self,
+        scale_factor: f64,
+        hit_test: impl FnOnce(Point) -> Option<String>,
+    ) -> UiEvent {
+        let locate = |paths: Vec<PathBuf>, position: Option<PhysicalPosition<f64>>| -> Located {
+            let point = position.map(|position| {
+                let position = position.to_logical::<f64>(scale_factor);
+                Point::new(position.x, position.y)
+            });
+            let paths = paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            (
+                paths,
+                point.map(|point| point.x),
+                point.map(|point| point.y),
+                point.and_then(hit_test),
+            )
+        };
+        match self {
+            FileDrag::Enter { paths, position } => {
+                let (paths, x, y, target_widget_id) = locate(paths, position);
+                UiEvent::FileHoverEnter {
+                    paths,
+                    x,
+                    y,
+                    target_widget_id,
+                }
+            }
+            FileDrag::Leave => UiEvent::FileHoverLeave {},
+            FileDrag::Drop { paths, position } => {
+                let (paths, x, y, target_widget_id) = locate(paths, position);
+                UiEvent::FileDropped {
+                    paths,
+                    x,
+                    y,
+                    target_widget_id,
+                }
+            }
+        }
+    }
+}
+
+/// Collects the file drag events masonry ignores.
+///
+/// winit reports every file of a drag as its own event, all within one event
+/// batch, and doesn't say where the pointer is. The router groups the files
+/// into one [`FileDrag`] and takes the position from the last `CursorMoved`.
+/// Most platforms send none while an OS drag is in progress, and a drag
+/// enters from outside the window (after `CursorLeft`), so the position is
+/// usually unknown and the client gets no target.
+#[derive(Debug, Default)]
+pub struct FileDropRouter {
+    /// Last pointer position in the window (physical pixels)
+    position: Option<PhysicalPosition<f64>>,
+    /// Drags seen since the last `take`, in order
+    pending: Vec<FileDrag>,
+}
+
+impl FileDropRouter {
+    /// Note a window event. Returns true if it was a file drag event.
+    pub fn window_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => self.position = Some(*position),
+            WindowEvent::CursorLeft { .. } => self.position = None,
+            WindowEvent::HoveredFile(path) => {
+                if let Some(FileDrag::Enter { paths, .. }) = self.pending.last_mut() {
+                    paths.push(path.clone());
+                } else {
+                    self.pending.push(FileDrag::Enter {
+                        paths: vec![path.clone()],
+                        position: self.position,
+                    });
+                }
+                return true;
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.pending.push(FileDrag::Leave);
+                return true;
+            }
+            WindowEvent::DroppedFile(path) => {
+                if let Some(FileDrag::Drop { paths, .. }) = self.pending.last_mut() {
+                    paths.push(path.clone());
+                } else {
+                    self.pending.push(FileDrag::Drop {
+                        paths: vec![path.clone()],
+                        position: self.position,
+                    });
+                }
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Drags completed by the current event batch.
+    pub fn take(&mut self) -> Vec<FileDrag> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masonry_winit::winit::event::DeviceId;
+
+    fn cursor_moved(x: f64, y: f64) -> WindowEvent {
+        WindowEvent::CursorMoved {
+            // SAFETY: only compared against other ids, never passed to the platform
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(x, y),
+        }
+    }
+
+    #[test]
+    fn test_router_groups_files_of_one_drag() {
+        let mut router = FileDropRouter::default();
+        assert!(!router.window_event(&cursor_moved(40.0, 20.0)));
+        for path in ["a.png", "b.png"] {
+            assert!(router.window_event(&WindowEvent::HoveredFile(PathBuf::from(path))));
+        }
+        assert!(router.window_event(&WindowEvent::HoveredFileCancelled));
+        assert_eq!(
+            router.take(),
+            [
+                FileDrag::Enter {
+                    paths: vec![PathBuf::from("a.png"), PathBuf::from("b.png")],
+                    position: Some(PhysicalPosition::new(40.0, 20.0)),
+                },
+                FileDrag::Leave,
+            ]
+        );
+        assert!(router.take().is_empty());
+
+        router.window_event(&WindowEvent::HoveredFile(PathBuf::from("a.png")));
+        router.window_event(&WindowEvent::DroppedFile(PathBuf::from("a.png")));
+        router.window_event(&WindowEvent::DroppedFile(PathBuf::from("c.txt")));
+        let drags = router.take();
+        assert_eq!(drags.len(), 2);
+        assert_eq!(
+            drags[1],
+            FileDrag::Drop {
+                paths: vec![PathBuf::from("a.png"), PathBuf::from("c.txt")],
+                position: Some(PhysicalPosition::new(40.0, 20.0)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_drag_without_pointer_moves_has_no_position() {
+        let mut router = FileDropRouter::default();
+        // The pointer left before the drag came back in
+        router.window_event(&cursor_moved(40.0, 20.0));
+        router.window_event(&WindowEvent::CursorLeft {
+            // SAFETY: only compared against other ids, never passed to the platform
+            device_id: unsafe { DeviceId::dummy() },
+        });
+        router.window_event(&WindowEvent::HoveredFile(PathBuf::from("a.png")));
+        router.window_event(&WindowEvent::DroppedFile(PathBuf::from("a.png")));
+        let drags = router.take();
+        assert_eq!(
+            drags,
+            [
+                FileDrag::Enter {
+                    paths: vec![PathBuf::from("a.png")],
+                    position: None,
+                },
+                FileDrag::Drop {
+                    paths: vec![PathBuf::from("a.png")],
+                    position: None,
+                },
+            ]
+        );
+
+        // Which reaches the client as a drop without target
+        let event = drags[1]
+            .clone()
+            .into_event(1.0, |_| panic!("no position to test"));
+        assert!(matches!(
+            event,
+            UiEvent::FileDropped {
+                x: None,
+                y: None,
+                target_widget_id: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_drop_event_targets_widget_under_logical_position() {
+        let drop = FileDrag::Drop {
+            paths: vec![PathBuf::from("/tmp/report.pdf")],
+            position: Some(PhysicalPosition::new(200.0, 100.0)),
+        };
+        let event = drop.into_event(2.0, |point| {
+            (point == Point::new(100.0, 50.0)).then(|| "dropzone".to_string())
+        });
+        let UiEvent::FileDropped {
+            paths,
+            x,
+            y,
+            target_widget_id,
+        } = event
+        else {
+            panic!("expected FileDropped");
+        };
+        assert_eq!(paths, ["/tmp/report.pdf"]);
+        assert_eq!((x, y), (Some(100.0), Some(50.0)));
+        assert_eq!(target_widget_id.as_deref(), Some("dropzone"));
+
+        // Without a pointer position there is nothing to hit-test
+        let event = FileDrag::Enter {
+            paths: vec![PathBuf::from("a.png")],
+            position: None,
+        }
+        .into_event(1.0, |_| panic!("no position to test"));
+        assert!(matches!(
+            event,
+            UiEvent::FileHoverEnter {
+                x: None,
+                target_widget_id: None,
+                ..
+            }
+        ));
+    }
+}
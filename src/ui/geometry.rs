@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use masonry::app::RenderRoot;
+use masonry::core::{Widget, WidgetId, WidgetRef};
+use masonry::kurbo::{Point, Size};
+use serde::Serialize;
+
+use super::widget_manager::WidgetManager;
+use crate::ipc::WidgetKind;
+
+/// Axis-aligned rectangle in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    fn new(origin: Point, size: Size) -> Self {
+        Self {
+            x: origin.x,
+            y: origin.y,
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    /// Overlap with another rectangle; zero-sized when they do not overlap.
+    fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Rect {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        point.x >= self.x
+            && point.x <= self.x + self.width
+            && point.y >= self.y
+            && point.y <= self.y + self.height
+    }
+}
+
+/// An `ObserveIntersection` subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntersectionObserver {
+    /// Ancestor whose bounds act as the viewport (None means the window)
+    pub root_id: Option<String>,
+    /// Visible fraction at which the widget counts as intersecting;
+    /// 0 means any visible part
+    pub threshold: f64,
+    /// Whether the widget was intersecting at the last report
+    pub intersecting: Option<bool>,
+}
+
+impl IntersectionObserver {
+    pub fn new(root_id: Option<String>, threshold: f64) -> Self {
+        Self {
+            root_id,
+            threshold,
+            intersecting: None,
+        }
+    }
+
+    /// Record the current visible ratio. Returns true if the widget crossed
+    /// the threshold since the last report (or was never reported).
+    pub fn update(&mut self, ratio: f64) -> bool {
+        let intersecting = if self.threshold == 0.0 {
+            ratio > 0.0
+        } else {
+            ratio >= self.threshold
+        };
+        self.intersecting.replace(intersecting) != Some(intersecting)
+    }
+}
+
+/// Fraction of `target` inside `clip`. A zero-sized target counts as fully
+/// visible when it lies inside the clip.
+fn intersection_ratio(target: &Rect, clip: &Rect) -> f64 {
+    let area = target.width * target.height;
+    if area <= 0.0 {
+        return if clip.contains(Point::new(target.x, target.y)) {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    let visible = target.intersect(clip);
+    visible.width * visible.height / area
+}
+
+/// Result of `GetWidgetRect`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WidgetBounds {
+    /// Bounds relative to the window's top-left corner
+    pub window: Rect,
+    /// Bounds relative to the JS parent widget (the window for top-level widgets)
+    pub parent: Rect,
+    /// Distance of the text baseline above the bottom edge
+    pub baseline: f64,
+}
+
+/// Result of `GetWindowSize`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WindowSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Bounds of a JS widget after the last layout pass. Transforms are not
+/// applied, so this is where the widget was laid out.
+pub fn widget_bounds(
+    render_root: &RenderRoot,
+    widget_manager: &WidgetManager,
+    id: &str,
+) -> Option<WidgetBounds> {
+    let info = widget_manager.widgets.get(id)?;
+    let widget = render_root.get_widget(info.widget_id)?;
+    let origin = widget.ctx().window_origin();
+    let size = widget.ctx().size();
+
+    // The root Flex sits at the window origin
+    let parent_origin = info
+        .parent_id
+        .as_deref()
+        .and_then(|parent_id| widget_manager.widgets.get(parent_id))
+        .and_then(|parent| render_root.get_widget(parent.widget_id))
+        .map(|parent| parent.ctx().window_origin())
+        .unwrap_or(Point::ORIGIN);
+
+    Some(WidgetBounds {
+        window: Rect::new(origin, size),
+        parent: Rect::new((origin - parent_origin).to_point(), size),
+        baseline: widget.ctx().baseline_offset(),
+    })
+}
+
+/// Logical size of the window content.
+pub fn window_size(render_root: &RenderRoot) -> WindowSize {
+    let size = render_root.get_root_widget().ctx().size();
+    WindowSize {
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Fraction of a JS widget visible through its scroll containers, up to and
+/// including `root_id` (or the window). Reflects the scroll offsets applied
+/// by the last compose pass. None if the widget is not in the tree or
+/// `root_id` is not one of its ancestors.
+pub fn visible_ratio(
+    render_root: &RenderRoot,
+    widget_manager: &WidgetManager,
+    id: &str,
+    root_id: Option<&str>,
+) -> Option<f64> {
+    let window_rect = |id: &str| {
+        let info = widget_manager.widgets.get(id)?;
+        let widget = render_root.get_widget(info.widget_id)?;
+        Some(Rect::new(widget.ctx().window_origin(), widget.ctx().size()))
+    };
+
+    let target = window_rect(id)?;
+    // An explicit root is the viewport, even where it extends past the window
+    let mut clip = root_id.is_none().then(|| {
+        let window = window_size(render_root);
+        Rect::new(Point::ORIGIN, Size::new(window.width, window.height))
+    });
+
+    let mut ancestor = widget_manager.widgets.get(id)?.parent_id.as_deref();
+    while let Some(ancestor_id) = ancestor {
+        let info = widget_manager.widgets.get(ancestor_id)?;
+        let is_root = root_id == Some(ancestor_id);
+        if is_root || matches!(info.kind, WidgetKind::Portal) {
+            let bounds = window_rect(ancestor_id)?;
+            clip = Some(match clip {
+                Some(clip) => clip.intersect(&bounds),
+                None => bounds,
+            });
+        }
+        if is_root {
+            break;
+        }
+        ancestor = info.parent_id.as_deref();
+    }
+
+    Some(intersection_ratio(&target, &clip?))
+}
+
+/// JS id of the innermost JS widget under a window point, using masonry's
+/// hit-testing (which honors transforms and clipping).
+pub fn hit_test(
+    render_root: &RenderRoot,
+    widget_manager: &WidgetManager,
+    point: Point,
+) -> Option<String> {
+    let root = render_root.get_root_widget();
+    let hit = root.find_widget_under_pointer(point)?;
+
+    let client_ids: HashMap<WidgetId, &String> = widget_manager
+        .widgets
+        .iter()
+        .map(|(id, info)| (info.widget_id, id))
+        .collect();
+
+    // Masonry widgets created internally (a button's label, wrappers) map to
+    // the nearest JS ancestor
+    let mut parents = HashMap::new();
+    collect_parents(&root, &mut parents);
+    let mut current = Some(hit.id());
+    while let Some(widget_id) = current {
+        if let Some(id) = client_ids.get(&widget_id) {
+            return Some((*id).clone());
+        }
+        current = parents.get(&widget_id).copied();
+    }
+    None
+}
+
+fn collect_parents(widget: &WidgetRef<'_, dyn Widget>, parents: &mut HashMap<WidgetId, WidgetId>) {
+    for child in widget.children() {
+        parents.insert(child.id(), widget.id());
+        collect_parents(&child, parents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widget_bounds_serialization() {
+        let bounds = WidgetBounds {
+            window: Rect::new(Point::new(10.0, 20.0), Size::new(30.0, 40.0)),
+            parent: Rect::new(Point::new(2.0, 4.0), Size::new(30.0, 40.0)),
+            baseline: 6.0,
+        };
+        let value = serde_json::to_value(bounds).unwrap();
+        assert_eq!(value["window"]["x"], 10.0);
+        assert_eq!(value["parent"]["y"], 4.0);
+        assert_eq!(value["window"]["height"], 40.0);
+        assert_eq!(value["baseline"], 6.0);
+    }
+
+    #[test]
+    fn test_intersection_ratio() {
+        let viewport = Rect::new(Point::ORIGIN, Size::new(100.0, 100.0));
+        let half_out = Rect::new(Point::new(0.0, 80.0), Size::new(50.0, 40.0));
+        assert_eq!(intersection_ratio(&half_out, &viewport), 0.5);
+
+        let below = Rect::new(Point::new(0.0, 150.0), Size::new(50.0, 40.0));
+        assert_eq!(intersection_ratio(&below, &viewport), 0.0);
+
+        let empty = Rect::new(Point::new(10.0, 10.0), Size::ZERO);
+        assert_eq!(intersection_ratio(&empty, &viewport), 1.0);
+    }
+
+    #[test]
+    fn test_intersection_observer_reports_threshold_crossings() {
+        let mut observer = IntersectionObserver::new(None, 0.5);
+        // The first update always reports
+        assert!(observer.update(0.2));
+        assert!(!observer.update(0.4));
+        assert!(observer.update(0.5));
+        assert!(!observer.update(1.0));
+        assert!(observer.update(0.0));
+
+        let mut any_pixel = IntersectionObserver::new(None, 0.0);
+        assert!(any_pixel.update(0.0));
+        assert!(any_pixel.update(0.01));
+    }
+}
@@ -1,42 +1,536 @@
+use std::collections::HashSet;
+
 use masonry::app::{RenderRoot, RenderRootSignal};
+use masonry::core::{StyleProperty, WidgetId};
+use masonry::kurbo::{Affine, Point, Size};
 use masonry::widgets::TextArea;
 use masonry::widgets::{
-    Button, Checkbox, Flex, Label, ProgressBar, Prose, SizedBox, Slider, TextInput, ZStack,
+    Button, Checkbox, Flex, Label, Portal, ProgressBar, Prose, SizedBox, Slider, Spinner,
+    TextInput, ZStack,
 };
 use masonry_winit::app::WindowId;
 use winit::dpi::PhysicalSize;
 
-use crate::ipc::{BoxStyle, ClientCommand, UiEventSender, WidgetKind};
+use crate::ipc::{
+    BoxStyle, ClientCommand, CommandResult, ErrorReport, TransformOrigin, TransformValue,
+    UiEventSender, VellumError, WidgetKind,
+};
 
 use super::creation::create_and_add_widget;
+use super::geometry::{self, IntersectionObserver};
+use super::style_registry::ROOT_STYLE_KEY;
 use super::styles::{
-    apply_box_props_to_widget, apply_flex_style, build_text_styles, color_value_to_peniko,
+    BUTTON_FLEX_ALIGNMENT, FLEX_ALIGNMENT, ROOT_FLEX_ALIGNMENT, apply_box_props_to_widget,
+    apply_flex_style, build_text_styles, color_value_to_peniko, text_style_resets,
+    transform_affine,
 };
-use super::widget_manager::{ROOT_FLEX_TAG, WidgetManager};
+use super::widget_manager::{ROOT_FLEX_TAG, ROOT_HOST_TAG, WidgetInfo, WidgetManager};
+use super::widgets::hoverable::Hoverable;
+use super::widgets::root_host::RootHost;
 use super::widgets::svg_widget_impl::SvgWidget;
 use super::widgets::video_widget_impl::VideoWidget;
 
-fn report_runtime_error(event_sender: &UiEventSender, source: &str, message: String, fatal: bool) {
+fn report_runtime_error(
+    event_sender: &UiEventSender,
+    source: &str,
+    error: ErrorReport,
+    fatal: bool,
+) {
     if let Err(send_err) = event_sender.send(crate::ipc::UiEvent::RuntimeError {
         source: source.to_string(),
-        message,
+        error,
         fatal,
     }) {
         eprintln!("[UI] Failed to report runtime error to JS thread: {send_err}");
     }
 }
 
+/// Apply a fully computed style to the root container.
+fn apply_root_style(render_root: &mut RenderRoot, style: &BoxStyle) {
+    let style = &style.with_opacity_applied();
+    render_root.edit_widget_with_tag(ROOT_FLEX_TAG, |mut widget| {
+        let mut flex = widget.downcast::<Flex>();
+        apply_flex_style(&mut flex, style, ROOT_FLEX_ALIGNMENT);
+    });
+}
+
+/// Text styles of a fully computed style, including resets for the
+/// properties it leaves unset.
+fn restyled_text(style: &BoxStyle) -> Vec<StyleProperty> {
+    let mut text_styles = build_text_styles(style);
+    text_styles.extend(text_style_resets(style));
+    text_styles
+}
+
+/// Apply a fully computed style to an existing widget. Every kind is handled,
+/// so a theme switch reaches all widgets.
+fn apply_style_to_widget(render_root: &mut RenderRoot, info: &WidgetInfo, style: &BoxStyle) {
+    let style = &style.with_opacity_applied();
+    let widget_id = info.widget_id;
+    match &info.kind {
+        // Custom widgets are currently rendered as labels
+        WidgetKind::Label | WidgetKind::Custom(_) => {
+            let text_styles = restyled_text(style);
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut label = widget.downcast::<Label>();
+                for s in &text_styles {
+                    Label::insert_style(&mut label, s.clone());
+                }
+                apply_box_props_to_widget(&mut label, style);
+            });
+        }
+        WidgetKind::Button => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                // Apply box properties to the button itself
+                let mut button = widget.downcast::<Button>();
+                apply_box_props_to_widget(&mut button, style);
+
+                // Apply flex styles to the inner flex container
+                let mut child = Button::child_mut(&mut button);
+                let mut flex = child.downcast::<Flex>();
+                apply_flex_style(&mut flex, style, BUTTON_FLEX_ALIGNMENT);
+
+                let child_count = masonry::core::CollectionWidget::len(&*flex.widget);
+                for index in 0..child_count {
+                    let mut inner = masonry::core::CollectionWidget::get_mut(&mut flex, index);
+
+                    if let Some(mut label) = inner.try_downcast::<Label>() {
+                        if let Some(ref color) = style.color {
+                            label.insert_prop(masonry::properties::ContentColor::new(
+                                color_value_to_peniko(color),
+                            ));
+                        }
+                        continue;
+                    }
+
+                    if let Some(mut svg) = inner.try_downcast::<SvgWidget>() {
+                        if let Some(ref color) = style.color {
+                            svg.insert_prop(masonry::properties::ContentColor::new(
+                                color_value_to_peniko(color),
+                            ));
+                        }
+
+                        if let Some(icon_size) = style.icon_size {
+                            svg.insert_prop(masonry::properties::Dimensions::fixed(
+                                masonry::layout::Length::px(icon_size),
+                                masonry::layout::Length::px(icon_size),
+                            ));
+                        }
+                    }
+                }
+            });
+        }
+        WidgetKind::Svg => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut svg_widget = widget.downcast::<SvgWidget>();
+                apply_box_props_to_widget(&mut svg_widget, style);
+            });
+        }
+        // Grids are laid out as a Flex column for now
+        WidgetKind::Flex | WidgetKind::Container | WidgetKind::Grid => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut flex = widget.downcast::<Flex>();
+                apply_flex_style(&mut flex, style, FLEX_ALIGNMENT);
+            });
+        }
+        WidgetKind::ProgressBar => {
+            // ProgressBar value changes are handled via SetWidgetValue
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut pbar = widget.downcast::<ProgressBar>();
+                apply_box_props_to_widget(&mut pbar, style);
+            });
+        }
+        WidgetKind::Slider => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut slider = widget.downcast::<Slider>();
+                apply_box_props_to_widget(&mut slider, style);
+            });
+        }
+        WidgetKind::SizedBox => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut sbox = widget.downcast::<SizedBox>();
+                apply_box_props_to_widget(&mut sbox, style);
+            });
+        }
+        WidgetKind::Image => {
+            // Images in masonry do not support arbitrary box styles natively like HTML.
+            // Width/height are handled by wrapping them in SizedBox (done in image.rs).
+            // We silently ignore box styles on the inner image here to prevent log spam.
+        }
+        WidgetKind::Video => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut video =
+                    widget.downcast::<crate::ui::widgets::video_widget_impl::VideoWidget>();
+                apply_box_props_to_widget(&mut video, style);
+                crate::ui::widgets::video_widget_impl::VideoWidget::set_width(
+                    &mut video,
+                    style.width,
+                );
+                crate::ui::widgets::video_widget_impl::VideoWidget::set_height(
+                    &mut video,
+                    style.height,
+                );
+            });
+        }
+        WidgetKind::TextInput => {
+            let text_styles = restyled_text(style);
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut input = widget.downcast::<TextInput>();
+                apply_box_props_to_widget(&mut input, style);
+                let mut area = TextInput::text_mut(&mut input);
+                for s in &text_styles {
+                    TextArea::<true>::insert_style(&mut area, s.clone());
+                }
+            });
+        }
+        WidgetKind::TextArea => {
+            let text_styles = restyled_text(style);
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut area = widget.downcast::<TextArea<true>>();
+                apply_box_props_to_widget(&mut area, style);
+                for s in &text_styles {
+                    TextArea::<true>::insert_style(&mut area, s.clone());
+                }
+            });
+        }
+        WidgetKind::Prose => {
+            let text_styles = restyled_text(style);
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut prose = widget.downcast::<Prose>();
+                apply_box_props_to_widget(&mut prose, style);
+                let mut area = Prose::text_mut(&mut prose);
+                for s in &text_styles {
+                    TextArea::<false>::insert_style(&mut area, s.clone());
+                }
+            });
+        }
+        WidgetKind::Checkbox => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut checkbox = widget.downcast::<Checkbox>();
+                apply_box_props_to_widget(&mut checkbox, style);
+            });
+        }
+        WidgetKind::Spinner => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut spinner = widget.downcast::<Spinner>();
+                apply_box_props_to_widget(&mut spinner, style);
+            });
+        }
+        WidgetKind::ZStack => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut zstack = widget.downcast::<ZStack>();
+                apply_box_props_to_widget(&mut zstack, style);
+            });
+        }
+        WidgetKind::Portal => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut portal = widget.downcast::<Portal<Flex>>();
+                apply_box_props_to_widget(&mut portal, style);
+            });
+        }
+        WidgetKind::Hoverable => {
+            render_root.edit_widget(widget_id, |mut widget| {
+                let mut hoverable = widget.downcast::<Hoverable>();
+                apply_box_props_to_widget(&mut hoverable, style);
+            });
+        }
+    }
+}
+
+/// Set the paint transform of a widget, resolving its origin against the
+/// widget's current size.
+fn apply_transform(
+    render_root: &mut RenderRoot,
+    widget_id: WidgetId,
+    transform: Option<&(TransformValue, TransformOrigin)>,
+) {
+    let affine = match transform {
+        Some((transform, origin)) => {
+            let size = render_root
+                .get_widget(widget_id)
+                .map(|widget| widget.ctx().size())
+                .unwrap_or(Size::ZERO);
+            transform_affine(transform, origin, size)
+        }
+        None => Affine::IDENTITY,
+    };
+    render_root.edit_widget(widget_id, |mut widget| {
+        widget.ctx.set_transform(affine);
+    });
+}
+
+/// Apply the transform of a widget's computed style if it changed. While any
+/// widget is transformed, the root host reports layout passes so transforms
+/// follow size changes (see [`refresh_transforms`]).
+pub fn update_transform(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    widget_id: WidgetId,
+    style: &BoxStyle,
+) {
+    if !widget_manager.track_transform(widget_id, style) {
+        return;
+    }
+    apply_transform(
+        render_root,
+        widget_id,
+        widget_manager.transforms.get(&widget_id),
+    );
+    sync_layout_reports(render_root, widget_manager);
+}
+
+/// Record the cursor of a widget's computed style. While any widget sets one,
+/// the root host reports pointer movement so the driver can show it.
+pub fn update_cursor(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    id: &str,
+    style: &BoxStyle,
+) {
+    if widget_manager.track_cursor(id, style) {
+        sync_layout_reports(render_root, widget_manager);
+    }
+}
+
+/// Let the root host report layout passes (and pointer movement) only while
+/// something depends on them.
+fn sync_layout_reports(render_root: &mut RenderRoot, widget_manager: &WidgetManager) {
+    let reports_layout = widget_manager.needs_layout_reports();
+    let reports_viewport = widget_manager.needs_viewport_reports();
+    let reports_pointer = widget_manager.needs_pointer_reports();
+    render_root.edit_widget_with_tag(ROOT_HOST_TAG, |mut host| {
+        RootHost::set_reports_layout(&mut host, reports_layout);
+        RootHost::set_reports_viewport(&mut host, reports_viewport);
+        RootHost::set_reports_pointer(&mut host, reports_pointer);
+    });
+}
+
+/// Re-apply every transform after a layout pass, as widget sizes may have changed.
+pub fn refresh_transforms(render_root: &mut RenderRoot, widget_manager: &WidgetManager) {
+    for (widget_id, transform) in &widget_manager.transforms {
+        apply_transform(render_root, *widget_id, Some(transform));
+    }
+}
+
+/// Send `WidgetResized` for every observed widget whose size differs from the
+/// one last reported. Called once per layout pass, so a widget resized several
+/// times between passes is reported once.
+pub fn report_resized_widgets(
+    render_root: &RenderRoot,
+    widget_manager: &mut WidgetManager,
+    event_sender: &UiEventSender,
+) {
+    let WidgetManager {
+        widgets,
+        resize_observers,
+        ..
+    } = widget_manager;
+    for (id, last_size) in resize_observers.iter_mut() {
+        let Some(size) = widgets
+            .get(id)
+            .and_then(|info| render_root.get_widget(info.widget_id))
+            .map(|widget| widget.ctx().size())
+        else {
+            continue;
+        };
+        if *last_size == Some(size) {
+            continue;
+        }
+        *last_size = Some(size);
+        if let Err(send_err) = event_sender.send(crate::ipc::UiEvent::WidgetResized {
+            widget_id: id.clone(),
+            width: size.width,
+            height: size.height,
+        }) {
+            eprintln!("[UI] Failed to send WidgetResized to JS thread: {send_err}");
+        }
+    }
+}
+
+/// Send `IntersectionChanged` for every observed widget that crossed its
+/// visibility threshold since the last report.
+pub fn report_intersections(
+    render_root: &RenderRoot,
+    widget_manager: &mut WidgetManager,
+    event_sender: &UiEventSender,
+) {
+    let mut changed = Vec::new();
+    for (id, observer) in &widget_manager.intersection_observers {
+        if let Some(ratio) =
+            geometry::visible_ratio(render_root, widget_manager, id, observer.root_id.as_deref())
+        {
+            changed.push((id.clone(), ratio));
+        }
+    }
+
+    for (id, ratio) in changed {
+        let Some(observer) = widget_manager.intersection_observers.get_mut(&id) else {
+            continue;
+        };
+        if !observer.update(ratio) {
+            continue;
+        }
+        if let Err(send_err) = event_sender.send(crate::ipc::UiEvent::IntersectionChanged {
+            widget_id: id,
+            ratio,
+        }) {
+            eprintln!("[UI] Failed to send IntersectionChanged to JS thread: {send_err}");
+        }
+    }
+}
+
+/// Recompute and re-apply the effective style of a single widget, if it exists.
+fn restyle_widget(render_root: &mut RenderRoot, widget_manager: &mut WidgetManager, id: &str) {
+    if id == ROOT_STYLE_KEY {
+        apply_root_style(render_root, &widget_manager.styles.computed_root_style());
+        return;
+    }
+    let Some(info) = widget_manager.widgets.get(id) else {
+        return;
+    };
+    let style = widget_manager.computed_style(id, info);
+    let widget_id = info.widget_id;
+    apply_style_to_widget(render_root, info, &style);
+    update_transform(render_root, widget_manager, widget_id, &style);
+    update_cursor(render_root, widget_manager, id, &style);
+}
+
+/// Restyle the given widgets together with their descendants, which may
+/// inherit text styles from them. Each widget is restyled at most once.
+fn restyle_subtrees(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    ids: impl IntoIterator<Item = String>,
+) {
+    let mut restyled = HashSet::new();
+    for id in ids {
+        let subtree = widget_manager.descendants(&id);
+        for widget in std::iter::once(id).chain(subtree) {
+            if restyled.insert(widget.clone()) {
+                restyle_widget(render_root, widget_manager, &widget);
+            }
+        }
+    }
+}
+
+/// Recompute and re-apply the effective style of every widget, e.g. after the
+/// theme changed.
+pub fn restyle_all(render_root: &mut RenderRoot, widget_manager: &mut WidgetManager) {
+    apply_root_style(render_root, &widget_manager.styles.computed_root_style());
+
+    let ids: Vec<String> = widget_manager.widgets.keys().cloned().collect();
+    for id in ids {
+        restyle_widget(render_root, widget_manager, &id);
+    }
+}
+
+/// Restyle a widget whose animated overlay changed, including its descendants
+/// when the overlay touches inherited text properties.
+fn restyle_animated(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    id: &str,
+    inherited_changed: bool,
+) {
+    if inherited_changed {
+        restyle_subtrees(render_root, widget_manager, [id.to_string()]);
+    } else {
+        restyle_widget(render_root, widget_manager, id);
+    }
+}
+
+/// Make the style overlay of a widget match what is currently animating on it.
+fn sync_animated_overlay(widget_manager: &mut WidgetManager, id: &str) {
+    match widget_manager.animations.overlay(id) {
+        Some(overlay) => widget_manager.styles.set_animated(id, overlay),
+        None => widget_manager.styles.clear_animated(id),
+    }
+}
+
+/// Ask the root host to forward animation frames.
+fn request_animation_frames(render_root: &mut RenderRoot) {
+    render_root.edit_widget_with_tag(ROOT_HOST_TAG, |mut host| {
+        RootHost::set_animating(&mut host, true);
+    });
+}
+
+/// Start transitions for properties of `id` whose value changed from `before`
+/// (the style displayed before the change) to the new target style.
+fn start_style_transitions(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    id: &str,
+    info: &WidgetInfo,
+    before: &BoxStyle,
+) {
+    // The target is the style without any in-flight tween
+    let overlay = widget_manager.styles.take_animated(id);
+    let target = widget_manager.computed_style(id, info);
+    if let Some(overlay) = overlay {
+        widget_manager.styles.set_animated(id, overlay);
+    }
+
+    let Some(spec) = target.transition.as_ref() else {
+        return;
+    };
+    if widget_manager
+        .animations
+        .start_transitions(id, before, &target, spec)
+    {
+        sync_animated_overlay(widget_manager, id);
+        request_animation_frames(render_root);
+    }
+}
+
+fn notify_animation_ended(event_sender: &UiEventSender, widget_id: String, cancelled: bool) {
+    if let Err(send_err) = event_sender.send(crate::ipc::UiEvent::AnimationEnded {
+        widget_id,
+        cancelled,
+    }) {
+        eprintln!("[UI] Failed to send AnimationEnded to JS thread: {send_err}");
+    }
+}
+
+/// Advance running animations by one animation frame and restyle the animated
+/// widgets. Returns false once no animation is left running.
+pub fn advance_animations(
+    render_root: &mut RenderRoot,
+    widget_manager: &mut WidgetManager,
+    event_sender: &UiEventSender,
+    interval_ns: u64,
+) -> bool {
+    let (restyle, finished) = widget_manager.advance_animations(interval_ns as f64 / 1_000_000.0);
+
+    for (id, inherited_changed) in restyle {
+        restyle_animated(render_root, widget_manager, &id, inherited_changed);
+    }
+    for id in finished {
+        notify_animation_ended(event_sender, id, false);
+    }
+
+    widget_manager.animations.is_active()
+}
+
+/// Wrap the answer to a query command as a command result.
+fn query_result(value: impl serde::Serialize) -> CommandResult {
+    serde_json::to_value(value)
+        .map(Some)
+        .map_err(|e| VellumError::internal(format!("Failed to encode query result: {e}")))
+}
+
 /// Process a single ClientCommand by mutating the widget tree.
+/// Errors are reported to the client by the caller, as a response when the
+/// command was a request and as a runtime error otherwise.
 pub fn handle_client_command(
     cmd: ClientCommand,
     _window_id: WindowId,
     render_root: &mut RenderRoot,
     widget_manager: &mut WidgetManager,
     _event_sender: &UiEventSender,
-) {
+) -> CommandResult {
     match cmd {
         ClientCommand::SetTitle(title) => {
-            println!("[UI] Setting window title: {}", title);
+            eprintln!("[UI] Setting window title: {}", title);
             render_root.emit_signal(RenderRootSignal::SetTitle(title));
         }
 
@@ -47,7 +541,11 @@ pub fn handle_client_command(
             text,
             style,
             data,
+            classes,
         } => {
+            if widget_manager.widgets.contains_key(&id) {
+                return Err(VellumError::DuplicateId { widget_id: id });
+            }
             create_and_add_widget(
                 render_root,
                 widget_manager,
@@ -57,7 +555,8 @@ pub fn handle_client_command(
                 text,
                 style,
                 data,
-            );
+                classes,
+            )?;
         }
 
         ClientCommand::SetWidgetText { id, text } => {
@@ -85,13 +584,12 @@ pub fn handle_client_command(
                         });
                     }
                     WidgetKind::Button => {
-                        report_runtime_error(
-                            _event_sender,
-                            "ui-handler",
-                            "SetWidgetText on Button is not supported. Use a child label widget instead."
-                                .to_string(),
-                            false,
-                        );
+                        // Button text lives in a child label widget
+                        return Err(VellumError::unsupported(
+                            Some(&id),
+                            &info.kind,
+                            "SetWidgetText",
+                        ));
                     }
                     WidgetKind::Svg => {
                         let svg_markup = text.clone();
@@ -101,25 +599,16 @@ pub fn handle_client_command(
                         });
                     }
                     _ => {
-                        report_runtime_error(
-                            _event_sender,
-                            "ui-handler",
-                            format!(
-                                "SetWidgetText on {:?} is not supported for widget '{id}'",
-                                info.kind
-                            ),
-                            false,
-                        );
+                        return Err(VellumError::unsupported(
+                            Some(&id),
+                            &info.kind,
+                            "SetWidgetText",
+                        ));
                     }
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for SetWidgetText", id);
-                report_runtime_error(
-                    _event_sender,
-                    "ui-handler",
-                    format!("Widget '{id}' not found for SetWidgetText"),
-                    false,
-                );
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
@@ -140,25 +629,16 @@ pub fn handle_client_command(
                         });
                     }
                     _ => {
-                        report_runtime_error(
-                            _event_sender,
-                            "ui-handler",
-                            format!(
-                                "SetWidgetValue on {:?} is not supported for widget '{id}'",
-                                info.kind
-                            ),
-                            false,
-                        );
+                        return Err(VellumError::unsupported(
+                            Some(&id),
+                            &info.kind,
+                            "SetWidgetValue",
+                        ));
                     }
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for SetWidgetValue", id);
-                report_runtime_error(
-                    _event_sender,
-                    "ui-handler",
-                    format!("Widget '{id}' not found for SetWidgetValue"),
-                    false,
-                );
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
@@ -171,18 +651,11 @@ pub fn handle_client_command(
                         VideoWidget::play(&mut video);
                     });
                 } else {
-                    report_runtime_error(
-                        _event_sender,
-                        "ui-handler",
-                        format!(
-                            "PlayVideo on {:?} is not supported for widget '{id}'",
-                            info.kind
-                        ),
-                        false,
-                    );
+                    return Err(VellumError::unsupported(Some(&id), &info.kind, "PlayVideo"));
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for PlayVideo", id);
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
@@ -195,18 +668,15 @@ pub fn handle_client_command(
                         VideoWidget::pause(&mut video);
                     });
                 } else {
-                    report_runtime_error(
-                        _event_sender,
-                        "ui-handler",
-                        format!(
-                            "PauseVideo on {:?} is not supported for widget '{id}'",
-                            info.kind
-                        ),
-                        false,
-                    );
+                    return Err(VellumError::unsupported(
+                        Some(&id),
+                        &info.kind,
+                        "PauseVideo",
+                    ));
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for PauseVideo", id);
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
@@ -219,18 +689,11 @@ pub fn handle_client_command(
                         VideoWidget::seek(&mut video, time_secs);
                     });
                 } else {
-                    report_runtime_error(
-                        _event_sender,
-                        "ui-handler",
-                        format!(
-                            "SeekVideo on {:?} is not supported for widget '{id}'",
-                            info.kind
-                        ),
-                        false,
-                    );
+                    return Err(VellumError::unsupported(Some(&id), &info.kind, "SeekVideo"));
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for SeekVideo", id);
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
@@ -243,164 +706,146 @@ pub fn handle_client_command(
                         Checkbox::set_checked(&mut cb, checked);
                     });
                 } else {
-                    report_runtime_error(
-                        _event_sender,
-                        "ui-handler",
-                        format!(
-                            "SetWidgetChecked on {:?} is not supported for widget '{id}'",
-                            info.kind
-                        ),
-                        false,
-                    );
+                    return Err(VellumError::unsupported(
+                        Some(&id),
+                        &info.kind,
+                        "SetWidgetChecked",
+                    ));
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for SetWidgetChecked", id);
-                report_runtime_error(
-                    _event_sender,
-                    "ui-handler",
-                    format!("Widget '{id}' not found for SetWidgetChecked"),
-                    false,
-                );
+                return Err(VellumError::widget_not_found(&id));
             }
         }
 
         ClientCommand::SetWidgetStyle { id, style } => {
             // Special handling for root flex (the "body" element)
-            if id == "__root__" {
-                render_root.edit_widget_with_tag(ROOT_FLEX_TAG, |mut widget| {
-                    let mut flex = widget.downcast::<Flex>();
-                    apply_flex_style(&mut flex, &style);
-                });
-                return;
+            if id == ROOT_STYLE_KEY {
+                widget_manager.styles.merge_inline(ROOT_STYLE_KEY, &style);
+                if style.sets_inherited_text() {
+                    restyle_all(render_root, widget_manager);
+                } else {
+                    apply_root_style(render_root, &widget_manager.styles.computed_root_style());
+                }
+                return Ok(None);
             }
 
-            if let Some(info) = widget_manager.widgets.get(&id) {
-                let widget_id = info.widget_id;
-                match &info.kind {
-                    WidgetKind::Label => {
-                        let text_styles = build_text_styles(&style);
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut label = widget.downcast::<Label>();
-                            for s in &text_styles {
-                                Label::insert_style(&mut label, s.clone());
-                            }
-                            apply_box_props_to_widget(&mut label, &style);
-                        });
-                    }
-                    WidgetKind::Button => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            // Apply box properties to the button itself
-                            let mut button = widget.downcast::<Button>();
-                            apply_box_props_to_widget(&mut button, &style);
-
-                            // Apply flex styles to the inner flex container
-                            let mut child = Button::child_mut(&mut button);
-                            let mut flex = child.downcast::<Flex>();
-                            apply_flex_style(&mut flex, &style);
-
-                            let child_count = masonry::core::CollectionWidget::len(&*flex.widget);
-                            for index in 0..child_count {
-                                let mut inner =
-                                    masonry::core::CollectionWidget::get_mut(&mut flex, index);
-
-                                if let Some(mut label) = inner.try_downcast::<Label>() {
-                                    if let Some(ref color) = style.color {
-                                        label.insert_prop(masonry::properties::ContentColor::new(
-                                            color_value_to_peniko(color),
-                                        ));
-                                    }
-                                    continue;
-                                }
-
-                                if let Some(mut svg) = inner.try_downcast::<SvgWidget>() {
-                                    if let Some(ref color) = style.color {
-                                        svg.insert_prop(masonry::properties::ContentColor::new(
-                                            color_value_to_peniko(color),
-                                        ));
-                                    }
-
-                                    if let Some(icon_size) = style.icon_size {
-                                        svg.insert_prop(masonry::properties::Dimensions::fixed(
-                                            masonry::layout::Length::px(icon_size),
-                                            masonry::layout::Length::px(icon_size),
-                                        ));
-                                    }
-                                }
-                            }
-                        });
-                    }
-                    WidgetKind::Svg => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut svg_widget = widget.downcast::<SvgWidget>();
-                            apply_box_props_to_widget(&mut svg_widget, &style);
-                        });
-                    }
-                    WidgetKind::Flex | WidgetKind::Container => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut flex = widget.downcast::<Flex>();
-                            apply_flex_style(&mut flex, &style);
-                        });
-                    }
-                    WidgetKind::ProgressBar => {
-                        // ProgressBar value changes are handled via SetWidgetValue
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut pbar = widget.downcast::<ProgressBar>();
-                            apply_box_props_to_widget(&mut pbar, &style);
-                        });
-                    }
-                    WidgetKind::Slider => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut slider = widget.downcast::<Slider>();
-                            apply_box_props_to_widget(&mut slider, &style);
-                        });
-                    }
-                    WidgetKind::SizedBox => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut sbox = widget.downcast::<SizedBox>();
-                            apply_box_props_to_widget(&mut sbox, &style);
-                        });
-                    }
-                    WidgetKind::Image => {
-                        // Images in masonry do not support arbitrary box styles natively like HTML.
-                        // Width/height are handled by wrapping them in SizedBox (done in image.rs).
-                        // We silently ignore box styles on the inner image here to prevent log spam.
-                    }
-                    WidgetKind::Video => {
-                        render_root.edit_widget(widget_id, |mut widget| {
-                            let mut video = widget
-                                .downcast::<crate::ui::widgets::video_widget_impl::VideoWidget>(
-                            );
-                            apply_box_props_to_widget(&mut video, &style);
-                            crate::ui::widgets::video_widget_impl::VideoWidget::set_width(
-                                &mut video,
-                                style.width,
-                            );
-                            crate::ui::widgets::video_widget_impl::VideoWidget::set_height(
-                                &mut video,
-                                style.height,
-                            );
-                        });
-                    }
-                    _ => {
-                        report_runtime_error(
-                            _event_sender,
-                            "ui-handler",
-                            format!(
-                                "SetWidgetStyle was not fully supported for {:?} widget '{id}'",
-                                info.kind
-                            ),
-                            false,
-                        );
-                    }
+            if let Some(info) = widget_manager.widgets.get(&id).cloned() {
+                let before = widget_manager.computed_style(&id, &info);
+                widget_manager.styles.merge_inline(&id, &style);
+                start_style_transitions(render_root, widget_manager, &id, &info, &before);
+                if style.sets_inherited_text() {
+                    let descendants = widget_manager.descendants(&id);
+                    restyle_subtrees(render_root, widget_manager, descendants);
                 }
+                let computed = widget_manager.computed_style(&id, &info);
+                apply_style_to_widget(render_root, &info, &computed);
+                update_transform(render_root, widget_manager, info.widget_id, &computed);
+                update_cursor(render_root, widget_manager, &id, &computed);
             } else {
                 eprintln!("[UI] Widget '{}' not found for SetWidgetStyle", id);
-                report_runtime_error(
-                    _event_sender,
-                    "ui-handler",
-                    format!("Widget '{id}' not found for SetWidgetStyle"),
-                    false,
-                );
+                return Err(VellumError::widget_not_found(&id));
+            }
+        }
+
+        ClientCommand::DefineStyleClass { name, style } => {
+            eprintln!("[UI] Defining style class '{}'", name);
+            let members = widget_manager.styles.define_class(name, style);
+            restyle_subtrees(render_root, widget_manager, members);
+        }
+
+        ClientCommand::SetWidgetClasses { id, classes } => {
+            if id == ROOT_STYLE_KEY || widget_manager.widgets.contains_key(&id) {
+                widget_manager.styles.set_classes(&id, classes);
+                let descendants = widget_manager.descendants(&id);
+                restyle_subtrees(render_root, widget_manager, descendants);
+                restyle_widget(render_root, widget_manager, &id);
+            } else {
+                eprintln!("[UI] Widget '{}' not found for SetWidgetClasses", id);
+                return Err(VellumError::widget_not_found(&id));
+            }
+        }
+
+        ClientCommand::SetTheme { theme } => {
+            eprintln!("[UI] Applying new theme");
+            widget_manager.styles.theme = theme;
+            restyle_all(render_root, widget_manager);
+        }
+
+        ClientCommand::SetThemeVariant { variant } => {
+            eprintln!("[UI] Switching theme variant to {:?}", variant);
+            widget_manager.styles.theme.variant = variant;
+            restyle_all(render_root, widget_manager);
+        }
+
+        ClientCommand::SetStyleVariables { variables } => {
+            eprintln!("[UI] Setting {} style variable(s)", variables.len());
+            let changed = widget_manager.styles.set_variables(variables);
+            if changed.is_empty() {
+                return Ok(None);
+            }
+            if widget_manager
+                .styles
+                .references_any(ROOT_STYLE_KEY, None, &changed)
+            {
+                restyle_all(render_root, widget_manager);
+                return Ok(None);
+            }
+            let styles = &widget_manager.styles;
+            let affected: Vec<String> = widget_manager
+                .widgets
+                .iter()
+                .filter(|(id, info)| styles.references_any(id, Some(&info.kind), &changed))
+                .map(|(id, _)| id.clone())
+                .collect();
+            restyle_subtrees(render_root, widget_manager, affected);
+        }
+
+        ClientCommand::Animate {
+            id,
+            mut keyframes,
+            duration_ms,
+            easing,
+            iterations,
+            direction,
+        } => {
+            let Some(info) = widget_manager.widgets.get(&id).cloned() else {
+                eprintln!("[UI] Widget '{}' not found for Animate", id);
+                return Err(VellumError::widget_not_found(&id));
+            };
+
+            // A running animation is replaced; its overlay must not leak into the base style
+            if widget_manager.animations.cancel(&id) {
+                sync_animated_overlay(widget_manager, &id);
+                notify_animation_ended(_event_sender, id.clone(), true);
+            }
+            for keyframe in &mut keyframes {
+                widget_manager.styles.resolve(&mut keyframe.style);
+            }
+            let base = widget_manager.computed_style(&id, &info);
+            eprintln!(
+                "[UI] Animating '{}' over {}ms ({} keyframes)",
+                id,
+                duration_ms,
+                keyframes.len()
+            );
+            widget_manager.animations.start(
+                id,
+                keyframes,
+                &base,
+                duration_ms,
+                easing,
+                iterations,
+                direction,
+            );
+            request_animation_frames(render_root);
+        }
+
+        ClientCommand::CancelAnimation { id } => {
+            if widget_manager.animations.cancel(&id) {
+                sync_animated_overlay(widget_manager, &id);
+                restyle_animated(render_root, widget_manager, &id, true);
+                notify_animation_ended(_event_sender, id, true);
             }
         }
 
@@ -409,49 +854,25 @@ pub fn handle_client_command(
             property,
             value,
         } => {
-            println!(
+            eprintln!(
                 "[UI] SetStyleProperty id={}, {}={} (applying via full style path)",
                 id, property, value
             );
             // Build a partial style and delegate
-            // Build a JSON value properly so control characters (newlines in SVG
-            // strings, etc.) are escaped correctly instead of being embedded raw.
-            let parsed_value: serde_json::Value = if let Ok(n) = value.parse::<f64>() {
-                serde_json::Value::Number(
-                    serde_json::Number::from_f64(n).unwrap_or_else(|| serde_json::Number::from(0)),
-                )
-            } else if value == "true" {
-                serde_json::Value::Bool(true)
-            } else if value == "false" {
-                serde_json::Value::Bool(false)
-            } else if (value.starts_with('{') || value.starts_with('['))
-                && serde_json::from_str::<serde_json::Value>(&value).is_ok()
-            {
-                serde_json::from_str(&value).unwrap()
-            } else {
-                // Strip surrounding quotes if present, then use
-                // serde_json::Value::String which handles escaping.
-                let raw = value
-                    .strip_prefix('"')
-                    .and_then(|s| s.strip_suffix('"'))
-                    .unwrap_or(&value);
-                serde_json::Value::String(raw.to_string())
-            };
-
-            let json_str = serde_json::json!({ &property: parsed_value }).to_string();
-
-            let style = match serde_json::from_str::<BoxStyle>(&json_str) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!(
-                        "[UI] Failed to parse SetStyleProperty json '{}': {}",
-                        json_str, e
-                    );
-                    BoxStyle::default()
+            let style = BoxStyle::from_property(&property, &value).map_err(|e| {
+                eprintln!(
+                    "[UI] Failed to parse SetStyleProperty {}={}: {}",
+                    property, value, e
+                );
+                VellumError::InvalidStyle {
+                    widget_id: Some(id.clone()),
+                    property: property.clone(),
+                    value: value.clone(),
+                    reason: e.to_string(),
                 }
-            };
+            })?;
             // Re-dispatch as SetWidgetStyle
-            handle_client_command(
+            return handle_client_command(
                 ClientCommand::SetWidgetStyle { id, style },
                 _window_id,
                 render_root,
@@ -460,15 +881,16 @@ pub fn handle_client_command(
             );
         }
 
-        ClientCommand::SetWidgetVisible { id, visible } => {
-            report_runtime_error(
-                _event_sender,
-                "ui-handler",
-                format!(
-                    "SetWidgetVisible is not implemented for widget '{id}' (requested visible={visible})"
-                ),
-                false,
-            );
+        ClientCommand::SetWidgetVisible { id, visible: _ } => {
+            let Some(info) = widget_manager.widgets.get(&id) else {
+                return Err(VellumError::widget_not_found(&id));
+            };
+            // Not implemented for any widget kind yet
+            return Err(VellumError::unsupported(
+                Some(&id),
+                &info.kind,
+                "SetWidgetVisible",
+            ));
         }
 
         ClientCommand::RemoveWidget { id } => {
@@ -485,13 +907,16 @@ pub fn handle_client_command(
                     report_runtime_error(
                         _event_sender,
                         "ui-handler",
-                        format!(
-                            "RemoveWidget for '{id}' found no siblings under parent '{parent_key}'; metadata was synced only"
+                        ErrorReport::new(
+                            VellumError::internal(format!(
+                                "RemoveWidget for '{id}' found no siblings under parent '{parent_key}'; metadata was synced only"
+                            )),
+                            Some("RemoveWidget"),
                         ),
                         false,
                     );
                     widget_manager.remove_widget_subtree(&id);
-                    return;
+                    return Ok(None);
                 }
 
                 let safe_index = if child_index < sibling_count {
@@ -504,8 +929,11 @@ pub fn handle_client_command(
                     report_runtime_error(
                         _event_sender,
                         "ui-handler",
-                        format!(
-                            "RemoveWidget for '{id}' had stale index {child_index}; clamped within parent '{parent_key}'"
+                        ErrorReport::new(
+                            VellumError::internal(format!(
+                                "RemoveWidget for '{id}' had stale index {child_index}; clamped within parent '{parent_key}'"
+                            )),
+                            Some("RemoveWidget"),
                         ),
                         false,
                     );
@@ -553,9 +981,13 @@ pub fn handle_client_command(
                             report_runtime_error(
                                 _event_sender,
                                 "ui-handler",
-                                format!(
-                                    "Parent '{parent_key}' of kind {:?} does not support child removal for '{id}'",
-                                    parent_info.kind
+                                ErrorReport::new(
+                                    VellumError::unsupported(
+                                        Some(parent_key),
+                                        &parent_info.kind,
+                                        "RemoveWidget",
+                                    ),
+                                    Some("RemoveWidget"),
                                 ),
                                 false,
                             );
@@ -569,8 +1001,9 @@ pub fn handle_client_command(
                     report_runtime_error(
                         _event_sender,
                         "ui-handler",
-                        format!(
-                            "Parent widget '{parent_key}' not found for RemoveWidget '{id}'; metadata was synced only"
+                        ErrorReport::new(
+                            VellumError::widget_not_found(parent_key),
+                            Some("RemoveWidget"),
                         ),
                         false,
                     );
@@ -578,11 +1011,11 @@ pub fn handle_client_command(
 
                 widget_manager.remove_widget_subtree(&id);
 
-                println!("[UI] Removed widget '{}'", id);
+                eprintln!("[UI] Removed widget '{}'", id);
             } else {
                 // If it's not found, it's highly likely a parent was removed recently
                 // and `remove_widget_subtree` already recursively deleted this child.
-                println!(
+                eprintln!(
                     "[UI] Widget '{}' not found for RemoveWidget (likely implicitly removed by parent)",
                     id
                 );
@@ -590,46 +1023,148 @@ pub fn handle_client_command(
         }
 
         ClientCommand::ResizeWindow { width, height } => {
-            println!("[UI] Resizing window to {}x{}", width, height);
+            eprintln!("[UI] Resizing window to {}x{}", width, height);
             let size = PhysicalSize::new(width, height);
             render_root.emit_signal(RenderRootSignal::SetSize(size));
         }
 
         ClientCommand::CloseWindow => {
-            println!("[UI] Closing window");
+            eprintln!("[UI] Closing window");
             render_root.emit_signal(RenderRootSignal::Exit);
         }
 
         ClientCommand::ExitApp => {
-            println!("[UI] Exiting application");
+            eprintln!("[UI] Exiting application");
             render_root.emit_signal(RenderRootSignal::Exit);
         }
 
+        ClientCommand::ResetUi => {
+            eprintln!("[UI] Resetting the UI for a new client");
+            let root_children = widget_manager.current_child_count("__root__");
+            render_root.edit_widget_with_tag(ROOT_FLEX_TAG, |mut flex| {
+                for index in (0..root_children).rev() {
+                    masonry::core::CollectionWidget::remove(&mut flex, index);
+                }
+            });
+            *widget_manager = WidgetManager::new();
+            restyle_all(render_root, widget_manager);
+            sync_layout_reports(render_root, widget_manager);
+        }
+
         ClientCommand::SetImageData { id, data } => {
             if let Some(info) = widget_manager.widgets.get(&id) {
                 if matches!(info.kind, WidgetKind::Image) {
                     let widget_id = info.widget_id;
-                    super::widgets::image::update_data(render_root, widget_id, &data);
+                    super::widgets::image::update_data(render_root, &id, widget_id, &data)?;
                 } else {
-                    report_runtime_error(
-                        _event_sender,
-                        "ui-handler",
-                        format!(
-                            "SetImageData on {:?} is not supported for widget '{id}'",
-                            info.kind
-                        ),
-                        false,
-                    );
+                    return Err(VellumError::unsupported(
+                        Some(&id),
+                        &info.kind,
+                        "SetImageData",
+                    ));
                 }
             } else {
                 eprintln!("[UI] Widget '{}' not found for SetImageData", id);
-                report_runtime_error(
-                    _event_sender,
-                    "ui-handler",
-                    format!("Widget '{id}' not found for SetImageData"),
-                    false,
-                );
+                return Err(VellumError::widget_not_found(&id));
+            }
+        }
+
+        ClientCommand::GetWidgetRect { id } => {
+            let Some(bounds) = geometry::widget_bounds(render_root, widget_manager, &id) else {
+                return Err(VellumError::widget_not_found(&id));
+            };
+            return query_result(bounds);
+        }
+
+        ClientCommand::GetWindowSize => {
+            return query_result(geometry::window_size(render_root));
+        }
+
+        ClientCommand::HitTest { x, y } => {
+            return query_result(geometry::hit_test(
+                render_root,
+                widget_manager,
+                Point::new(x, y),
+            ));
+        }
+
+        ClientCommand::ObserveResize { id } => {
+            let Some(info) = widget_manager.widgets.get(&id) else {
+                return Err(VellumError::widget_not_found(&id));
+            };
+            let widget_id = info.widget_id;
+            widget_manager.resize_observers.entry(id).or_insert(None);
+            sync_layout_reports(render_root, widget_manager);
+            // The next layout pass reports the current size
+            render_root.edit_widget(widget_id, |mut widget| {
+                widget.ctx.request_layout();
+            });
+        }
+
+        ClientCommand::UnobserveResize { id } => {
+            if widget_manager.resize_observers.remove(&id).is_some() {
+                sync_layout_reports(render_root, widget_manager);
+            }
+        }
+
+        ClientCommand::ObserveIntersection {
+            id,
+            root_id,
+            threshold,
+        } => {
+            let Some(info) = widget_manager.widgets.get(&id) else {
+                return Err(VellumError::widget_not_found(&id));
+            };
+            let widget_id = info.widget_id;
+            if let Some(root_id) = &root_id
+                && !widget_manager.descendants(root_id).contains(&id)
+            {
+                return Err(VellumError::InvalidArgument {
+                    widget_id: Some(id),
+                    reason: format!("intersection root '{root_id}' is not an ancestor"),
+                });
             }
+            widget_manager
+                .intersection_observers
+                .insert(id, IntersectionObserver::new(root_id, threshold));
+            sync_layout_reports(render_root, widget_manager);
+            // The next layout pass reports the current visibility
+            render_root.edit_widget(widget_id, |mut widget| {
+                widget.ctx.request_layout();
+            });
+        }
+
+        ClientCommand::UnobserveIntersection { id } => {
+            if widget_manager.intersection_observers.remove(&id).is_some() {
+                sync_layout_reports(render_root, widget_manager);
+            }
+        }
+
+        ClientCommand::SetCursor(cursor) => {
+            // The driver shows it right away and keeps it up as the pointer moves
+            widget_manager.cursor_override = cursor;
+            sync_layout_reports(render_root, widget_manager);
+        }
+
+        // These need the winit window, so the driver runs them through its
+        // `WindowController` instead
+        command @ (ClientCommand::SetWindowMinimized(_)
+        | ClientCommand::SetWindowMaximized(_)
+        | ClientCommand::SetWindowFullscreen(_)
+        | ClientCommand::SetWindowPosition { .. }
+        | ClientCommand::SetWindowMinSize(_)
+        | ClientCommand::SetWindowMaxSize(_)
+        | ClientCommand::SetWindowAlwaysOnTop(_)
+        | ClientCommand::SetWindowDecorations(_)
+        | ClientCommand::SetWindowIcon(_)
+        | ClientCommand::RequestWindowAttention { .. }
+        | ClientCommand::GetWindowState) => {
+            return Err(VellumError::internal(format!(
+                "{} reached the widget handler",
+                command.name()
+            )));
         }
     }
+
+    Ok(None)
 }
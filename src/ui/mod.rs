@@ -1,24 +1,32 @@
 // UI Thread Module
 // Handles the main window, widget tree, and rendering using masonry_winit
 
+pub mod animator;
+pub mod app;
 pub mod creation;
+pub mod cursor;
 pub mod driver;
+pub mod file_drop;
+pub mod geometry;
 pub mod global_state;
 pub mod handler;
 pub mod layout;
+pub mod style_registry;
 pub mod styles;
 pub mod widget_manager;
 pub mod widgets;
+pub mod window;
 
 use masonry::core::NewWidget;
-use masonry::dpi::LogicalSize;
+use masonry::peniko::Color;
 use masonry::theme::default_property_set;
 use masonry_winit::app::{EventLoopProxy, NewWindow, WindowId};
-use masonry_winit::winit::window::Window;
 
 use self::driver::VellumDriver;
 use self::layout::create_initial_ui;
-use self::widget_manager::ROOT_FLEX_TAG;
+use self::widget_manager::{ROOT_FLEX_TAG, ROOT_HOST_TAG};
+use self::widgets::root_host::RootHost;
+pub use self::window::WindowConfig;
 use crate::ipc::UiEventSender;
 
 /// Holds the information needed to set up IPC before the event loop blocks.
@@ -53,34 +61,44 @@ pub fn run_ui_blocking(
     event_loop: masonry_winit::app::EventLoop,
     window_id: WindowId,
     event_sender: UiEventSender,
+    window: &WindowConfig,
 ) {
-    let window_size = LogicalSize::new(800.0, 600.0);
-
-    let window_attributes = Window::default_attributes()
-        .with_title("Vellum - JavaScript Desktop Runtime")
-        .with_resizable(true)
-        .with_min_inner_size(LogicalSize::new(400.0, 300.0))
-        .with_inner_size(window_size);
+    let window_attributes = window.attributes();
 
     let error_sender = event_sender.clone();
     let driver = VellumDriver::new(event_sender);
     let main_widget = create_initial_ui();
 
-    masonry_winit::app::run_with(
+    let mut new_window = NewWindow::new_with_id(
+        window_id,
+        window_attributes,
+        NewWidget::new_with_tag(
+            RootHost::new(NewWidget::new_with_tag(main_widget, ROOT_FLEX_TAG)),
+            ROOT_HOST_TAG,
+        )
+        .erased(),
+    );
+    if window.transparent {
+        // Otherwise the renderer clears the surface to an opaque base color
+        new_window = new_window.with_base_color(Color::TRANSPARENT);
+    }
+
+    app::run(
         event_loop,
-        vec![NewWindow::new_with_id(
-            window_id,
-            window_attributes,
-            NewWidget::new_with_tag(main_widget, ROOT_FLEX_TAG).erased(),
-        )],
+        vec![new_window],
         driver,
+        // Theme styles are applied to each widget (see `handler::restyle_all`),
+        // so a theme switch leaves no stale defaults behind
         default_property_set(),
     )
     .unwrap_or_else(|e| {
         let message = format!("Fatal UI runtime failure: {e}");
         let _ = error_sender.send(crate::ipc::UiEvent::RuntimeError {
             source: "ui-runtime".to_string(),
-            message: message.clone(),
+            error: crate::ipc::ErrorReport::new(
+                crate::ipc::VellumError::internal(message.clone()),
+                None,
+            ),
             fatal: true,
         });
         panic!("{message}");
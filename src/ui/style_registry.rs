@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ipc::style_vars::{normalize_var_name, parse_var_reference, referenced_var_names};
+use crate::ipc::{BoxStyle, ThemeTokens, WidgetKind};
+
+/// Key under which the root container's inline style is stored.
+pub const ROOT_STYLE_KEY: &str = "__root__";
+
+/// Tracks the styling inputs of every widget so that its effective style can
+/// be recomputed whenever a shared input (theme, style class) changes.
+///
+/// Cascade order, lowest to highest priority: theme defaults for the widget
+/// kind, text styles inherited from the parent container, style classes in
+/// the order they were listed, inline style, running animation.
+/// `var(--name)` references left after the cascade are then resolved against
+/// the variable table, falling back to the theme's token variables.
+pub struct StyleRegistry {
+    /// Active theme tokens.
+    pub theme: ThemeTokens,
+    /// Named style class definitions.
+    classes: HashMap<String, BoxStyle>,
+    /// Style classes assigned to each widget, keyed by JS widget id.
+    widget_classes: HashMap<String, Vec<String>>,
+    /// Inline styles as sent by the client, keyed by JS widget id.
+    inline: HashMap<String, BoxStyle>,
+    /// Style variables set via `SetStyleVariables`, keyed without the `--` prefix.
+    variables: HashMap<String, String>,
+    /// Current frame of each running animation, keyed by JS widget id.
+    animated: HashMap<String, BoxStyle>,
+}
+
+/// Upper bound on variable-to-variable indirection, guarding against cycles.
+const MAX_VAR_DEPTH: usize = 8;
+
+impl StyleRegistry {
+    pub fn new() -> Self {
+        Self {
+            theme: ThemeTokens::default(),
+            classes: HashMap::new(),
+            widget_classes: HashMap::new(),
+            inline: HashMap::new(),
+            variables: HashMap::new(),
+            animated: HashMap::new(),
+        }
+    }
+
+    /// Set or (with `None`) remove style variables. Returns the names of every
+    /// variable whose value changed, including variables defined in terms of them.
+    pub fn set_variables(&mut self, variables: HashMap<String, Option<String>>) -> HashSet<String> {
+        let mut changed = HashSet::new();
+        for (name, value) in variables {
+            let name = normalize_var_name(&name).to_string();
+            let previous = match value {
+                Some(value) => self.variables.insert(name.clone(), value),
+                None => self.variables.remove(&name),
+            };
+            if previous != self.variables.get(&name).cloned() {
+                changed.insert(name);
+            }
+        }
+
+        // Propagate through variables whose value references a changed one
+        loop {
+            let dependents: Vec<String> = self
+                .variables
+                .iter()
+                .filter(|(name, value)| {
+                    !changed.contains(*name)
+                        && referenced_var_names(value)
+                            .iter()
+                            .any(|dep| changed.contains(*dep))
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            if dependents.is_empty() {
+                break;
+            }
+            changed.extend(dependents);
+        }
+        changed
+    }
+
+    /// Current value of a variable: client-defined first, then theme tokens.
+    fn variable(&self, name: &str) -> Option<String> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| self.theme.token_variable(name))
+    }
+
+    /// Resolve a `var(...)` expression to a literal value, following fallbacks
+    /// and variables that are themselves defined as references.
+    fn resolve_expression(&self, expression: &str) -> Option<String> {
+        let mut current = expression.to_string();
+        for _ in 0..MAX_VAR_DEPTH {
+            let Some(reference) = parse_var_reference(&current) else {
+                return Some(current);
+            };
+            current = match (self.variable(reference.name), reference.fallback) {
+                (Some(value), _) => value,
+                (None, Some(fallback)) => fallback.to_string(),
+                (None, None) => return None,
+            };
+        }
+        None
+    }
+
+    /// Merge a (possibly partial) inline style into the widget's stored inline style.
+    pub fn merge_inline(&mut self, id: &str, style: &BoxStyle) {
+        self.inline
+            .entry(id.to_string())
+            .or_default()
+            .merge_from(style);
+    }
+
+    /// Replace the style classes assigned to a widget.
+    pub fn set_classes(&mut self, id: &str, classes: Vec<String>) {
+        if classes.is_empty() {
+            self.widget_classes.remove(id);
+        } else {
+            self.widget_classes.insert(id.to_string(), classes);
+        }
+    }
+
+    /// Define or replace a style class. Returns the ids of widgets using it.
+    pub fn define_class(&mut self, name: String, style: BoxStyle) -> Vec<String> {
+        let members = self.class_members(&name);
+        self.classes.insert(name, style);
+        members
+    }
+
+    /// Ids of all widgets that list `name` among their classes.
+    pub fn class_members(&self, name: &str) -> Vec<String> {
+        self.widget_classes
+            .iter()
+            .filter(|(_, classes)| classes.iter().any(|class| class == name))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Set the animated properties overlaid on a widget's style for the current frame.
+    pub fn set_animated(&mut self, id: &str, style: BoxStyle) {
+        self.animated.insert(id.to_string(), style);
+    }
+
+    /// Remove a widget's animation overlay once its animation ended.
+    pub fn clear_animated(&mut self, id: &str) {
+        self.animated.remove(id);
+    }
+
+    /// Remove and return a widget's animation overlay.
+    pub fn take_animated(&mut self, id: &str) -> Option<BoxStyle> {
+        self.animated.remove(id)
+    }
+
+    /// Drop all styling state for a removed widget.
+    pub fn forget(&mut self, id: &str) {
+        self.inline.remove(id);
+        self.widget_classes.remove(id);
+        self.animated.remove(id);
+    }
+
+    /// Overlay the widget's classes and inline style onto `style`.
+    fn apply_cascade(&self, id: &str, style: &mut BoxStyle) {
+        if let Some(classes) = self.widget_classes.get(id) {
+            for class_style in classes.iter().filter_map(|name| self.classes.get(name)) {
+                style.merge_from(class_style);
+            }
+        }
+        if let Some(inline) = self.inline.get(id) {
+            style.merge_from(inline);
+        }
+        if let Some(animated) = self.animated.get(id) {
+            style.merge_from(animated);
+        }
+    }
+
+    /// Replace variable references with their values, then resolve palette names.
+    /// References that cannot be resolved leave the property as the cascade set it.
+    pub fn resolve(&self, style: &mut BoxStyle) {
+        for (property, expression) in std::mem::take(&mut style.var_refs) {
+            let Some(value) = self.resolve_expression(&expression) else {
+                eprintln!("[UI] Unresolved style variable {expression} for '{property}'");
+                continue;
+            };
+            match BoxStyle::from_property(&property, &value) {
+                Ok(resolved) => style.merge_from(&resolved),
+                Err(e) => eprintln!(
+                    "[UI] Style variable {expression} = '{value}' is invalid for '{property}': {e}"
+                ),
+            }
+        }
+        self.theme.resolve_palette(style);
+    }
+
+    /// Cascaded style of a widget before variable resolution.
+    fn cascaded_style(&self, id: &str, kind: Option<&WidgetKind>) -> BoxStyle {
+        let mut style = match kind {
+            Some(kind) => self.theme.widget_style(kind),
+            None => self.theme.root_style(),
+        };
+        self.apply_cascade(id, &mut style);
+        style
+    }
+
+    /// Whether the widget's style depends on any of the given variables.
+    /// `kind` is `None` for the root container.
+    pub fn references_any(
+        &self,
+        id: &str,
+        kind: Option<&WidgetKind>,
+        names: &HashSet<String>,
+    ) -> bool {
+        self.cascaded_style(id, kind)
+            .var_refs
+            .values()
+            .flat_map(|expression| referenced_var_names(expression))
+            .any(|name| names.contains(name))
+    }
+
+    /// Effective style of a widget after the full cascade. `inherited` holds the
+    /// parent's computed text styles (see `BoxStyle::inherited_text`).
+    pub fn computed_style(&self, id: &str, kind: &WidgetKind, inherited: &BoxStyle) -> BoxStyle {
+        let mut style = self.theme.widget_style(kind);
+        style.merge_from(inherited);
+        self.apply_cascade(id, &mut style);
+        self.resolve(&mut style);
+        style
+    }
+
+    /// Effective style of the root container.
+    pub fn computed_root_style(&self) -> BoxStyle {
+        let mut style = self.cascaded_style(ROOT_STYLE_KEY, None);
+        self.resolve(&mut style);
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cascade_order() {
+        let mut registry = StyleRegistry::new();
+        registry.define_class(
+            "card".to_string(),
+            BoxStyle {
+                corner_radius: Some(8.0),
+                gap: Some(4.0),
+                ..Default::default()
+            },
+        );
+        registry.define_class(
+            "dense".to_string(),
+            BoxStyle {
+                gap: Some(2.0),
+                ..Default::default()
+            },
+        );
+        registry.set_classes("panel", vec!["card".to_string(), "dense".to_string()]);
+        registry.merge_inline(
+            "panel",
+            &BoxStyle {
+                corner_radius: Some(12.0),
+                ..Default::default()
+            },
+        );
+
+        let style = registry.computed_style("panel", &WidgetKind::Flex, &BoxStyle::default());
+        // Later classes override earlier ones, inline overrides classes
+        assert_eq!(style.gap, Some(2.0));
+        assert_eq!(style.corner_radius, Some(12.0));
+    }
+
+    #[test]
+    fn test_define_class_reports_members() {
+        let mut registry = StyleRegistry::new();
+        registry.set_classes("a", vec!["muted".to_string()]);
+        registry.set_classes("b", vec!["other".to_string()]);
+
+        let members = registry.define_class("muted".to_string(), BoxStyle::default());
+        assert_eq!(members, vec!["a".to_string()]);
+
+        registry.forget("a");
+        assert!(registry.class_members("muted").is_empty());
+    }
+
+    #[test]
+    fn test_variables_resolve_and_track_dependents() {
+        let mut registry = StyleRegistry::new();
+        registry.merge_inline(
+            "title",
+            &BoxStyle::from_property("fontSize", "var(--title-size, 20)").unwrap(),
+        );
+        registry.merge_inline(
+            "card",
+            &BoxStyle::from_property("background", "var(--surface)").unwrap(),
+        );
+
+        // Fallback applies until the variable is defined
+        let style = registry.computed_style("title", &WidgetKind::Label, &BoxStyle::default());
+        assert_eq!(style.font_size, Some(20.0));
+
+        let mut vars = HashMap::new();
+        vars.insert("--accent".to_string(), Some("#ff0000".to_string()));
+        vars.insert("--surface".to_string(), Some("var(--accent)".to_string()));
+        registry.set_variables(vars);
+
+        let style = registry.computed_style("card", &WidgetKind::Flex, &BoxStyle::default());
+        assert!(matches!(
+            style.background,
+            Some(crate::ipc::ColorValue::Rgba {
+                r: 255,
+                g: 0,
+                b: 0,
+                ..
+            })
+        ));
+
+        // Changing `accent` also marks `surface`, which `card` references
+        let mut vars = HashMap::new();
+        vars.insert("accent".to_string(), Some("#00ff00".to_string()));
+        let changed = registry.set_variables(vars);
+        assert!(changed.contains("surface"));
+        assert!(registry.references_any("card", Some(&WidgetKind::Flex), &changed));
+        assert!(!registry.references_any("title", Some(&WidgetKind::Label), &changed));
+    }
+}
@@ -1,4 +1,5 @@
 use masonry::core::{PropertySet, StyleProperty};
+use masonry::kurbo::{Affine, Size, Vec2};
 use masonry::layout::{Dim, Length};
 use masonry::parley::style::{
     FontFamily, FontStack, FontStyle, FontWeight, GenericFamily, LineHeight,
@@ -11,7 +12,10 @@ use masonry::properties::{
 };
 use masonry::widgets::Flex;
 
-use crate::ipc::{BoxStyle, ColorValue, CrossAlign, FontStyleValue, MainAlign, PaddingValue};
+use crate::ipc::{
+    BoxStyle, ColorValue, CrossAlign, FontStyleValue, MainAlign, PaddingValue, TransformOrigin,
+    TransformValue,
+};
 
 // ── Color conversion helper ──
 
@@ -74,6 +78,41 @@ pub fn build_text_styles(style: &BoxStyle) -> Vec<StyleProperty> {
     props
 }
 
+/// Text styles that put the properties `style` leaves unset back to the
+/// label defaults. Applied after [`build_text_styles`] when restyling an
+/// existing widget, which would otherwise keep a removed property's old value.
+pub fn text_style_resets(style: &BoxStyle) -> Vec<StyleProperty> {
+    let mut props = Vec::new();
+
+    if style.font_size.is_none() {
+        props.push(StyleProperty::FontSize(masonry::theme::TEXT_SIZE_NORMAL));
+    }
+    if style.font_weight.is_none() {
+        props.push(StyleProperty::FontWeight(FontWeight::NORMAL));
+    }
+    if style.font_style.is_none() {
+        props.push(StyleProperty::FontStyle(FontStyle::Normal));
+    }
+    if style.letter_spacing.is_none() {
+        props.push(StyleProperty::LetterSpacing(0.0));
+    }
+    if style.line_height.is_none() {
+        props.push(StyleProperty::LineHeight(LineHeight::default()));
+    }
+    if style.word_spacing.is_none() {
+        props.push(StyleProperty::WordSpacing(0.0));
+    }
+    // `build_text_styles` only ever turns these on
+    if style.underline != Some(true) {
+        props.push(StyleProperty::Underline(false));
+    }
+    if style.strikethrough != Some(true) {
+        props.push(StyleProperty::Strikethrough(false));
+    }
+
+    props
+}
+
 /// Build a Properties set with box-model styling
 pub fn build_box_properties(style: &BoxStyle) -> PropertySet {
     let mut props = PropertySet::new();
@@ -139,8 +178,49 @@ pub fn build_box_properties(style: &BoxStyle) -> PropertySet {
     props
 }
 
+/// A box-model property a style can set on a widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxProp {
+    Color,
+    Background,
+    BorderColor,
+    HoverBorderColor,
+    BorderWidth,
+    CornerRadius,
+    Padding,
+    Gap,
+    Dimensions,
+}
+
+/// Box properties `style` leaves unset. Restyling removes them from the
+/// widget, so it falls back to its theme defaults instead of keeping the value
+/// an earlier style (or animation frame) gave it.
+pub fn unset_box_props(style: &BoxStyle) -> Vec<BoxProp> {
+    [
+        (style.color.is_none(), BoxProp::Color),
+        (style.background.is_none(), BoxProp::Background),
+        (style.border_color.is_none(), BoxProp::BorderColor),
+        (
+            style.hover_border_color.is_none(),
+            BoxProp::HoverBorderColor,
+        ),
+        (style.border_width.is_none(), BoxProp::BorderWidth),
+        (style.corner_radius.is_none(), BoxProp::CornerRadius),
+        (style.padding.is_none(), BoxProp::Padding),
+        (style.gap.is_none(), BoxProp::Gap),
+        (
+            style.width.is_none() && style.height.is_none(),
+            BoxProp::Dimensions,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(unset, prop)| unset.then_some(prop))
+    .collect()
+}
+
 /// Apply box-model style properties to an existing widget via insert_prop.
 /// Works on any WidgetMut that implements HasProperty for the relevant properties.
+/// See [`unset_box_props`] for the properties it removes.
 pub fn apply_box_props_to_widget(
     widget: &mut masonry::core::WidgetMut<'_, impl masonry::core::Widget>,
     style: &BoxStyle,
@@ -202,37 +282,102 @@ pub fn apply_box_props_to_widget(
         }
         (None, None) => {}
     }
+
+    for prop in unset_box_props(style) {
+        match prop {
+            BoxProp::Color => {
+                widget.remove_prop::<ContentColor>();
+            }
+            BoxProp::Background => {
+                widget.remove_prop::<Background>();
+            }
+            BoxProp::BorderColor => {
+                widget.remove_prop::<BorderColor>();
+            }
+            BoxProp::HoverBorderColor => {
+                widget.remove_prop::<HoveredBorderColor>();
+            }
+            BoxProp::BorderWidth => {
+                widget.remove_prop::<BorderWidth>();
+            }
+            BoxProp::CornerRadius => {
+                widget.remove_prop::<CornerRadius>();
+            }
+            BoxProp::Padding => {
+                widget.remove_prop::<Padding>();
+            }
+            BoxProp::Gap => {
+                widget.remove_prop::<Gap>();
+            }
+            BoxProp::Dimensions => {
+                widget.remove_prop::<Dimensions>();
+            }
+        }
+    }
+}
+
+// ── Transforms ──
+
+/// Affine for a style transform applied around `origin` of a widget of `size`.
+pub fn transform_affine(
+    transform: &TransformValue,
+    origin: &TransformOrigin,
+    size: Size,
+) -> Affine {
+    let origin = Vec2::new(origin.x.resolve(size.width), origin.y.resolve(size.height));
+    Affine::translate(origin)
+        * Affine::translate((transform.translate_x, transform.translate_y))
+        * Affine::rotate(transform.rotate.to_radians())
+        * Affine::skew(
+            transform.skew_x.to_radians().tan(),
+            transform.skew_y.to_radians().tan(),
+        )
+        * Affine::scale_non_uniform(transform.scale_x, transform.scale_y)
+        * Affine::translate(-origin)
 }
 
+/// Alignments of a Flex created without alignment styles.
+pub const FLEX_ALIGNMENT: (CrossAxisAlignment, MainAxisAlignment) =
+    (CrossAxisAlignment::Center, MainAxisAlignment::Start);
+/// Alignments of a button's inner Flex: content centered both ways.
+pub const BUTTON_FLEX_ALIGNMENT: (CrossAxisAlignment, MainAxisAlignment) =
+    (CrossAxisAlignment::Center, MainAxisAlignment::Center);
+/// Alignments of the root Flex, which stretches children to the window width.
+pub const ROOT_FLEX_ALIGNMENT: (CrossAxisAlignment, MainAxisAlignment) =
+    (CrossAxisAlignment::Stretch, MainAxisAlignment::Start);
+
 /// Apply style to a Flex widget (root or otherwise). Handles box props + flex-specific props.
-pub fn apply_flex_style(flex: &mut masonry::core::WidgetMut<'_, Flex>, style: &BoxStyle) {
+pub fn apply_flex_style(
+    flex: &mut masonry::core::WidgetMut<'_, Flex>,
+    style: &BoxStyle,
+    unset: (CrossAxisAlignment, MainAxisAlignment),
+) {
     apply_box_props_to_widget(flex, style);
 
-    if let Some(ref ca) = style.cross_axis_alignment {
-        Flex::set_cross_axis_alignment(
-            flex,
-            match ca {
-                CrossAlign::Start => CrossAxisAlignment::Start,
-                CrossAlign::Center => CrossAxisAlignment::Center,
-                CrossAlign::End => CrossAxisAlignment::End,
-                CrossAlign::Fill => CrossAxisAlignment::Stretch,
-                CrossAlign::Baseline => CrossAxisAlignment::Start,
-            },
-        );
-    }
-    if let Some(ref ma) = style.main_axis_alignment {
-        Flex::set_main_axis_alignment(
-            flex,
-            match ma {
-                MainAlign::Start => MainAxisAlignment::Start,
-                MainAlign::Center => MainAxisAlignment::Center,
-                MainAlign::End => MainAxisAlignment::End,
-                MainAlign::SpaceBetween => MainAxisAlignment::SpaceBetween,
-                MainAlign::SpaceAround => MainAxisAlignment::SpaceAround,
-                MainAlign::SpaceEvenly => MainAxisAlignment::SpaceEvenly,
-            },
-        );
-    }
+    // Alignments the style leaves unset go back to the ones the widget was created with
+    Flex::set_cross_axis_alignment(
+        flex,
+        match style.cross_axis_alignment {
+            Some(CrossAlign::Start) => CrossAxisAlignment::Start,
+            Some(CrossAlign::Center) => CrossAxisAlignment::Center,
+            Some(CrossAlign::End) => CrossAxisAlignment::End,
+            Some(CrossAlign::Fill) => CrossAxisAlignment::Stretch,
+            Some(CrossAlign::Baseline) => CrossAxisAlignment::Start,
+            None => unset.0,
+        },
+    );
+    Flex::set_main_axis_alignment(
+        flex,
+        match style.main_axis_alignment {
+            Some(MainAlign::Start) => MainAxisAlignment::Start,
+            Some(MainAlign::Center) => MainAxisAlignment::Center,
+            Some(MainAlign::End) => MainAxisAlignment::End,
+            Some(MainAlign::SpaceBetween) => MainAxisAlignment::SpaceBetween,
+            Some(MainAlign::SpaceAround) => MainAxisAlignment::SpaceAround,
+            Some(MainAlign::SpaceEvenly) => MainAxisAlignment::SpaceEvenly,
+            None => unset.1,
+        },
+    );
 
     if let Some(true) = style.must_fill_main_axis {
         let stretch_dims = match style.direction {
@@ -307,6 +452,27 @@ mod tests {
         assert!(matches!(props[0], StyleProperty::FontStack(_)));
     }
 
+    #[test]
+    fn test_text_style_resets_cover_unset_properties() {
+        let style = BoxStyle {
+            font_size: Some(24.0),
+            underline: Some(true),
+            ..Default::default()
+        };
+        let resets = text_style_resets(&style);
+
+        assert!(
+            !resets
+                .iter()
+                .any(|p| matches!(p, StyleProperty::FontSize(_) | StyleProperty::Underline(_)))
+        );
+        assert!(resets.contains(&StyleProperty::FontWeight(FontWeight::NORMAL)));
+        assert!(resets.contains(&StyleProperty::Strikethrough(false)));
+        // Everything set by `build_text_styles` is reset when unset (the font
+        // stack always is set)
+        assert_eq!(text_style_resets(&BoxStyle::default()).len(), 8);
+    }
+
     #[test]
     fn test_build_box_properties() {
         let style = BoxStyle {
@@ -349,4 +515,24 @@ mod tests {
         let defaults = default_text_style_props();
         assert_eq!(defaults.len(), 2);
     }
+
+    #[test]
+    fn test_transform_affine_keeps_origin_fixed() {
+        let transform = TransformValue {
+            rotate: 90.0,
+            scale_x: 2.0,
+            scale_y: 2.0,
+            ..Default::default()
+        };
+        let affine = transform_affine(
+            &transform,
+            &TransformOrigin::default(),
+            Size::new(40.0, 20.0),
+        );
+        let center = affine * masonry::kurbo::Point::new(20.0, 10.0);
+        assert!((center.x - 20.0).abs() < 1e-9 && (center.y - 10.0).abs() < 1e-9);
+        // The top-left corner is 20px left and 10px up from the center; doubled and rotated
+        let corner = affine * masonry::kurbo::Point::ORIGIN;
+        assert!((corner.x - 40.0).abs() < 1e-9 && (corner.y - -30.0).abs() < 1e-9);
+    }
 }
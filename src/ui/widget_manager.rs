@@ -1,12 +1,20 @@
-use crate::ipc::WidgetKind;
+use super::animator::Animator;
+use super::geometry::IntersectionObserver;
+use super::style_registry::StyleRegistry;
+use super::widgets::root_host::RootHost;
+use crate::ipc::{BoxStyle, CursorStyle, TransformOrigin, TransformValue, WidgetKind};
 use masonry::core::WidgetId;
 use masonry::core::WidgetTag;
+use masonry::kurbo::Size;
 use masonry::widgets::Flex;
 use std::collections::HashMap;
 
 /// Tag for the root Flex container that holds all dynamically created widgets.
 pub const ROOT_FLEX_TAG: WidgetTag<Flex> = WidgetTag::named("root_flex");
 
+/// Tag for the window root wrapping the root Flex.
+pub const ROOT_HOST_TAG: WidgetTag<RootHost> = WidgetTag::named("root_host");
+
 /// Information tracked for each JS-created widget.
 #[derive(Debug, Clone)]
 pub struct WidgetInfo {
@@ -26,6 +34,22 @@ pub struct WidgetManager {
     pub widgets: HashMap<String, WidgetInfo>,
     /// Maps a parent ID (or "__root__") to an ordered list of child IDs.
     pub parent_to_children: HashMap<String, Vec<String>>,
+    /// Styling inputs (theme, inline styles) used to recompute widget styles.
+    pub styles: StyleRegistry,
+    /// Keyframe animations running on widgets.
+    pub animations: Animator,
+    /// Non-identity transforms by masonry id. They are re-applied after layout
+    /// because the transform origin depends on the widget size.
+    pub transforms: HashMap<WidgetId, (TransformValue, TransformOrigin)>,
+    /// Widgets observed for size changes, with the size last reported to JS
+    /// (None until the first report).
+    pub resize_observers: HashMap<String, Option<Size>>,
+    /// Widgets observed for visibility changes.
+    pub intersection_observers: HashMap<String, IntersectionObserver>,
+    /// `cursor` of each widget whose computed style sets one.
+    pub cursors: HashMap<String, CursorStyle>,
+    /// Window-wide cursor set with `SetCursor`; wins over `cursors`.
+    pub cursor_override: Option<CursorStyle>,
 }
 
 impl WidgetManager {
@@ -35,6 +59,13 @@ impl WidgetManager {
         Self {
             widgets: HashMap::new(),
             parent_to_children,
+            styles: StyleRegistry::new(),
+            animations: Animator::new(),
+            transforms: HashMap::new(),
+            resize_observers: HashMap::new(),
+            intersection_observers: HashMap::new(),
+            cursors: HashMap::new(),
+            cursor_override: None,
         }
     }
 
@@ -62,6 +93,113 @@ impl WidgetManager {
         self.current_child_count(parent_key)
     }
 
+    /// Ids of every widget below `parent_key` (a widget id or "__root__"), depth first.
+    pub fn descendants(&self, parent_key: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        self.collect_descendants(parent_key, &mut out);
+        out
+    }
+
+    /// Text styles inherited by the children of `parent_id` (None means the root).
+    pub fn inherited_style(&self, parent_id: Option<&str>) -> BoxStyle {
+        match parent_id.and_then(|id| self.widgets.get_key_value(id)) {
+            Some((id, info)) => self.computed_style(id, info).inherited_text(),
+            None => self.styles.computed_root_style().inherited_text(),
+        }
+    }
+
+    /// Effective style of a registered widget, including inherited text styles.
+    pub fn computed_style(&self, id: &str, info: &WidgetInfo) -> BoxStyle {
+        let inherited = self.inherited_style(info.parent_id.as_deref());
+        self.styles.computed_style(id, &info.kind, &inherited)
+    }
+
+    /// Advance running animations by `delta_ms` and update the animated style
+    /// overlays to match. Returns the widgets to restyle, each with whether
+    /// the change reaches its descendants (inherited text properties), and
+    /// the widgets whose explicit animation finished.
+    pub fn advance_animations(&mut self, delta_ms: f64) -> (Vec<(String, bool)>, Vec<String>) {
+        let step = self.animations.advance(delta_ms);
+        let mut restyle = Vec::new();
+        for (id, style) in step.frames {
+            restyle.push((id.clone(), style.sets_inherited_text()));
+            self.styles.set_animated(&id, style);
+        }
+        // Nothing animates these anymore: back to the plain computed style
+        for id in step.settled {
+            self.styles.clear_animated(&id);
+            restyle.push((id, true));
+        }
+        (restyle, step.finished)
+    }
+
+    /// Record the transform of a widget's computed style.
+    /// Returns true if it differs from the one recorded before.
+    pub fn track_transform(&mut self, widget_id: WidgetId, style: &BoxStyle) -> bool {
+        let transform = style
+            .transform
+            .filter(|transform| !transform.is_identity())
+            .map(|transform| (transform, style.transform_origin.unwrap_or_default()));
+        let previous = match transform {
+            Some(transform) => self.transforms.insert(widget_id, transform),
+            None => self.transforms.remove(&widget_id),
+        };
+        previous != transform
+    }
+
+    /// Record the cursor of a widget's computed style.
+    /// Returns true if it differs from the one recorded before.
+    pub fn track_cursor(&mut self, id: &str, style: &BoxStyle) -> bool {
+        let previous = match style.cursor {
+            Some(cursor) => self.cursors.insert(id.to_string(), cursor),
+            None => self.cursors.remove(id),
+        };
+        previous != style.cursor
+    }
+
+    /// Cursor shown over widget `id`: its own or its nearest styled ancestor's.
+    /// Text inputs keep the text cursor masonry gives them unless they set one.
+    pub fn cursor_at(&self, id: &str) -> Option<CursorStyle> {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if let Some(cursor) = self.cursors.get(id) {
+                return Some(*cursor);
+            }
+            let info = self.widgets.get(id)?;
+            if matches!(info.kind, WidgetKind::TextInput | WidgetKind::TextArea) {
+                return None;
+            }
+            current = info.parent_id.as_deref();
+        }
+        None
+    }
+
+    /// Whether the driver needs to hear where the pointer is, to show cursors.
+    pub fn needs_pointer_reports(&self) -> bool {
+        !self.cursors.is_empty() || self.cursor_override.is_some()
+    }
+
+    /// Whether the driver needs to hear about layout passes, to re-apply
+    /// transforms or report size changes.
+    pub fn needs_layout_reports(&self) -> bool {
+        !self.transforms.is_empty()
+            || !self.resize_observers.is_empty()
+            || self.needs_viewport_reports()
+    }
+
+    /// Whether the driver needs to hear about scroll input.
+    pub fn needs_viewport_reports(&self) -> bool {
+        !self.intersection_observers.is_empty()
+    }
+
+    /// Drop observers of a removed widget, and those using it as their root.
+    fn forget_observers(&mut self, id: &str) {
+        self.resize_observers.remove(id);
+        self.intersection_observers.remove(id);
+        self.intersection_observers
+            .retain(|_, observer| observer.root_id.as_deref() != Some(id));
+    }
+
     fn collect_descendants(&self, parent_id: &str, out: &mut Vec<String>) {
         if let Some(children) = self.parent_to_children.get(parent_id) {
             for child_id in children {
@@ -97,12 +235,23 @@ impl WidgetManager {
         let mut descendants = Vec::new();
         self.collect_descendants(id, &mut descendants);
         for child_id in descendants {
-            self.widgets.remove(&child_id);
+            if let Some(info) = self.widgets.remove(&child_id) {
+                self.transforms.remove(&info.widget_id);
+            }
             self.parent_to_children.remove(&child_id);
+            self.forget_observers(&child_id);
+            self.cursors.remove(&child_id);
+            self.styles.forget(&child_id);
+            self.animations.forget(&child_id);
         }
 
         // Remove the sublist for the widget
         self.parent_to_children.remove(id);
+        self.transforms.remove(&removed.widget_id);
+        self.forget_observers(id);
+        self.cursors.remove(id);
+        self.styles.forget(id);
+        self.animations.forget(id);
 
         self.recompute_parent_state(&parent_key);
 
@@ -238,4 +387,223 @@ mod tests {
         assert_eq!(manager.next_child_index("__root__"), 1);
         assert_eq!(manager.current_child_count("__root__"), 1);
     }
+
+    #[test]
+    fn test_text_styles_inherit_from_ancestors() {
+        let mut manager = WidgetManager::new();
+        manager.register_widget(
+            "outer".to_string(),
+            WidgetInfo {
+                widget_id: WidgetId::next(),
+                kind: WidgetKind::Flex,
+                parent_id: None,
+                child_index: 0,
+            },
+        );
+        manager.register_widget(
+            "inner".to_string(),
+            WidgetInfo {
+                widget_id: WidgetId::next(),
+                kind: WidgetKind::Flex,
+                parent_id: Some("outer".to_string()),
+                child_index: 0,
+            },
+        );
+        manager.register_widget(
+            "title".to_string(),
+            WidgetInfo {
+                widget_id: WidgetId::next(),
+                kind: WidgetKind::Label,
+                parent_id: Some("inner".to_string()),
+                child_index: 0,
+            },
+        );
+
+        manager.styles.merge_inline(
+            "outer",
+            &BoxStyle {
+                font_family: Some("Inter".to_string()),
+                font_size: Some(20.0),
+                gap: Some(8.0),
+                ..Default::default()
+            },
+        );
+        manager.styles.merge_inline(
+            "title",
+            &BoxStyle {
+                font_size: Some(12.0),
+                ..Default::default()
+            },
+        );
+
+        let info = manager.widgets.get("title").unwrap().clone();
+        let style = manager.computed_style("title", &info);
+        assert_eq!(style.font_family.as_deref(), Some("Inter"));
+        // The widget's own style wins over inherited values
+        assert_eq!(style.font_size, Some(12.0));
+        // Only text properties are inherited
+        assert!(style.gap.is_none());
+
+        assert_eq!(
+            manager.descendants("outer"),
+            vec!["inner".to_string(), "title".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_finished_animation_unsets_property_missing_from_base_style() {
+        use crate::ipc::{AnimationDirection, ColorValue, Easing, Keyframe};
+        use crate::ui::styles::{BoxProp, unset_box_props};
+
+        let mut manager = WidgetManager::new();
+        let info = WidgetInfo {
+            widget_id: WidgetId::next(),
+            kind: WidgetKind::Label,
+            parent_id: None,
+            child_index: 0,
+        };
+        manager.register_widget("flash".to_string(), info.clone());
+        let base = manager.computed_style("flash", &info);
+        assert!(base.background.is_none());
+
+        // Ends on a background the widget's own style never sets
+        manager.animations.start(
+            "flash".to_string(),
+            vec![Keyframe {
+                offset: Some(1.0),
+                style: BoxStyle {
+                    background: ColorValue::parse("#ff0000"),
+                    ..Default::default()
+                },
+            }],
+            &base,
+            100.0,
+            Easing::Linear,
+            1.0,
+            AnimationDirection::Normal,
+        );
+        let (restyle, finished) = manager.advance_animations(60.0);
+        assert_eq!(restyle, [("flash".to_string(), false)]);
+        assert!(finished.is_empty());
+        let style = manager.computed_style("flash", &info);
+        assert!(style.background.is_some());
+        assert!(!unset_box_props(&style).contains(&BoxProp::Background));
+
+        let (restyle, finished) = manager.advance_animations(60.0);
+        assert_eq!(restyle, [("flash".to_string(), true)]);
+        assert_eq!(finished, ["flash".to_string()]);
+        // The painted background must be removed, not left at the last frame
+        let style = manager.computed_style("flash", &info);
+        assert!(style.background.is_none());
+        assert!(unset_box_props(&style).contains(&BoxProp::Background));
+    }
+
+    #[test]
+    fn test_track_transform_reports_changes() {
+        let mut manager = WidgetManager::new();
+        let widget_id = WidgetId::next();
+        let rotated = BoxStyle {
+            transform: TransformValue::parse("rotate(90deg)"),
+            ..Default::default()
+        };
+
+        assert!(manager.track_transform(widget_id, &rotated));
+        assert!(!manager.track_transform(widget_id, &rotated));
+        assert_eq!(manager.transforms[&widget_id].1, TransformOrigin::default());
+
+        // An identity transform clears the entry
+        let identity = BoxStyle {
+            transform: TransformValue::parse("none"),
+            ..Default::default()
+        };
+        assert!(manager.track_transform(widget_id, &identity));
+        assert!(manager.transforms.is_empty());
+        assert!(!manager.track_transform(widget_id, &BoxStyle::default()));
+    }
+
+    #[test]
+    fn test_remove_subtree_drops_resize_observers() {
+        let mut manager = WidgetManager::new();
+        for (id, parent_id) in [("panel", None), ("chart", Some("panel"))] {
+            manager.register_widget(
+                id.to_string(),
+                WidgetInfo {
+                    widget_id: WidgetId::next(),
+                    kind: WidgetKind::Container,
+                    parent_id: parent_id.map(str::to_string),
+                    child_index: 0,
+                },
+            );
+        }
+        manager.resize_observers.insert("chart".to_string(), None);
+        assert!(manager.needs_layout_reports());
+
+        manager.remove_widget_subtree("panel");
+        assert!(manager.resize_observers.is_empty());
+        assert!(!manager.needs_layout_reports());
+    }
+
+    #[test]
+    fn test_removing_root_drops_intersection_observers() {
+        let mut manager = WidgetManager::new();
+        for (id, parent_id) in [("list", None), ("row", Some("list")), ("footer", None)] {
+            manager.register_widget(
+                id.to_string(),
+                WidgetInfo {
+                    widget_id: WidgetId::next(),
+                    kind: WidgetKind::Portal,
+                    parent_id: parent_id.map(str::to_string),
+                    child_index: 0,
+                },
+            );
+        }
+        manager.intersection_observers.insert(
+            "row".to_string(),
+            IntersectionObserver::new(Some("list".to_string()), 0.5),
+        );
+        manager
+            .intersection_observers
+            .insert("footer".to_string(), IntersectionObserver::new(None, 0.0));
+        assert!(manager.needs_viewport_reports());
+
+        manager.remove_widget_subtree("list");
+        assert_eq!(
+            manager.intersection_observers.keys().collect::<Vec<_>>(),
+            vec!["footer"]
+        );
+    }
+
+    #[test]
+    fn test_cursor_comes_from_nearest_styled_ancestor() {
+        let mut manager = WidgetManager::new();
+        for (id, kind, parent_id) in [
+            ("card", WidgetKind::Hoverable, None),
+            ("title", WidgetKind::Label, Some("card")),
+            ("note", WidgetKind::TextInput, Some("card")),
+        ] {
+            manager.register_widget(
+                id.to_string(),
+                WidgetInfo {
+                    widget_id: WidgetId::next(),
+                    kind,
+                    parent_id: parent_id.map(str::to_string),
+                    child_index: 0,
+                },
+            );
+        }
+        let pointer = BoxStyle {
+            cursor: Some(CursorStyle::Pointer),
+            ..Default::default()
+        };
+        assert!(manager.track_cursor("card", &pointer));
+        assert!(!manager.track_cursor("card", &pointer));
+        assert!(manager.needs_pointer_reports());
+
+        assert_eq!(manager.cursor_at("title"), Some(CursorStyle::Pointer));
+        // Text inputs keep their own cursor
+        assert_eq!(manager.cursor_at("note"), None);
+
+        manager.remove_widget_subtree("card");
+        assert!(!manager.needs_pointer_reports());
+    }
 }
@@ -2,7 +2,7 @@ use masonry::app::RenderRoot;
 use masonry::core::{NewWidget, WidgetOptions};
 use masonry::widgets::Checkbox;
 
-use crate::ipc::{BoxStyle, WidgetData, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetData, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -17,7 +17,7 @@ pub fn create(
     style: Option<BoxStyle>,
     data: Option<WidgetData>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
     let label_text = text.unwrap_or_default();
 
@@ -33,21 +33,22 @@ pub fn create(
     let new_widget = NewWidget::new_with(checkbox, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind: WidgetKind::Checkbox,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Checkbox,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
@@ -1,11 +1,8 @@
 use masonry::app::RenderRoot;
-use masonry::core::{NewWidget, PropertySet, StyleProperty, WidgetOptions};
-use masonry::parley::style::{FontFamily, FontStack, GenericFamily};
-use masonry::peniko::Color;
-use masonry::properties::ContentColor;
+use masonry::core::{NewWidget, StyleProperty, WidgetOptions};
 use masonry::widgets::Label;
 
-use crate::ipc::{BoxStyle, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetKind};
 use crate::ui::styles::{build_box_properties, build_text_styles};
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -20,46 +17,39 @@ pub fn create(
     text: Option<String>,
     style: Option<BoxStyle>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
     // Custom widgets default to a Label for now
     let label_text = text.unwrap_or_else(|| format!("[{:?}]", kind));
 
-    let text_styles: Vec<StyleProperty> = style_ref.map(build_text_styles).unwrap_or_else(|| {
-        vec![
-            StyleProperty::FontSize(16.0),
-            StyleProperty::FontStack(FontStack::Single(FontFamily::Generic(
-                GenericFamily::SansSerif,
-            ))),
-        ]
-    });
+    let text_styles: Vec<StyleProperty> = style_ref.map(build_text_styles).unwrap_or_default();
 
     let mut label = Label::new(label_text);
     for s in &text_styles {
         label = label.with_style(s.clone());
     }
 
-    let props = style_ref
-        .map(build_box_properties)
-        .unwrap_or_else(|| PropertySet::new().with(ContentColor::new(Color::WHITE)));
+    // Defaults (e.g. text color) come from the theme via the computed style.
+    let props = style_ref.map(build_box_properties).unwrap_or_default();
     let new_widget = NewWidget::new_with(label, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
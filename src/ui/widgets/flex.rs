@@ -5,7 +5,9 @@ use masonry::properties::Dimensions;
 use masonry::properties::types::{CrossAxisAlignment, MainAxisAlignment};
 use masonry::widgets::Flex;
 
-use crate::ipc::{BoxStyle, CrossAlign, FlexDirection, MainAlign, WidgetData, WidgetKind};
+use crate::ipc::{
+    BoxStyle, CrossAlign, FlexDirection, MainAlign, VellumError, WidgetData, WidgetKind,
+};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -20,7 +22,7 @@ pub fn create(
     style: Option<BoxStyle>,
     _data: Option<WidgetData>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
 
     let dir = style_ref.and_then(|s| s.direction.clone());
@@ -68,22 +70,23 @@ pub fn create(
     let new_widget = NewWidget::new_with(new_flex, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        // Flex/Container can have children, so init child count
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    // Flex/Container can have children, so init child count
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
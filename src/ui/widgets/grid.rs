@@ -2,7 +2,7 @@ use masonry::app::RenderRoot;
 use masonry::core::{NewWidget, WidgetOptions};
 use masonry::widgets::Flex;
 
-use crate::ipc::{BoxStyle, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -14,7 +14,7 @@ pub fn create(
     parent_id: Option<String>,
     style: Option<BoxStyle>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
     // Grid is not natively supported yet, fallback to Flex column
     let flex = Flex::column();
@@ -23,21 +23,22 @@ pub fn create(
     let new_widget = NewWidget::new_with(flex, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind: WidgetKind::Grid,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Grid,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
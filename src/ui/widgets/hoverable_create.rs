@@ -1,7 +1,7 @@
 use masonry::app::RenderRoot;
 use masonry::core::{NewWidget, WidgetOptions};
 
-use crate::ipc::{BoxStyle, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -15,7 +15,7 @@ pub fn create(
     parent_id: Option<String>,
     style: Option<BoxStyle>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
 
     let hoverable = Hoverable::new_empty();
@@ -24,21 +24,22 @@ pub fn create(
     let new_widget = NewWidget::new_with(hoverable, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind: WidgetKind::Hoverable,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Hoverable,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
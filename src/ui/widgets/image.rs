@@ -4,18 +4,18 @@ use masonry::peniko::{ImageAlphaType, ImageData, ImageFormat};
 use masonry::properties::ObjectFit;
 use masonry::widgets::Image;
 
-use crate::ipc::{BoxStyle, WidgetData, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetData, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
 
 /// Decode raw file bytes (PNG/JPEG/WebP/etc.) into masonry ImageData
-fn decode_image_bytes(data: &[u8]) -> Option<ImageData> {
+fn decode_image_bytes(id: &str, data: &[u8]) -> Result<ImageData, VellumError> {
     match image::load_from_memory(data) {
         Ok(img) => {
             let rgba = img.to_rgba8();
             let (width, height) = rgba.dimensions();
-            Some(ImageData {
+            Ok(ImageData {
                 data: rgba.into_raw().into(),
                 format: ImageFormat::Rgba8,
                 alpha_type: ImageAlphaType::Alpha,
@@ -24,8 +24,12 @@ fn decode_image_bytes(data: &[u8]) -> Option<ImageData> {
             })
         }
         Err(e) => {
-            eprintln!("[UI] Failed to decode image: {}", e);
-            None
+            eprintln!("[UI] Failed to decode image for '{}': {}", id, e);
+            Err(VellumError::DecodeFailed {
+                widget_id: Some(id.to_string()),
+                what: "image data".to_string(),
+                reason: e.to_string(),
+            })
         }
     }
 }
@@ -50,26 +54,19 @@ pub fn create(
     style: Option<BoxStyle>,
     data: Option<WidgetData>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     // Extract image-specific data from WidgetData
     let (image_data_bytes, object_fit_str) = match &data {
         Some(WidgetData::Image { data, object_fit }) => (data.as_slice(), object_fit.clone()),
         _ => {
-            eprintln!(
-                "[UI] Image widget '{}' missing image data in WidgetData",
-                id
-            );
-            return;
+            return Err(VellumError::InvalidArgument {
+                widget_id: Some(id),
+                reason: "Image widget is missing image data".to_string(),
+            });
         }
     };
 
-    let image_data = match decode_image_bytes(image_data_bytes) {
-        Some(d) => d,
-        None => {
-            eprintln!("[UI] Image widget '{}' failed to decode image data", id);
-            return;
-        }
-    };
+    let image_data = decode_image_bytes(&id, image_data_bytes)?;
 
     let object_fit = object_fit_str
         .as_deref()
@@ -88,37 +85,38 @@ pub fn create(
     );
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind: WidgetKind::Image,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Image,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
 
 /// Update an existing Image widget's data
-pub fn update_data(render_root: &mut RenderRoot, widget_id: WidgetId, data: &[u8]) {
-    let image_data = match decode_image_bytes(data) {
-        Some(d) => d,
-        None => {
-            eprintln!("[UI] Failed to decode image data for update");
-            return;
-        }
-    };
+pub fn update_data(
+    render_root: &mut RenderRoot,
+    id: &str,
+    widget_id: WidgetId,
+    data: &[u8],
+) -> Result<(), VellumError> {
+    let image_data = decode_image_bytes(id, data)?;
 
     render_root.edit_widget(widget_id, |mut widget| {
         let mut img = widget.downcast::<Image>();
         Image::set_image_data(&mut img, image_data);
     });
+    Ok(())
 }
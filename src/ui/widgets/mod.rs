@@ -10,6 +10,7 @@ pub mod label;
 pub mod portal;
 pub mod progress_bar;
 pub mod prose;
+pub mod root_host;
 pub mod sized_box;
 pub mod slider;
 pub mod spinner;
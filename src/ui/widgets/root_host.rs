@@ -0,0 +1,247 @@
+use masonry::accesskit::{Node, Role};
+use masonry::core::keyboard::{Key, KeyState, NamedKey};
+use masonry::core::{
+    AccessCtx, ChildrenIds, EventCtx, LayoutCtx, MeasureCtx, NewWidget, PaintCtx, PointerEvent,
+    PropertiesMut, PropertiesRef, RegisterCtx, TextEvent, UpdateCtx, Widget, WidgetMut, WidgetPod,
+};
+use masonry::kurbo::Point;
+use masonry::vello::Scene;
+use masonry_winit::app::MasonryUserEvent;
+
+use crate::ui::global_state::get_event_loop_proxy;
+
+/// Submitted by [`RootHost`] on every animation frame while animations run.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationTick {
+    /// Time since the previous animation frame, in nanoseconds.
+    pub interval_ns: u64,
+}
+
+/// Posted to the driver after each layout pass while `reports_layout` is set.
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutPass;
+
+/// Posted to the driver after input that may have scrolled a portal (wheel,
+/// clicks and drags, scroll keys), while `reports_viewport` is set. Scrolling
+/// moves widgets without a layout pass.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportChange;
+
+/// Posted to the driver when the pointer moves, while `reports_pointer` is
+/// set. Holds the position in window coordinates, or None once the pointer
+/// left the window.
+#[derive(Debug, Clone, Copy)]
+pub struct PointerMoved(pub Option<Point>);
+
+/// Window root wrapping the tagged root Flex.
+///
+/// The driver cannot request animation frames itself, so this widget requests
+/// them while `animating` is set and forwards each one as an [`AnimationTick`].
+/// Layout passes are reported through the event loop proxy, since widgets cannot
+/// submit actions while laying out.
+pub struct RootHost {
+    child: WidgetPod<dyn Widget>,
+    animating: bool,
+    reports_layout: bool,
+    reports_viewport: bool,
+    reports_pointer: bool,
+}
+
+impl RootHost {
+    pub fn new(child: NewWidget<impl Widget + ?Sized>) -> Self {
+        Self {
+            child: child.erased().to_pod(),
+            animating: false,
+            reports_layout: false,
+            reports_viewport: false,
+            reports_pointer: false,
+        }
+    }
+
+    /// Start or stop forwarding animation frames.
+    pub fn set_animating(this: &mut WidgetMut<'_, Self>, animating: bool) {
+        if animating && !this.widget.animating {
+            this.ctx.request_anim_frame();
+        }
+        this.widget.animating = animating;
+    }
+
+    /// Start or stop posting a [`LayoutPass`] after each layout.
+    pub fn set_reports_layout(this: &mut WidgetMut<'_, Self>, reports_layout: bool) {
+        this.widget.reports_layout = reports_layout;
+    }
+
+    /// Start or stop posting a [`ViewportChange`] after scroll input.
+    pub fn set_reports_viewport(this: &mut WidgetMut<'_, Self>, reports_viewport: bool) {
+        this.widget.reports_viewport = reports_viewport;
+    }
+
+    /// Start or stop posting a [`PointerMoved`] on pointer movement.
+    pub fn set_reports_pointer(this: &mut WidgetMut<'_, Self>, reports_pointer: bool) {
+        this.widget.reports_pointer = reports_pointer;
+    }
+
+    fn post_pointer_moved(&self, position: Option<Point>) {
+        // Handled after masonry updated the cursor for the new hovered widget
+        if self.reports_pointer
+            && let Some((proxy, window_id)) = get_event_loop_proxy()
+        {
+            let _ = proxy.send_event(MasonryUserEvent::AsyncAction(
+                window_id,
+                Box::new(PointerMoved(position)),
+            ));
+        }
+    }
+
+    fn post_viewport_change(&self) {
+        // Handled after the current event's passes, once scroll offsets are composed
+        if self.reports_viewport
+            && let Some((proxy, window_id)) = get_event_loop_proxy()
+        {
+            let _ = proxy.send_event(MasonryUserEvent::AsyncAction(
+                window_id,
+                Box::new(ViewportChange),
+            ));
+        }
+    }
+}
+
+/// Keys a focused portal may scroll by.
+fn is_scroll_key(key: &Key) -> bool {
+    match key {
+        Key::Named(named) => matches!(
+            named,
+            NamedKey::ArrowUp
+                | NamedKey::ArrowDown
+                | NamedKey::ArrowLeft
+                | NamedKey::ArrowRight
+                | NamedKey::PageUp
+                | NamedKey::PageDown
+                | NamedKey::Home
+                | NamedKey::End
+        ),
+        Key::Character(text) => text == " ",
+    }
+}
+
+impl Widget for RootHost {
+    type Action = AnimationTick;
+
+    fn accepts_pointer_interaction(&self) -> bool {
+        false
+    }
+
+    fn register_children(&mut self, ctx: &mut RegisterCtx<'_>) {
+        ctx.register_child(&mut self.child);
+    }
+
+    fn on_pointer_event(
+        &mut self,
+        ctx: &mut EventCtx<'_>,
+        _props: &mut PropertiesMut<'_>,
+        event: &PointerEvent,
+    ) {
+        // The root sits at the window origin, so local positions are window positions
+        match event {
+            PointerEvent::Move(update) => {
+                self.post_pointer_moved(Some(ctx.local_position(update.current.position)));
+            }
+            PointerEvent::Leave(..) => self.post_pointer_moved(None),
+            _ => {}
+        }
+
+        // Wheel scrolling, scrollbar track clicks, and scrollbar or touch drags
+        let scrolls = match event {
+            PointerEvent::Scroll(..) | PointerEvent::Down(..) => true,
+            PointerEvent::Move(update) => !update.current.buttons.is_empty(),
+            _ => false,
+        };
+        if scrolls {
+            self.post_viewport_change();
+        }
+    }
+
+    fn on_text_event(
+        &mut self,
+        _ctx: &mut EventCtx<'_>,
+        _props: &mut PropertiesMut<'_>,
+        event: &TextEvent,
+    ) {
+        if let TextEvent::Keyboard(key) = event
+            && key.state == KeyState::Down
+            && is_scroll_key(&key.key)
+        {
+            self.post_viewport_change();
+        }
+    }
+
+    fn on_anim_frame(
+        &mut self,
+        ctx: &mut UpdateCtx<'_>,
+        _props: &mut PropertiesMut<'_>,
+        interval: u64,
+    ) {
+        if self.animating {
+            ctx.submit_action::<<RootHost as Widget>::Action>(AnimationTick {
+                interval_ns: interval,
+            });
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn measure(
+        &mut self,
+        ctx: &mut MeasureCtx<'_>,
+        _props: &PropertiesRef<'_>,
+        axis: masonry::kurbo::Axis,
+        len_req: masonry::layout::LenReq,
+        cross_length: Option<f64>,
+    ) -> f64 {
+        ctx.compute_length(
+            &mut self.child,
+            len_req.into(),
+            masonry::layout::LayoutSize::maybe(axis.cross(), cross_length),
+            axis,
+            cross_length,
+        )
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx<'_>,
+        _props: &PropertiesRef<'_>,
+        size: masonry::kurbo::Size,
+    ) {
+        // The root Flex always fills the window
+        ctx.run_layout(&mut self.child, size);
+        ctx.place_child(&mut self.child, masonry::kurbo::Point::ORIGIN);
+        ctx.derive_baselines(&self.child);
+
+        if self.reports_layout
+            && let Some((proxy, window_id)) = get_event_loop_proxy()
+        {
+            let _ = proxy.send_event(MasonryUserEvent::AsyncAction(
+                window_id,
+                Box::new(LayoutPass),
+            ));
+        }
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx<'_>, _props: &PropertiesRef<'_>, _scene: &mut Scene) {}
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(
+        &mut self,
+        _ctx: &mut AccessCtx<'_>,
+        _props: &PropertiesRef<'_>,
+        _node: &mut Node,
+    ) {
+    }
+
+    fn children_ids(&self) -> ChildrenIds {
+        ChildrenIds::from_slice(&[self.child.id()])
+    }
+}
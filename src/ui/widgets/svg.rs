@@ -1,7 +1,7 @@
 use masonry::app::RenderRoot;
 use masonry::core::{NewWidget, WidgetOptions};
 
-use crate::ipc::{BoxStyle, WidgetData, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetData, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::svg_widget_impl::SvgWidget;
@@ -17,7 +17,7 @@ pub fn create(
     style: Option<BoxStyle>,
     data: Option<WidgetData>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     let style_ref = style.as_ref();
 
     // Extract SVG data from WidgetData, falling back to text
@@ -27,31 +27,35 @@ pub fn create(
     }
     .or_else(|| text.clone());
 
-    if let Some(svg) = svg_data {
-        let props = style_ref.map(build_box_properties).unwrap_or_default();
+    let Some(svg) = svg_data else {
+        return Err(VellumError::InvalidArgument {
+            widget_id: Some(id),
+            reason: "SVG widget is missing svg_data/text payload".to_string(),
+        });
+    };
 
-        let new_widget =
-            NewWidget::new_with(SvgWidget::new(svg), None, WidgetOptions::default(), props);
-        let widget_id = new_widget.id();
+    let props = style_ref.map(build_box_properties).unwrap_or_default();
 
-        if add_to_parent(
-            render_root,
-            widget_manager,
-            &parent_id,
-            new_widget,
-            style_ref.and_then(|s| s.flex),
-        ) {
-            widget_manager.register_widget(
-                id,
-                WidgetInfo {
-                    widget_id,
-                    kind: WidgetKind::Svg,
-                    parent_id: parent_id.clone(),
-                    child_index,
-                },
-            );
-        }
-    } else {
-        eprintln!("[UI] SVG widget '{}' missing svg_data/text payload", id);
-    }
+    let new_widget =
+        NewWidget::new_with(SvgWidget::new(svg), None, WidgetOptions::default(), props);
+    let widget_id = new_widget.id();
+
+    add_to_parent(
+        render_root,
+        widget_manager,
+        &id,
+        &parent_id,
+        new_widget,
+        style_ref.and_then(|s| s.flex),
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Svg,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
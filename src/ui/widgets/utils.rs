@@ -2,21 +2,22 @@ use masonry::app::RenderRoot;
 use masonry::core::NewWidget;
 use masonry::widgets::{ChildAlignment, Flex, SizedBox, ZStack};
 
-use crate::ipc::WidgetKind;
+use crate::ipc::{VellumError, WidgetKind};
 use crate::ui::widget_manager::{ROOT_FLEX_TAG, WidgetManager};
 
 use super::hoverable::Hoverable;
 
 /// Helper: add a widget to the root flex or a named parent flex.
 /// If `flex_factor` is Some, the child is added with that flex grow factor.
-/// Returns false if the parent was not found or is not a container.
+/// Fails if the parent was not found or cannot hold `child_id`.
 pub fn add_to_parent(
     render_root: &mut RenderRoot,
     widget_manager: &WidgetManager,
+    child_id: &str,
     parent_id: &Option<String>,
     new_widget: NewWidget<impl masonry::core::Widget>,
     flex_factor: Option<f64>,
-) -> bool {
+) -> Result<(), VellumError> {
     let parent_key = parent_id.as_deref().unwrap_or("__root__");
 
     if parent_id.is_none() {
@@ -27,7 +28,7 @@ pub fn add_to_parent(
                 Flex::add_fixed(&mut flex, new_widget);
             }
         });
-        true
+        Ok(())
     } else if let Some(parent_info) = widget_manager.widgets.get(parent_key) {
         match &parent_info.kind {
             WidgetKind::Flex | WidgetKind::Container => {
@@ -40,7 +41,7 @@ pub fn add_to_parent(
                         Flex::add_fixed(&mut flex, new_widget);
                     }
                 });
-                true
+                Ok(())
             }
             WidgetKind::Button => {
                 let parent_wid = parent_info.widget_id;
@@ -54,7 +55,7 @@ pub fn add_to_parent(
                         Flex::add_fixed(&mut flex, new_widget);
                     }
                 });
-                true
+                Ok(())
             }
             WidgetKind::SizedBox => {
                 let parent_wid = parent_info.widget_id;
@@ -62,7 +63,7 @@ pub fn add_to_parent(
                     let mut sbox = parent_widget.downcast::<SizedBox>();
                     SizedBox::set_child(&mut sbox, new_widget);
                 });
-                true
+                Ok(())
             }
             WidgetKind::ZStack => {
                 let parent_wid = parent_info.widget_id;
@@ -74,7 +75,7 @@ pub fn add_to_parent(
                         ChildAlignment::ParentAligned,
                     );
                 });
-                true
+                Ok(())
             }
             WidgetKind::Hoverable => {
                 let parent_wid = parent_info.widget_id;
@@ -89,24 +90,32 @@ pub fn add_to_parent(
                         "[UI] Hoverable '{}' already has a child. Hoverable can only have one child — wrap multiple children in a <flex> or <row>.",
                         parent_key
                     );
-                    return false;
+                    return Err(VellumError::ParentRejectsChild {
+                        widget_id: child_id.to_string(),
+                        parent_id: parent_key.to_string(),
+                        parent_kind: parent_info.kind.style_key().to_string(),
+                    });
                 }
                 render_root.edit_widget(parent_wid, |mut parent_widget| {
                     let mut hoverable = parent_widget.downcast::<Hoverable>();
                     Hoverable::set_child(&mut hoverable, new_widget);
                 });
-                true
+                Ok(())
             }
             other => {
                 eprintln!(
                     "[UI] Cannot add child to widget '{}' of kind {:?} — only Flex/Container/SizedBox/ZStack/Hoverable can have children",
                     parent_key, other
                 );
-                false
+                Err(VellumError::ParentRejectsChild {
+                    widget_id: child_id.to_string(),
+                    parent_id: parent_key.to_string(),
+                    parent_kind: other.style_key().to_string(),
+                })
             }
         }
     } else {
         eprintln!("[UI] Parent widget '{}' not found", parent_key);
-        false
+        Err(VellumError::widget_not_found(parent_key))
     }
 }
@@ -1,7 +1,7 @@
 use masonry::app::RenderRoot;
 use masonry::core::{NewWidget, WidgetOptions};
 
-use crate::ipc::{BoxStyle, WidgetData, WidgetKind};
+use crate::ipc::{BoxStyle, VellumError, WidgetData, WidgetKind};
 use crate::ui::styles::build_box_properties;
 use crate::ui::widget_manager::{WidgetInfo, WidgetManager};
 use crate::ui::widgets::utils::add_to_parent;
@@ -15,13 +15,15 @@ pub fn create(
     style: Option<BoxStyle>,
     data: Option<WidgetData>,
     child_index: usize,
-) {
+) -> Result<(), VellumError> {
     // Extract src from WidgetData
     let src = match &data {
         Some(WidgetData::Video { src }) => src.as_str(),
         _ => {
-            eprintln!("[UI] Video widget '{}' missing src in WidgetData", id);
-            return;
+            return Err(VellumError::InvalidArgument {
+                widget_id: Some(id),
+                reason: "Video widget is missing src".to_string(),
+            });
         }
     };
 
@@ -36,21 +38,22 @@ pub fn create(
     let new_widget = NewWidget::new_with(video_widget, None, WidgetOptions::default(), props);
     let widget_id = new_widget.id();
 
-    if add_to_parent(
+    add_to_parent(
         render_root,
         widget_manager,
+        &id,
         &parent_id,
         new_widget,
         style_ref.and_then(|s| s.flex),
-    ) {
-        widget_manager.register_widget(
-            id,
-            WidgetInfo {
-                widget_id,
-                kind: WidgetKind::Video,
-                parent_id: parent_id.clone(),
-                child_index,
-            },
-        );
-    }
+    )?;
+    widget_manager.register_widget(
+        id,
+        WidgetInfo {
+            widget_id,
+            kind: WidgetKind::Video,
+            parent_id: parent_id.clone(),
+            child_index,
+        },
+    );
+    Ok(())
 }
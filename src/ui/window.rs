@@ -0,0 +1,302 @@
+use std::path::{Path, PathBuf};
+
+use masonry::dpi::{LogicalPosition, LogicalSize};
+use masonry_winit::winit::window::{
+    Fullscreen, Icon, UserAttentionType, Window, WindowAttributes, WindowLevel,
+};
+
+use crate::ipc::{
+    ClientCommand, CommandResult, UiEvent, UiEventSender, VellumError, WindowOptions, WindowState,
+};
+
+/// Stand-in for the missing side of a half-specified maximum size.
+const UNBOUNDED: f64 = 65_535.0;
+
+/// How the window looks when it is first shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Logical inner size
+    pub width: f64,
+    pub height: f64,
+    pub min_size: Option<(f64, f64)>,
+    pub max_size: Option<(f64, f64)>,
+    /// Logical outer position; `None` lets the platform place the window
+    pub position: Option<(f64, f64)>,
+    pub resizable: bool,
+    pub decorations: bool,
+    pub transparent: bool,
+    pub icon: Option<PathBuf>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Vellum - JavaScript Desktop Runtime".to_string(),
+            width: 800.0,
+            height: 600.0,
+            min_size: Some((400.0, 300.0)),
+            max_size: None,
+            position: None,
+            resizable: true,
+            decorations: true,
+            transparent: false,
+            icon: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Override the fields `options` sets. A lone `minWidth` (etc.) keeps the
+    /// other side of the current bound. The default minimum gives way to a
+    /// smaller requested size, but a minimum `options` sets above the size or
+    /// the maximum is an error.
+    pub fn apply(&mut self, options: &WindowOptions) -> Result<(), String> {
+        if let Some(title) = &options.title {
+            self.title = title.clone();
+        }
+        self.width = options.width.unwrap_or(self.width);
+        self.height = options.height.unwrap_or(self.height);
+        if options.min_width.is_some() || options.min_height.is_some() {
+            let (width, height) = self.min_size.unwrap_or((0.0, 0.0));
+            self.min_size = Some((
+                options.min_width.unwrap_or(width),
+                options.min_height.unwrap_or(height),
+            ));
+        }
+        if options.max_width.is_some() || options.max_height.is_some() {
+            let (width, height) = self.max_size.unwrap_or((UNBOUNDED, UNBOUNDED));
+            self.max_size = Some((
+                options.max_width.unwrap_or(width),
+                options.max_height.unwrap_or(height),
+            ));
+        }
+        if options.x.is_some() || options.y.is_some() {
+            let (x, y) = self.position.unwrap_or((0.0, 0.0));
+            self.position = Some((options.x.unwrap_or(x), options.y.unwrap_or(y)));
+        }
+        self.resizable = options.resizable.unwrap_or(self.resizable);
+        self.decorations = options.decorations.unwrap_or(self.decorations);
+        self.transparent = options.transparent.unwrap_or(self.transparent);
+        if let Some(icon) = &options.icon {
+            self.icon = Some(PathBuf::from(icon));
+        }
+
+        if let Some((min_width, min_height)) = self.min_size {
+            let (max_width, max_height) = self.max_size.unwrap_or((UNBOUNDED, UNBOUNDED));
+            let (width, height) = (self.width.min(max_width), self.height.min(max_height));
+            if options.min_width.is_none() && options.min_height.is_none() {
+                self.min_size = Some((min_width.min(width), min_height.min(height)));
+            } else if min_width > width || min_height > height {
+                return Err(format!(
+                    "minimum window size {min_width}x{min_height} is larger than the \
+                     requested size {}x{} or the maximum",
+                    self.width, self.height
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn attributes(&self) -> WindowAttributes {
+        let mut attributes = Window::default_attributes()
+            .with_title(self.title.clone())
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations)
+            .with_transparent(self.transparent)
+            .with_inner_size(LogicalSize::new(self.width, self.height));
+        if let Some((width, height)) = self.min_size {
+            attributes = attributes.with_min_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.max_size {
+            attributes = attributes.with_max_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            attributes = attributes.with_position(LogicalPosition::new(x, y));
+        }
+        if let Some(path) = &self.icon {
+            match load_icon(path) {
+                Ok(icon) => attributes = attributes.with_window_icon(Some(icon)),
+                Err(e) => eprintln!("[UI] Ignoring window icon {}: {e}", path.display()),
+            }
+        }
+        attributes
+    }
+}
+
+/// Decode an image file into a window icon.
+pub fn load_icon(path: &Path) -> Result<Icon, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    icon_from_bytes(&bytes)
+}
+
+/// Decode an encoded image (PNG, ICO, ...) into a window icon.
+pub fn icon_from_bytes(bytes: &[u8]) -> Result<Icon, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| e.to_string())?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(|e| e.to_string())
+}
+
+/// Runs the window control commands against the winit window and tells the
+/// client when the window's state changes.
+#[derive(Debug, Default)]
+pub struct WindowController {
+    /// winit can set the window level but not read it back
+    always_on_top: bool,
+    /// Last state sent in `WindowStateChanged`
+    reported: Option<WindowState>,
+}
+
+impl WindowController {
+    /// Run `command` if it controls the window; `None` leaves it to
+    /// `handle_client_command`.
+    pub fn handle(&mut self, window: &Window, command: &ClientCommand) -> Option<CommandResult> {
+        match command {
+            ClientCommand::SetWindowMinimized(minimized) => window.set_minimized(*minimized),
+            ClientCommand::SetWindowMaximized(maximized) => window.set_maximized(*maximized),
+            ClientCommand::SetWindowFullscreen(fullscreen) => {
+                // Borderless on the monitor the window is on
+                window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+            }
+            ClientCommand::SetWindowPosition { x, y } => {
+                window.set_outer_position(LogicalPosition::new(*x, *y));
+            }
+            ClientCommand::SetWindowMinSize(size) => window
+                .set_min_inner_size(size.map(|(width, height)| LogicalSize::new(width, height))),
+            ClientCommand::SetWindowMaxSize(size) => window
+                .set_max_inner_size(size.map(|(width, height)| LogicalSize::new(width, height))),
+            ClientCommand::SetWindowAlwaysOnTop(always_on_top) => {
+                self.always_on_top = *always_on_top;
+                window.set_window_level(if *always_on_top {
+                    WindowLevel::AlwaysOnTop
+                } else {
+                    WindowLevel::Normal
+                });
+            }
+            ClientCommand::SetWindowDecorations(decorations) => {
+                window.set_decorations(*decorations);
+            }
+            ClientCommand::SetWindowIcon(data) => {
+                let icon = match data.as_deref().map(icon_from_bytes).transpose() {
+                    Ok(icon) => icon,
+                    Err(reason) => {
+                        return Some(Err(VellumError::DecodeFailed {
+                            widget_id: None,
+                            what: "window icon".to_string(),
+                            reason,
+                        }));
+                    }
+                };
+                window.set_window_icon(icon);
+            }
+            ClientCommand::RequestWindowAttention { critical } => {
+                window.request_user_attention(Some(if *critical {
+                    UserAttentionType::Critical
+                } else {
+                    UserAttentionType::Informational
+                }));
+            }
+            ClientCommand::GetWindowState => {
+                return Some(
+                    serde_json::to_value(self.state(window))
+                        .map(Some)
+                        .map_err(|e| {
+                            VellumError::internal(format!("Failed to encode window state: {e}"))
+                        }),
+                );
+            }
+            _ => return None,
+        }
+        Some(Ok(None))
+    }
+
+    /// The window's current state.
+    pub fn state(&self, window: &Window) -> WindowState {
+        let scale = window.scale_factor();
+        let size = window.inner_size().to_logical::<f64>(scale);
+        let position = window
+            .outer_position()
+            .ok()
+            .map(|position| position.to_logical::<f64>(scale));
+        WindowState {
+            minimized: window.is_minimized().unwrap_or(false),
+            maximized: window.is_maximized(),
+            fullscreen: window.fullscreen().is_some(),
+            focused: window.has_focus(),
+            decorations: window.is_decorated(),
+            always_on_top: self.always_on_top,
+            x: position.map(|position| position.x),
+            y: position.map(|position| position.y),
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    /// Report the state again on the next `report_changes`, e.g. to a new client.
+    pub fn forget_reported(&mut self) {
+        self.reported = None;
+    }
+
+    /// Send `WindowStateChanged` if the state differs from the last one sent.
+    pub fn report_changes(&mut self, window: &Window, event_sender: &UiEventSender) {
+        let state = self.state(window);
+        if self.reported.as_ref() == Some(&state) {
+            return;
+        }
+        self.reported = Some(state.clone());
+        if let Err(e) = event_sender.send(UiEvent::WindowStateChanged(state)) {
+            eprintln!("[UI] Failed to send window state to JS thread: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_keeps_unset_fields_and_completes_partial_bounds() {
+        let mut config = WindowConfig::default();
+        config
+            .apply(&WindowOptions {
+                title: Some("Notes".to_string()),
+                min_width: Some(200.0),
+                max_height: Some(900.0),
+                x: Some(40.0),
+                decorations: Some(false),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(config.title, "Notes");
+        assert_eq!((config.width, config.height), (800.0, 600.0));
+        assert_eq!(config.min_size, Some((200.0, 300.0)));
+        assert_eq!(config.max_size, Some((UNBOUNDED, 900.0)));
+        assert_eq!(config.position, Some((40.0, 0.0)));
+        assert!(config.resizable);
+        assert!(!config.decorations);
+    }
+
+    #[test]
+    fn test_apply_rejects_minimum_above_requested_size() {
+        // The default 400x300 minimum gives way to a smaller window
+        let mut config = WindowConfig::default();
+        config
+            .apply(&WindowOptions {
+                width: Some(320.0),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(config.min_size, Some((320.0, 300.0)));
+
+        // An explicit one doesn't
+        let mut config = WindowConfig::default();
+        let error = config.apply(&WindowOptions {
+            width: Some(320.0),
+            min_width: Some(500.0),
+            ..Default::default()
+        });
+        assert!(error.is_err());
+    }
+}